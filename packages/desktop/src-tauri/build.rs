@@ -1,3 +1,5 @@
 fn main() {
-    tauri_build::build()
+    // `pika-cli` alone (no `app` feature) has no Tauri config to process
+    #[cfg(feature = "app")]
+    tauri_build::build();
 }
@@ -0,0 +1,469 @@
+// Native audio analysis
+//
+// Pure-Rust replacement for the core metrics of the Python sidecar
+// (python-src/audio_processing.py): the first minute of the file is decoded
+// with symphonia, mixed to mono at 22050 Hz, and measured in one pass.
+//
+// - Tempo: spectral-flux onset envelope, autocorrelated and scored with a
+//   comb over the first four beat multiples, weighted towards WCS tempos (70-130)
+// - Key: chroma folded from a high-resolution spectrum, matched against the
+//   Krumhansl-Kessler major/minor profiles
+// - Energy and the fingerprint metrics use the sidecar's normalizations, so
+//   values from either engine land on the same 0-100 scales
+
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use serde::Serialize;
+use specta::Type;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::keys::KEY_NAMES;
+
+const SAMPLE_RATE: u32 = 22050;
+/// Seconds analyzed from the start of the file
+const MAX_DURATION: u32 = 60;
+
+/// Onset / spectral feature frames
+const FRAME: usize = 1024;
+const HOP: usize = 256;
+/// RMS frames (librosa defaults)
+const RMS_FRAME: usize = 2048;
+const RMS_HOP: usize = 512;
+/// Chroma frames: long enough to separate semitones down to ~65 Hz
+const CHROMA_FRAME: usize = 8192;
+const CHROMA_HOP: usize = 4096;
+
+/// Tempo search range and the WCS range the prior favours
+const MIN_BPM: f64 = 50.0;
+const MAX_BPM: f64 = 220.0;
+const WCS_CENTER_BPM: f64 = 100.0;
+/// Width of the tempo prior, in octaves
+const TEMPO_PRIOR_WIDTH: f64 = 0.6;
+/// Beat multiples summed when scoring a tempo
+const COMB_HARMONICS: usize = 4;
+
+/// Krumhansl-Kessler key profiles, tonic first
+const MAJOR_PROFILE: [f64; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f64; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+/// Result of audio analysis - same fields as the sidecar's `AnalysisResult`
+/// (and `AnalysisResultSchema` in @pika/shared)
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Type)]
+pub struct AnalysisResult {
+    bpm: Option<f64>,
+    energy: Option<f64>,
+    key: Option<String>,
+    danceability: Option<f64>,
+    brightness: Option<f64>,
+    acousticness: Option<f64>,
+    groove: Option<f64>,
+    error: Option<String>,
+}
+
+impl AnalysisResult {
+    pub fn failed(error: String) -> Self {
+        AnalysisResult {
+            error: Some(error),
+            ..Default::default()
+        }
+    }
+}
+
+fn clamp_percent(value: f64) -> f64 {
+    value.clamp(0.0, 100.0)
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+/// Decode the first `MAX_DURATION` seconds of a file to mono at `SAMPLE_RATE`
+fn decode(path: &Path) -> Result<Vec<f32>, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| format!("Unsupported audio format: {}", e))?;
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| "No audio track found".to_string())?;
+    let track_id = track.id;
+    let source_rate = track.codec_params.sample_rate.ok_or_else(|| "Unknown sample rate".to_string())?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Unsupported codec: {}", e))?;
+
+    let limit = (source_rate * MAX_DURATION) as usize;
+    let mut mono: Vec<f32> = Vec::with_capacity(limit);
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+    while mono.len() < limit {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(format!("Failed to read audio: {}", e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt frame: skip it like any player would
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(format!("Failed to decode audio: {}", e)),
+        };
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        let samples = buffer.get_or_insert_with(|| SampleBuffer::new(decoded.capacity() as u64, spec));
+        if samples.capacity() < decoded.capacity() * channels {
+            *samples = SampleBuffer::new(decoded.capacity() as u64, spec);
+        }
+        samples.copy_interleaved_ref(decoded);
+        mono.extend(
+            samples
+                .samples()
+                .chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+        );
+    }
+    mono.truncate(limit);
+    Ok(resample(&mono, source_rate, SAMPLE_RATE))
+}
+
+/// Resample by averaging the source samples around each output instant
+/// (a box low-pass, enough to keep treble from folding into the analysis band)
+fn resample(input: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || input.is_empty() {
+        return input.to_vec();
+    }
+    let ratio = from as f64 / to as f64;
+    let half_width = (ratio / 2.0).max(0.5);
+    let out_len = (input.len() as f64 / ratio) as usize;
+    (0..out_len)
+        .map(|i| {
+            let center = i as f64 * ratio;
+            let start = (center - half_width).ceil().max(0.0) as usize;
+            let end = ((center + half_width).floor() as usize).min(input.len() - 1);
+            if start > end {
+                return input[center as usize];
+            }
+            input[start..=end].iter().sum::<f32>() / (end - start + 1) as f32
+        })
+        .collect()
+}
+
+/// Magnitude spectra of Hann-windowed frames
+fn spectrogram(y: &[f32], frame: usize, hop: usize) -> Vec<Vec<f32>> {
+    if y.len() < frame {
+        return Vec::new();
+    }
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(frame);
+    let window: Vec<f32> = (0..frame)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / frame as f32).cos())
+        .collect();
+    let mut buffer = vec![Complex::new(0.0, 0.0); frame];
+    (0..=(y.len() - frame) / hop)
+        .map(|index| {
+            let start = index * hop;
+            for (slot, (sample, w)) in buffer.iter_mut().zip(y[start..start + frame].iter().zip(&window)) {
+                *slot = Complex::new(sample * w, 0.0);
+            }
+            fft.process(&mut buffer);
+            buffer[..frame / 2 + 1].iter().map(|c| c.norm()).collect()
+        })
+        .collect()
+}
+
+/// Onset strength per frame: mean positive change in dB across bins (librosa-style)
+fn onset_envelope(spec: &[Vec<f32>]) -> Vec<f64> {
+    let max = spec.iter().flatten().fold(1e-10f32, |m, v| m.max(*v));
+    let ref_db = 20.0 * (max as f64).log10();
+    let db: Vec<Vec<f64>> = spec
+        .iter()
+        .map(|frame| frame.iter().map(|m| (20.0 * (*m as f64).max(1e-5).log10()).max(ref_db - 80.0)).collect())
+        .collect();
+    let mut envelope = vec![0.0];
+    for pair in db.windows(2) {
+        let flux: f64 = pair[1].iter().zip(&pair[0]).map(|(now, before)| (now - before).max(0.0)).sum();
+        envelope.push(flux / pair[1].len() as f64);
+    }
+    envelope
+}
+
+/// Biased autocorrelation of a mean-removed signal, normalized so lag 0 is 1
+fn autocorrelation(signal: &[f64], max_lag: usize) -> Vec<f64> {
+    let mean = signal.iter().sum::<f64>() / signal.len().max(1) as f64;
+    let centered: Vec<f64> = signal.iter().map(|v| v - mean).collect();
+    let max_lag = max_lag.min(centered.len().saturating_sub(1));
+    let mut acf: Vec<f64> = (0..=max_lag)
+        .map(|lag| centered.iter().zip(&centered[lag..]).map(|(a, b)| a * b).sum())
+        .collect();
+    if let Some(&zero) = acf.first().filter(|z| **z > 0.0) {
+        acf.iter_mut().for_each(|v| *v /= zero);
+    }
+    acf
+}
+
+/// Linearly interpolated ACF value at a fractional lag
+fn acf_at(acf: &[f64], lag: f64) -> f64 {
+    let index = lag.floor() as usize;
+    if index + 1 >= acf.len() {
+        return 0.0;
+    }
+    let frac = lag - index as f64;
+    acf[index] * (1.0 - frac) + acf[index + 1] * frac
+}
+
+/// Tempo with the strongest beat-multiple comb, favouring the WCS range
+fn estimate_bpm(acf: &[f64], frames_per_second: f64) -> Option<f64> {
+    let score = |bpm: f64| {
+        let beat = 60.0 * frames_per_second / bpm;
+        let comb: f64 = (1..=COMB_HARMONICS).map(|k| acf_at(acf, beat * k as f64)).sum();
+        let octaves = (bpm / WCS_CENTER_BPM).log2() / TEMPO_PRIOR_WIDTH;
+        comb * (-0.5 * octaves * octaves).exp()
+    };
+
+    let steps = ((MAX_BPM - MIN_BPM) / 0.05) as usize;
+    let (best_bpm, best_score) = (0..=steps)
+        .map(|i| MIN_BPM + i as f64 * 0.05)
+        .map(|bpm| (bpm, score(bpm)))
+        .fold((0.0, f64::MIN), |best, candidate| if candidate.1 > best.1 { candidate } else { best });
+    (best_score > 0.0).then_some(best_bpm)
+}
+
+/// Major/minor key whose profile correlates best with the average chroma
+fn estimate_key(spec: &[Vec<f32>]) -> Option<&'static str> {
+    let bin_hz = SAMPLE_RATE as f64 / CHROMA_FRAME as f64;
+    let mut chroma = [0.0f64; 12];
+    for frame in spec {
+        let mut frame_chroma = [0.0f64; 12];
+        for (bin, magnitude) in frame.iter().enumerate() {
+            let hz = bin as f64 * bin_hz;
+            if !(60.0..=2100.0).contains(&hz) {
+                continue;
+            }
+            let midi = 69.0 + 12.0 * (hz / 440.0).log2();
+            let pitch_class = (midi.round() as i64).rem_euclid(12) as usize;
+            frame_chroma[pitch_class] += (*magnitude as f64).powi(2);
+        }
+        // Each frame votes equally, so a loud break doesn't outweigh the rest of the track
+        let total: f64 = frame_chroma.iter().sum();
+        if total > 0.0 {
+            chroma.iter_mut().zip(frame_chroma).for_each(|(c, f)| *c += f / total);
+        }
+    }
+    if chroma.iter().all(|c| *c == 0.0) {
+        return None;
+    }
+
+    let correlation = |profile: &[f64; 12], tonic: usize| {
+        let rotated: Vec<f64> = (0..12).map(|i| profile[(i + 12 - tonic) % 12]).collect();
+        pearson(&chroma, &rotated)
+    };
+    (0..24)
+        .map(|index| {
+            let profile = if index < 12 { &MAJOR_PROFILE } else { &MINOR_PROFILE };
+            (index, correlation(profile, index % 12))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(index, _)| KEY_NAMES[index])
+}
+
+fn pearson(a: &[f64], b: &[f64]) -> f64 {
+    let mean_a = a.iter().sum::<f64>() / a.len() as f64;
+    let mean_b = b.iter().sum::<f64>() / b.len() as f64;
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        cov += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a).powi(2);
+        var_b += (y - mean_b).powi(2);
+    }
+    if var_a == 0.0 || var_b == 0.0 {
+        return 0.0;
+    }
+    cov / (var_a * var_b).sqrt()
+}
+
+/// Mean frame RMS, scaled like the sidecar (typical music RMS 0.01-0.2)
+fn energy(y: &[f32]) -> f64 {
+    let frames: Vec<f64> = y
+        .windows(RMS_FRAME)
+        .step_by(RMS_HOP)
+        .map(|frame| (frame.iter().map(|s| (*s as f64).powi(2)).sum::<f64>() / RMS_FRAME as f64).sqrt())
+        .collect();
+    if frames.is_empty() {
+        return 0.0;
+    }
+    clamp_percent(frames.iter().sum::<f64>() / frames.len() as f64 * 500.0)
+}
+
+/// Spectral centroid (brightness) and flatness (inverted: acousticness), both 0-100
+fn spectral_shape(spec: &[Vec<f32>]) -> (f64, f64) {
+    let bin_hz = SAMPLE_RATE as f64 / FRAME as f64;
+    let (mut centroid_sum, mut flatness_sum) = (0.0, 0.0);
+    for frame in spec {
+        let power: Vec<f64> = frame.iter().map(|m| (*m as f64).powi(2).max(1e-10)).collect();
+        let magnitude_total: f64 = frame.iter().map(|m| *m as f64).sum();
+        if magnitude_total > 0.0 {
+            centroid_sum += frame.iter().enumerate().map(|(i, m)| i as f64 * bin_hz * *m as f64).sum::<f64>()
+                / magnitude_total;
+        }
+        let log_mean = power.iter().map(|p| p.ln()).sum::<f64>() / power.len() as f64;
+        let mean = power.iter().sum::<f64>() / power.len() as f64;
+        flatness_sum += log_mean.exp() / mean;
+    }
+    let frames = spec.len().max(1) as f64;
+    (
+        clamp_percent(centroid_sum / frames / 4000.0 * 100.0),
+        clamp_percent((1.0 - flatness_sum / frames) * 100.0),
+    )
+}
+
+/// Analyze mono samples at `SAMPLE_RATE`
+pub(crate) fn analyze_samples(y: &[f32]) -> AnalysisResult {
+    if y.iter().fold(0.0f32, |m, s| m.max(s.abs())) < 0.01 {
+        return AnalysisResult::failed("Audio file appears to be silent".to_string());
+    }
+
+    let frames_per_second = SAMPLE_RATE as f64 / HOP as f64;
+    let spec = spectrogram(y, FRAME, HOP);
+    let onsets = onset_envelope(&spec);
+    let max_lag = (60.0 * frames_per_second / MIN_BPM) as usize * COMB_HARMONICS + 1;
+    let acf = autocorrelation(&onsets, max_lag);
+
+    let bpm = estimate_bpm(&acf, frames_per_second);
+    let key = estimate_key(&spectrogram(y, CHROMA_FRAME, CHROMA_HOP));
+    let (brightness, acousticness) = spectral_shape(&spec);
+
+    // Pulse clarity: the strongest periodicity against the average one (sidecar: tempogram peak ratio)
+    let min_lag = (60.0 * frames_per_second / MAX_BPM) as usize;
+    let periodicity: Vec<f64> = acf.iter().skip(min_lag).map(|v| v.max(0.0)).collect();
+    let mean_periodicity = periodicity.iter().sum::<f64>() / periodicity.len().max(1) as f64;
+    let danceability = if mean_periodicity > 0.0 {
+        clamp_percent(periodicity.iter().fold(0.0f64, |m, v| m.max(*v)) / mean_periodicity * 15.0)
+    } else {
+        50.0
+    };
+    let groove = clamp_percent(onsets.iter().sum::<f64>() / onsets.len().max(1) as f64 * 25.0);
+
+    AnalysisResult {
+        bpm: bpm.map(round1),
+        energy: Some(round1(energy(y))),
+        key: key.map(str::to_string),
+        danceability: Some(round1(danceability)),
+        brightness: Some(round1(brightness)),
+        acousticness: Some(round1(acousticness)),
+        groove: Some(round1(groove)),
+        error: None,
+    }
+}
+
+/// Analyze an audio file. Failures are reported in `error`, like the sidecar does.
+pub fn analyze_file(path: &Path) -> AnalysisResult {
+    if !path.is_file() {
+        return AnalysisResult::failed(format!("File not found: {}", path.display()));
+    }
+    match decode(path) {
+        Ok(samples) => analyze_samples(&samples),
+        Err(e) => AnalysisResult::failed(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Short decaying noise-burst clicks at `bpm`
+    fn click_track(bpm: f64, seconds: f64, rate: u32) -> Vec<f32> {
+        let len = (seconds * rate as f64) as usize;
+        let mut y = vec![0.0f32; len];
+        let beat = 60.0 / bpm * rate as f64;
+        let click_len = rate as usize / 50;
+        let mut noise: u32 = 12345;
+        let mut t = 0.0;
+        while (t as usize) < len {
+            let start = t as usize;
+            for i in 0..click_len.min(len - start) {
+                noise = noise.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                let white = (noise >> 16) as f32 / 32768.0 - 1.0;
+                y[start + i] = 0.8 * white * (-(i as f32) / (click_len as f32 / 5.0)).exp();
+            }
+            t += beat;
+        }
+        y
+    }
+
+    /// Sustained chord of sine tones with a few harmonics
+    fn chord(frequencies: &[f64], seconds: f64) -> Vec<f32> {
+        let len = (seconds * SAMPLE_RATE as f64) as usize;
+        (0..len)
+            .map(|i| {
+                let t = i as f64 / SAMPLE_RATE as f64;
+                let sample: f64 = frequencies
+                    .iter()
+                    .flat_map(|f| (1..=3).map(move |h| (2.0 * std::f64::consts::PI * f * h as f64 * t).sin() / h as f64))
+                    .sum();
+                (sample * 0.15) as f32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_click_track_tempo() {
+        for bpm in [72.0, 92.0, 100.0, 118.5, 128.0] {
+            let result = analyze_samples(&click_track(bpm, 30.0, SAMPLE_RATE));
+            let found = result.bpm.unwrap();
+            assert!((found - bpm).abs() <= 0.5, "expected {} BPM, got {}", bpm, found);
+        }
+    }
+
+    #[test]
+    fn test_tone_key_and_silence() {
+        // A minor (A2 bass, A3 C4 E4) and C major (C3 bass, C4 E4 G4)
+        let result = analyze_samples(&chord(&[110.0, 220.0, 261.63, 329.63], 10.0));
+        assert_eq!(result.key.as_deref(), Some("Am"));
+        let result = analyze_samples(&chord(&[130.81, 261.63, 329.63, 392.0], 10.0));
+        assert_eq!(result.key.as_deref(), Some("C"));
+        assert!(result.energy.unwrap() > 0.0);
+
+        let silent = analyze_samples(&vec![0.0; SAMPLE_RATE as usize * 5]);
+        assert_eq!(silent.error.as_deref(), Some("Audio file appears to be silent"));
+        assert_eq!(silent.bpm, None);
+    }
+
+    #[test]
+    fn test_analyze_wav_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("clicks.wav");
+
+        // 44.1 kHz stereo 16-bit, so decoding, downmixing and resampling are all exercised
+        let rate = 44100u32;
+        let samples: Vec<i16> = click_track(96.0, 20.0, rate)
+            .iter()
+            .flat_map(|s| [(s * 32767.0) as i16; 2])
+            .collect();
+        std::fs::write(&path, crate::test_support::pcm_wav(2, rate, &samples)).unwrap();
+
+        let result = analyze_file(&path);
+        assert_eq!(result.error, None);
+        assert!((result.bpm.unwrap() - 96.0).abs() <= 0.5, "got {:?}", result.bpm);
+
+        assert!(analyze_file(&dir.join("missing.mp3")).error.unwrap().starts_with("File not found"));
+    }
+}
@@ -0,0 +1,577 @@
+// Analysis job queue
+//
+// Tracks used to be queued for analysis in webview memory, which lost the queue
+// on every reload and stalled whenever the window was throttled. The queue lives
+// here now: jobs are ordered by priority (now playing, then next up, then the
+// background library), dispatched to the sidecar's /analyze endpoint with a
+// concurrency limit taken from the CPU priority setting, and written to
+// `analysis-queue.json` on every change so pending work survives a restart.
+// Every state change is pushed to the webview as an `analysis://progress`
+// event; finished jobs (with the sidecar's result) as `analysis://complete`.
+// Finished results stay in the file until the webview acknowledges them, so a
+// result that lands while the window is reloading (or just before a crash) is
+// picked up again on the next start.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+
+/// Emitted when a job is queued, starts, is retried or is cancelled
+pub const ANALYSIS_PROGRESS_EVENT: &str = "analysis://progress";
+/// Emitted when a job finishes, successfully or not
+pub const ANALYSIS_COMPLETE_EVENT: &str = "analysis://complete";
+
+/// Analysing a full track can take a while on slow machines
+const ANALYZE_TIMEOUT: Duration = Duration::from_secs(180);
+/// Connection failures before a job is given up on
+const MAX_ATTEMPTS: u32 = 3;
+/// How often the dispatcher re-checks the sidecar when nothing wakes it
+const IDLE_POLL: Duration = Duration::from_secs(1);
+/// Unacknowledged results kept; the oldest are dropped beyond this
+const MAX_UNACKED_RESULTS: usize = 1000;
+
+/// Declaration order is dispatch order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPriority {
+    NowPlaying,
+    NextUp,
+    Background,
+}
+
+/// Mirrors the `analysis.cpuPriority` setting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum CpuPriority {
+    #[default]
+    Low,
+    Normal,
+    High,
+}
+
+impl CpuPriority {
+    /// Jobs sent to the sidecar at once
+    fn concurrency(self) -> usize {
+        match self {
+            CpuPriority::Low | CpuPriority::Normal => 1,
+            CpuPriority::High => std::thread::available_parallelism().map(|n| (n.get() / 2).clamp(1, 4)).unwrap_or(2),
+        }
+    }
+
+    /// Pause after each job before its slot is reused
+    fn cooldown(self) -> Duration {
+        match self {
+            CpuPriority::Low => Duration::from_millis(3000),
+            CpuPriority::Normal => Duration::from_millis(2000),
+            CpuPriority::High => Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct AnalysisJob {
+    id: u64,
+    track_id: i64,
+    file_path: String,
+    priority: JobPriority,
+    /// Failed connection attempts so far
+    #[serde(default)]
+    attempts: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    /// Sidecar unreachable; back in the queue
+    Retrying,
+    Cancelled,
+    /// The sidecar answered; the result may still carry an analysis error
+    Completed,
+    /// Gave up after MAX_ATTEMPTS or the sidecar answered with an HTTP error
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct JobProgress {
+    job: AnalysisJob,
+    state: JobState,
+    pending: usize,
+    running: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct JobCompletion {
+    job: AnalysisJob,
+    state: JobState,
+    /// The sidecar's JSON response, passed through as-is
+    #[specta(type = Option<crate::analysis::AnalysisResult>)]
+    result: Option<serde_json::Value>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct QueueSnapshot {
+    paused: bool,
+    cpu_priority: CpuPriority,
+    concurrency: usize,
+    running: Vec<AnalysisJob>,
+    pending: Vec<AnalysisJob>,
+}
+
+/// What is written to disk
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PersistedQueue {
+    next_id: u64,
+    paused: bool,
+    jobs: Vec<AnalysisJob>,
+    /// Finished jobs the webview hasn't acknowledged yet
+    #[serde(default)]
+    results: Vec<JobCompletion>,
+}
+
+struct RunningJob {
+    job: AnalysisJob,
+    abort: tokio::task::AbortHandle,
+}
+
+#[derive(Default)]
+struct Queue {
+    next_id: u64,
+    paused: bool,
+    cpu_priority: CpuPriority,
+    pending: Vec<AnalysisJob>,
+    running: Vec<RunningJob>,
+    /// Slots held by finished jobs during their cooldown
+    cooling: usize,
+    /// Finished jobs waiting for `ack_analysis_results`, oldest first
+    results: Vec<JobCompletion>,
+}
+
+impl Queue {
+    /// Queue a track, or raise the priority of its existing job. Returns the job
+    /// id and whether it is new.
+    fn enqueue(&mut self, track_id: i64, file_path: String, priority: JobPriority) -> (AnalysisJob, bool) {
+        if let Some(running) = self.running.iter().find(|r| r.job.track_id == track_id) {
+            return (running.job.clone(), false);
+        }
+        if let Some(job) = self.pending.iter_mut().find(|j| j.track_id == track_id) {
+            job.priority = job.priority.min(priority);
+            return (job.clone(), false);
+        }
+        self.next_id += 1;
+        let job = AnalysisJob { id: self.next_id, track_id, file_path, priority, attempts: 0 };
+        self.pending.push(job.clone());
+        (job, true)
+    }
+
+    /// Highest priority first, oldest first within a priority
+    fn take_next(&mut self) -> Option<AnalysisJob> {
+        if self.paused || self.running.len() + self.cooling >= self.cpu_priority.concurrency() {
+            return None;
+        }
+        let index = self
+            .pending
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, job)| (job.priority, job.id))
+            .map(|(index, _)| index)?;
+        Some(self.pending.remove(index))
+    }
+
+    /// Remove a pending job or abort a running one
+    fn cancel(&mut self, job_id: u64) -> Option<AnalysisJob> {
+        if let Some(index) = self.pending.iter().position(|j| j.id == job_id) {
+            return Some(self.pending.remove(index));
+        }
+        let index = self.running.iter().position(|r| r.job.id == job_id)?;
+        let running = self.running.remove(index);
+        running.abort.abort();
+        Some(running.job)
+    }
+
+    fn finish(&mut self, job_id: u64) -> bool {
+        let before = self.running.len();
+        self.running.retain(|r| r.job.id != job_id);
+        self.running.len() != before
+    }
+
+    /// Keep a result until it is acknowledged
+    fn store_result(&mut self, completion: JobCompletion) {
+        self.results.push(completion);
+        if self.results.len() > MAX_UNACKED_RESULTS {
+            let dropped = self.results.len() - MAX_UNACKED_RESULTS;
+            eprintln!("[AnalysisQueue] Dropping {} unacknowledged results", dropped);
+            self.results.drain(..dropped);
+        }
+    }
+
+    /// Forget acknowledged results; returns how many were removed
+    fn ack(&mut self, job_ids: &[u64]) -> usize {
+        let before = self.results.len();
+        self.results.retain(|r| !job_ids.contains(&r.job.id));
+        before - self.results.len()
+    }
+
+    fn snapshot(&self) -> QueueSnapshot {
+        let mut pending = self.pending.clone();
+        pending.sort_by_key(|job| (job.priority, job.id));
+        QueueSnapshot {
+            paused: self.paused,
+            cpu_priority: self.cpu_priority,
+            concurrency: self.cpu_priority.concurrency(),
+            running: self.running.iter().map(|r| r.job.clone()).collect(),
+            pending,
+        }
+    }
+
+    fn progress(&self, job: &AnalysisJob, state: JobState) -> JobProgress {
+        JobProgress { job: job.clone(), state, pending: self.pending.len(), running: self.running.len() }
+    }
+
+    /// Running jobs are saved as pending: they never finished
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let mut jobs: Vec<AnalysisJob> = self.running.iter().map(|r| r.job.clone()).collect();
+        jobs.extend(self.pending.iter().cloned());
+        let persisted = PersistedQueue { next_id: self.next_id, paused: self.paused, jobs, results: self.results.clone() };
+        let json = serde_json::to_vec_pretty(&persisted).map_err(|e| format!("Failed to serialize queue: {}", e))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, json).map_err(|e| format!("Failed to write {}: {}", temp.display(), e))?;
+        std::fs::rename(&temp, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+    }
+
+    fn load(path: &Path) -> Result<Queue, String> {
+        if !path.exists() {
+            return Ok(Queue::default());
+        }
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let persisted: PersistedQueue =
+            serde_json::from_slice(&bytes).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        let next_id = persisted
+            .jobs
+            .iter()
+            .chain(persisted.results.iter().map(|r| &r.job))
+            .map(|j| j.id)
+            .max()
+            .unwrap_or(0)
+            .max(persisted.next_id);
+        Ok(Queue {
+            next_id,
+            paused: persisted.paused,
+            pending: persisted.jobs,
+            results: persisted.results,
+            ..Queue::default()
+        })
+    }
+}
+
+static QUEUE: Lazy<Mutex<Queue>> = Lazy::new(|| Mutex::new(Queue::default()));
+
+/// Where the queue is persisted; set by `start`
+static QUEUE_PATH: once_cell::sync::OnceCell<PathBuf> = once_cell::sync::OnceCell::new();
+
+/// Wakes the dispatcher when a slot frees up or work arrives
+static WAKE: Lazy<Notify> = Lazy::new(Notify::new);
+
+fn lock() -> std::sync::MutexGuard<'static, Queue> {
+    QUEUE.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+fn persist(queue: &Queue) {
+    if let Some(path) = QUEUE_PATH.get() {
+        if let Err(e) = queue.save(path) {
+            eprintln!("[AnalysisQueue] {}", e);
+        }
+    }
+}
+
+fn emit<S: Serialize + Clone>(app: &AppHandle, event: &str, payload: S) {
+    if let Err(e) = app.emit(event, payload) {
+        eprintln!("[AnalysisQueue] Failed to emit {}: {}", event, e);
+    }
+}
+
+/// Load the persisted queue and start dispatching
+pub fn start(app: AppHandle, path: PathBuf) {
+    if QUEUE_PATH.set(path.clone()).is_err() {
+        return;
+    }
+    match Queue::load(&path) {
+        Ok(loaded) => {
+            if !loaded.pending.is_empty() || !loaded.results.is_empty() {
+                println!(
+                    "[AnalysisQueue] Restored {} pending jobs and {} unacknowledged results",
+                    loaded.pending.len(),
+                    loaded.results.len()
+                );
+            }
+            let mut queue = lock();
+            let cpu_priority = queue.cpu_priority;
+            *queue = Queue { cpu_priority, ..loaded };
+        }
+        Err(e) => eprintln!("[AnalysisQueue] Starting with an empty queue: {}", e),
+    }
+    tauri::async_runtime::spawn(dispatch(app));
+}
+
+pub fn enqueue(app: &AppHandle, track_id: i64, file_path: String, priority: JobPriority) -> AnalysisJob {
+    let (job, progress) = {
+        let mut queue = lock();
+        let (job, added) = queue.enqueue(track_id, file_path, priority);
+        persist(&queue);
+        (job.clone(), added.then(|| queue.progress(&job, JobState::Queued)))
+    };
+    if let Some(progress) = progress {
+        println!("[AnalysisQueue] Queued {} ({:?})", job.file_path, job.priority);
+        emit(app, ANALYSIS_PROGRESS_EVENT, progress);
+    }
+    WAKE.notify_one();
+    job
+}
+
+/// Cancel one job. Returns false if it already finished.
+pub fn cancel(app: &AppHandle, job_id: u64) -> bool {
+    let progress = {
+        let mut queue = lock();
+        let Some(job) = queue.cancel(job_id) else {
+            return false;
+        };
+        persist(&queue);
+        queue.progress(&job, JobState::Cancelled)
+    };
+    emit(app, ANALYSIS_PROGRESS_EVENT, progress);
+    WAKE.notify_one();
+    true
+}
+
+/// Cancel every pending and running job
+pub fn clear(app: &AppHandle) {
+    let cancelled: Vec<JobProgress> = {
+        let mut queue = lock();
+        let ids: Vec<u64> = queue.running.iter().map(|r| r.job.id).chain(queue.pending.iter().map(|j| j.id)).collect();
+        let jobs: Vec<AnalysisJob> = ids.into_iter().filter_map(|id| queue.cancel(id)).collect();
+        persist(&queue);
+        jobs.iter().map(|job| queue.progress(job, JobState::Cancelled)).collect()
+    };
+    for progress in cancelled {
+        emit(app, ANALYSIS_PROGRESS_EVENT, progress);
+    }
+}
+
+/// Paused queues let running jobs finish but start no new ones
+pub fn set_paused(paused: bool) -> QueueSnapshot {
+    let snapshot = {
+        let mut queue = lock();
+        queue.paused = paused;
+        persist(&queue);
+        queue.snapshot()
+    };
+    WAKE.notify_one();
+    snapshot
+}
+
+pub fn set_cpu_priority(priority: CpuPriority) -> QueueSnapshot {
+    let snapshot = {
+        let mut queue = lock();
+        queue.cpu_priority = priority;
+        queue.snapshot()
+    };
+    WAKE.notify_one();
+    snapshot
+}
+
+pub fn snapshot() -> QueueSnapshot {
+    lock().snapshot()
+}
+
+/// Finished jobs not yet acknowledged, oldest first
+pub fn results() -> Vec<JobCompletion> {
+    lock().results.clone()
+}
+
+/// Drop results the webview has stored. Returns how many were removed.
+pub fn ack(job_ids: &[u64]) -> usize {
+    let mut queue = lock();
+    let removed = queue.ack(job_ids);
+    if removed > 0 {
+        persist(&queue);
+    }
+    removed
+}
+
+async fn dispatch(app: AppHandle) {
+    loop {
+        let next = if crate::sidecar::is_ready() { lock().take_next() } else { None };
+        let Some(job) = next else {
+            let _ = tokio::time::timeout(IDLE_POLL, WAKE.notified()).await;
+            continue;
+        };
+
+        // Register as running before the task can finish
+        let mut queue = lock();
+        let task = tauri::async_runtime::spawn(run_job(app.clone(), job.clone()));
+        queue.running.push(RunningJob { job: job.clone(), abort: task.inner().abort_handle() });
+        let progress = queue.progress(&job, JobState::Running);
+        drop(queue);
+        println!("[AnalysisQueue] Analyzing {}", job.file_path);
+        emit(&app, ANALYSIS_PROGRESS_EVENT, progress);
+    }
+}
+
+/// Percent-encode a query string value
+fn encode_query(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+async fn run_job(app: AppHandle, job: AnalysisJob) {
+    let path = format!("/analyze?path={}", encode_query(&job.file_path));
+    let outcome = crate::sidecar::get(&path, ANALYZE_TIMEOUT).await;
+    let Some((completion, cooldown)) = settle(&app, job, outcome) else {
+        return;
+    };
+
+    match &completion.error {
+        Some(e) => eprintln!("[AnalysisQueue] {}: {}", completion.job.file_path, e),
+        None => println!("[AnalysisQueue] Completed {}", completion.job.file_path),
+    }
+    emit(&app, ANALYSIS_COMPLETE_EVENT, completion);
+
+    tokio::time::sleep(cooldown).await;
+    lock().cooling -= 1;
+    WAKE.notify_one();
+}
+
+/// Take a finished request off the running list. Returns the completion and the
+/// cooldown to hold its slot for, or None if the job was cancelled or requeued.
+fn settle(app: &AppHandle, mut job: AnalysisJob, outcome: Result<String, String>) -> Option<(JobCompletion, Duration)> {
+    let mut queue = lock();
+    let cooldown = queue.cpu_priority.cooldown();
+    if !queue.finish(job.id) {
+        // Cancelled while the request was in flight
+        return None;
+    }
+    let completion = match outcome {
+        Ok(body) => match serde_json::from_str::<serde_json::Value>(&body) {
+            Ok(result) => {
+                let error = result.get("error").and_then(|e| e.as_str()).map(str::to_string);
+                JobCompletion { job: job.clone(), state: JobState::Completed, result: Some(result), error }
+            }
+            Err(e) => JobCompletion {
+                job: job.clone(),
+                state: JobState::Failed,
+                result: None,
+                error: Some(format!("Invalid analysis response: {}", e)),
+            },
+        },
+        Err(e) if e.starts_with("Unexpected response") => {
+            JobCompletion { job: job.clone(), state: JobState::Failed, result: None, error: Some(e) }
+        }
+        // The sidecar went away mid-request: put the job back unless it keeps failing
+        Err(e) => {
+            job.attempts += 1;
+            if job.attempts < MAX_ATTEMPTS {
+                eprintln!("[AnalysisQueue] {} (attempt {}/{}): {}", job.file_path, job.attempts, MAX_ATTEMPTS, e);
+                queue.pending.push(job.clone());
+                persist(&queue);
+                let progress = queue.progress(&job, JobState::Retrying);
+                drop(queue);
+                emit(app, ANALYSIS_PROGRESS_EVENT, progress);
+                WAKE.notify_one();
+                return None;
+            }
+            JobCompletion { job: job.clone(), state: JobState::Failed, result: None, error: Some(e) }
+        }
+    };
+    queue.store_result(completion.clone());
+    persist(&queue);
+    queue.cooling += 1;
+    Some((completion, cooldown))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priority_order_and_dedupe() {
+        let mut queue = Queue { cpu_priority: CpuPriority::Low, ..Queue::default() };
+        let (library, _) = queue.enqueue(1, "/music/a.mp3".to_string(), JobPriority::Background);
+        let (next_up, _) = queue.enqueue(2, "/music/b.mp3".to_string(), JobPriority::NextUp);
+        queue.enqueue(3, "/music/c.mp3".to_string(), JobPriority::Background);
+
+        // Re-queueing a track raises its priority instead of adding a second job
+        let (now_playing, added) = queue.enqueue(3, "/music/c.mp3".to_string(), JobPriority::NowPlaying);
+        assert!(!added);
+        assert_eq!(queue.pending.len(), 3);
+        let (_, added) = queue.enqueue(2, "/music/b.mp3".to_string(), JobPriority::Background);
+        assert!(!added);
+
+        let order: Vec<u64> = queue.snapshot().pending.iter().map(|j| j.id).collect();
+        assert_eq!(order, vec![now_playing.id, next_up.id, library.id]);
+
+        queue.paused = true;
+        assert!(queue.take_next().is_none());
+        queue.paused = false;
+        assert_eq!(queue.take_next().map(|j| j.track_id), Some(3));
+
+        assert_eq!(queue.cancel(next_up.id).map(|j| j.track_id), Some(2));
+        assert!(queue.cancel(next_up.id).is_none());
+        assert_eq!(queue.pending.len(), 1);
+    }
+
+    #[test]
+    fn test_pending_jobs_survive_restart() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("analysis-queue.json");
+
+        let mut queue = Queue::default();
+        queue.enqueue(7, "/music/x y.flac".to_string(), JobPriority::Background);
+        queue.enqueue(8, "/music/z.mp3".to_string(), JobPriority::NowPlaying);
+        queue.paused = true;
+        queue.save(&path).unwrap();
+
+        let restored = Queue::load(&path).unwrap();
+        assert!(restored.paused);
+        assert_eq!(restored.pending, queue.pending);
+        let mut restored = restored;
+        restored.paused = false;
+        assert_eq!(restored.take_next().map(|j| j.track_id), Some(8));
+        let (job, _) = restored.enqueue(9, "/music/new.mp3".to_string(), JobPriority::Background);
+        assert_eq!(job.id, 3);
+
+        // Results survive a restart until they are acknowledged
+        let completion = JobCompletion {
+            job: job.clone(),
+            state: JobState::Completed,
+            result: Some(serde_json::json!({ "bpm": 120.0 })),
+            error: None,
+        };
+        restored.store_result(completion.clone());
+        restored.save(&path).unwrap();
+        let mut reloaded = Queue::load(&path).unwrap();
+        assert_eq!(reloaded.results, vec![completion]);
+        assert_eq!(reloaded.next_id, 3);
+        assert_eq!(reloaded.ack(&[job.id, 42]), 1);
+        reloaded.save(&path).unwrap();
+        assert!(Queue::load(&path).unwrap().results.is_empty());
+
+        assert!(Queue::load(&dir.join("missing.json")).unwrap().pending.is_empty());
+        assert_eq!(encode_query("/music/x y&é.flac"), "%2Fmusic%2Fx%20y%26%C3%A9.flac");
+    }
+}
@@ -0,0 +1,296 @@
+// Embedded audio tag reader
+//
+// Fallback for tracks that no DJ database knows about (ghost tracks, files
+// played straight from a folder). ID3v2 frames - in MP3s and in the ID3 chunk
+// DJ software writes into WAV/AIFF - are read with `id3`; FLAC/OGG Vorbis
+// comments, MP4 atoms, RIFF INFO and every duration come from symphonia's
+// probe. Whichever source provides a field first wins.
+
+use id3::TagLike;
+use serde::Serialize;
+use specta::Type;
+use std::path::Path;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, StandardTagKey, Tag, Value};
+use symphonia::core::probe::Hint;
+
+use crate::error::PikaError;
+
+/// Tags read from an audio file. Same shape as `VdjTrackMetadata` (bpm, key,
+/// volume) plus the descriptive fields a DJ database would otherwise provide.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Type)]
+pub struct AudioTagMetadata {
+    bpm: Option<f64>,
+    /// Initial key exactly as tagged (e.g. "Am", "8A", "1m")
+    key: Option<String>,
+    /// Linear gain from the ReplayGain track gain, comparable to VDJ's volume
+    volume: Option<f64>,
+    artist: Option<String>,
+    title: Option<String>,
+    album: Option<String>,
+    genre: Option<String>,
+    year: Option<i32>,
+    /// Duration in seconds
+    duration: Option<f64>,
+    comment: Option<String>,
+    /// Container the tags were read from (mp3, flac, m4a, ogg, wav, aiff)
+    format: String,
+}
+
+/// Which field a tag maps to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Artist,
+    Title,
+    Album,
+    Genre,
+    Year,
+    Bpm,
+    Key,
+    Comment,
+    ReplayGain,
+}
+
+impl AudioTagMetadata {
+    /// Set `field` from a raw tag value unless an earlier source already did
+    fn fill(&mut self, field: Field, raw: &str) {
+        let text = raw.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+        if text.is_empty() {
+            return;
+        }
+        let string_slot = match field {
+            Field::Artist => &mut self.artist,
+            Field::Title => &mut self.title,
+            Field::Album => &mut self.album,
+            Field::Genre => &mut self.genre,
+            Field::Key => &mut self.key,
+            Field::Comment => &mut self.comment,
+            Field::Year => {
+                if self.year.is_none() {
+                    self.year = text.get(..4).and_then(|y| y.parse().ok());
+                }
+                return;
+            }
+            Field::Bpm => {
+                if self.bpm.is_none() {
+                    self.bpm = parse_bpm(text);
+                }
+                return;
+            }
+            Field::ReplayGain => {
+                if self.volume.is_none() {
+                    self.volume = parse_replay_gain(text);
+                }
+                return;
+            }
+        };
+        if string_slot.is_none() {
+            *string_slot = Some(text.to_string());
+        }
+    }
+}
+
+/// TBPM is free text: "128", "128.00", "127,5"
+fn parse_bpm(text: &str) -> Option<f64> {
+    text.replace(',', ".")
+        .parse::<f64>()
+        .ok()
+        .filter(|bpm| bpm.is_finite() && *bpm > 0.0 && *bpm < 1000.0)
+}
+
+/// "-6.52 dB" -> linear gain
+fn parse_replay_gain(text: &str) -> Option<f64> {
+    let db = text.trim_end_matches(|c: char| c.is_alphabetic() || c.is_whitespace());
+    db.trim().parse::<f64>().ok().map(|db| 10f64.powf(db / 20.0))
+}
+
+/// Map a symphonia tag to a field. Initial key has no standard key, so it is
+/// matched on the format's own name (TKEY, INITIALKEY, iTunes "initialkey").
+fn symphonia_field(tag: &Tag) -> Option<Field> {
+    match tag.std_key {
+        Some(StandardTagKey::Artist) => Some(Field::Artist),
+        Some(StandardTagKey::TrackTitle) => Some(Field::Title),
+        Some(StandardTagKey::Album) => Some(Field::Album),
+        Some(StandardTagKey::Genre) => Some(Field::Genre),
+        Some(StandardTagKey::Date) | Some(StandardTagKey::ReleaseDate) => Some(Field::Year),
+        Some(StandardTagKey::Bpm) => Some(Field::Bpm),
+        Some(StandardTagKey::Comment) => Some(Field::Comment),
+        Some(StandardTagKey::ReplayGainTrackGain) => Some(Field::ReplayGain),
+        _ => {
+            let key = tag.key.to_ascii_lowercase();
+            let key = key.rsplit(':').next().unwrap_or(&key);
+            matches!(key, "tkey" | "initialkey" | "initial key" | "key").then_some(Field::Key)
+        }
+    }
+}
+
+fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Float(f) => Some(f.to_string()),
+        Value::SignedInt(i) => Some(i.to_string()),
+        Value::UnsignedInt(u) => Some(u.to_string()),
+        Value::Binary(_) | Value::Boolean(_) | Value::Flag => None,
+    }
+}
+
+/// Read ID3v2 frames from an MP3, or from the ID3 chunk of a WAV/AIFF
+fn read_id3(path: &Path, meta: &mut AudioTagMetadata) {
+    // Detects MP3 vs. RIFF/AIFF containers by itself
+    let tag = match id3::Tag::read_from_path(path) {
+        Ok(tag) => tag,
+        Err(e) if matches!(e.kind, id3::ErrorKind::NoTag) => return,
+        Err(e) => {
+            eprintln!("[Tags] Failed to read ID3 from {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    let text = |id: &str| tag.get(id).and_then(|frame| frame.content().text()).map(str::to_string);
+    for (field, value) in [
+        (Field::Artist, tag.artist().map(str::to_string)),
+        (Field::Title, tag.title().map(str::to_string)),
+        (Field::Album, tag.album().map(str::to_string)),
+        (Field::Genre, tag.genre_parsed().map(|g| g.into_owned())),
+        (Field::Year, tag.year().map(|y| y.to_string()).or_else(|| text("TDRC"))),
+        (Field::Bpm, text("TBPM")),
+        (Field::Key, text("TKEY")),
+        (Field::Comment, tag.comments().next().map(|c| c.text.clone())),
+    ] {
+        if let Some(value) = value {
+            meta.fill(field, &value);
+        }
+    }
+    for extended in tag.extended_texts() {
+        match extended.description.to_ascii_uppercase().as_str() {
+            "REPLAYGAIN_TRACK_GAIN" => meta.fill(Field::ReplayGain, &extended.value),
+            "INITIALKEY" | "KEY" => meta.fill(Field::Key, &extended.value),
+            _ => {}
+        }
+    }
+}
+
+/// Probe the file with symphonia for container tags and duration
+fn read_container(path: &Path, extension: &str, meta: &mut AudioTagMetadata) -> Result<(), PikaError> {
+    let file = std::fs::File::open(path).map_err(|e| PikaError::io("Failed to open audio file", path, e))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    hint.with_extension(extension);
+
+    let mut probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| PikaError::invalid_format(format!("Unsupported or corrupt audio file: {}", e), Some(path)))?;
+
+    // Tags found ahead of the stream (ID3 in front of FLAC/MP3) and tags inside the container
+    let mut tags: Vec<Tag> = Vec::new();
+    if let Some(revision) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+        tags.extend(revision.tags().iter().cloned());
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        tags.extend(revision.tags().iter().cloned());
+    }
+    for tag in &tags {
+        if let (Some(field), Some(text)) = (symphonia_field(tag), value_text(&tag.value)) {
+            meta.fill(field, &text);
+        }
+    }
+
+    if let Some(track) = probed.format.default_track() {
+        let params = &track.codec_params;
+        meta.duration = match (params.n_frames, params.time_base, params.sample_rate) {
+            (Some(frames), Some(time_base), _) => {
+                let time = time_base.calc_time(frames);
+                Some(time.seconds as f64 + time.frac)
+            }
+            (Some(frames), None, Some(rate)) if rate > 0 => Some(frames as f64 / rate as f64),
+            _ => None,
+        };
+    }
+    Ok(())
+}
+
+/// Normalized container name for a file extension, if it is a format we read
+fn format_for_extension(extension: &str) -> Option<&'static str> {
+    Some(match extension.to_ascii_lowercase().as_str() {
+        "mp3" => "mp3",
+        "flac" => "flac",
+        "m4a" | "mp4" | "aac" | "alac" => "m4a",
+        "ogg" | "oga" => "ogg",
+        "wav" | "wave" => "wav",
+        "aif" | "aiff" | "aifc" => "aiff",
+        _ => return None,
+    })
+}
+
+/// Read the embedded tags and duration of an audio file
+pub(crate) fn read_tags(path: &Path) -> Result<AudioTagMetadata, PikaError> {
+    if !path.is_file() {
+        return Err(PikaError::not_found("Audio file not found", Some(path)));
+    }
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let format = format_for_extension(extension)
+        .ok_or_else(|| PikaError::invalid_format("Unsupported audio format", Some(path)))?;
+
+    let mut meta = AudioTagMetadata {
+        format: format.to_string(),
+        ..Default::default()
+    };
+    if matches!(format, "mp3" | "wav" | "aiff") {
+        read_id3(path, &mut meta);
+    }
+    // A file symphonia can't open may still have had readable ID3 tags
+    if let Err(e) = read_container(path, extension, &mut meta) {
+        if meta == (AudioTagMetadata { format: format.to_string(), ..Default::default() }) {
+            return Err(e);
+        }
+        eprintln!("[Tags] {} ({})", e, path.display());
+    }
+    Ok(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_id3_tags_from_wav() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("ghost.wav");
+        std::fs::write(&path, crate::test_support::pcm_wav(1, 11025, &[0; 22050])).unwrap();
+
+        let mut tag = id3::Tag::new();
+        tag.set_artist("Kacey");
+        tag.set_title("Slow Burn");
+        tag.set_genre("(80)");
+        tag.set_year(2018);
+        tag.set_text("TBPM", "101,5");
+        tag.set_text("TKEY", "Ebm");
+        tag.add_frame(id3::frame::ExtendedText {
+            description: "REPLAYGAIN_TRACK_GAIN".to_string(),
+            value: "-6.02 dB".to_string(),
+        });
+        tag.write_to_path(&path, id3::Version::Id3v24).unwrap();
+
+        let meta = read_tags(&path).unwrap();
+        assert_eq!(meta.artist.as_deref(), Some("Kacey"));
+        assert_eq!(meta.title.as_deref(), Some("Slow Burn"));
+        assert_eq!(meta.genre.as_deref(), Some("Folk"));
+        assert_eq!(meta.year, Some(2018));
+        assert_eq!(meta.bpm, Some(101.5));
+        assert_eq!(meta.key.as_deref(), Some("Ebm"));
+        assert!((meta.volume.unwrap() - 0.5).abs() < 0.001);
+        assert_eq!(meta.duration, Some(2.0));
+        assert_eq!(meta.format, "wav");
+
+        // Untagged: still a valid result with the duration
+        let bare = dir.join("bare.wav");
+        std::fs::write(&bare, crate::test_support::pcm_wav(1, 11025, &[0; 11025])).unwrap();
+        let meta = read_tags(&bare).unwrap();
+        assert_eq!(meta.artist, None);
+        assert_eq!(meta.duration, Some(1.0));
+
+        assert!(read_tags(&dir.join("notes.txt")).is_err());
+    }
+}
@@ -0,0 +1,6 @@
+// Headless entry point: library audits and history dumps without the GUI.
+// See `pika-cli --help`.
+
+fn main() -> std::process::ExitCode {
+    pika_desktop_lib::cli::run()
+}
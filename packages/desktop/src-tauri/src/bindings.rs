@@ -0,0 +1,117 @@
+// TypeScript bindings for the Tauri commands and events
+//
+// The frontend used to hand-copy Rust payload structs, and the copies drifted
+// (fields Rust never sends, fields it does send missing). Commands are
+// registered through tauri-specta instead: the same list builds the app's
+// invoke handler and `src/bindings.ts`, whose types come from each payload's
+// `specta::Type` derive (which follows serde's attributes).
+// `test_bindings_are_up_to_date` fails when the checked-in file is stale.
+//
+// Regenerate: PIKA_UPDATE_BINDINGS=1 cargo test bindings
+
+use super::*;
+use tauri_specta::{collect_commands, collect_events, ErrorHandlingMode};
+
+/// Every command the webview can invoke, plus the events it can listen to
+pub(crate) fn builder() -> tauri_specta::Builder<tauri::Wry> {
+    tauri_specta::Builder::<tauri::Wry>::new()
+        .commands(collect_commands![
+            import_virtualdj_library,
+            cancel_virtualdj_import,
+            import_rekordbox_library,
+            import_traktor_library,
+            import_serato_library,
+            import_engine_dj_library,
+            import_mixxx_library,
+            read_virtualdj_history,
+            read_virtualdj_history_full,
+            read_virtualdj_history_since,
+            find_history_sessions,
+            query_virtualdj_history,
+            start_history_watcher,
+            stop_history_watcher,
+            set_now_playing_source,
+            lookup_vdj_track_metadata,
+            read_audio_tags,
+            analyze_track,
+            write_pika_tags_to_virtualdj,
+            write_audio_tags,
+            undo_audio_tag_write,
+            get_sidecar_status,
+            restart_sidecar,
+            enqueue_analysis_job,
+            cancel_analysis_job,
+            clear_analysis_queue,
+            set_analysis_paused,
+            set_analysis_cpu_priority,
+            get_analysis_queue,
+            get_analysis_results,
+            ack_analysis_results,
+            set_vdj_extra_databases,
+            list_vdj_databases,
+            set_vdj_home,
+            get_vdj_locations,
+            get_local_ip,
+        ])
+        .events(collect_events![
+            HistoryTrack,
+            vdj_database::ImportProgress,
+            sidecar::SidecarStatus,
+            analysis_queue::JobProgress,
+            analysis_queue::JobCompletion,
+        ])
+        // Commands reject with the error payload, as plain `invoke` does
+        .error_handling(ErrorHandlingMode::Throw)
+}
+
+// Events are emitted with `Emitter::emit` under these names; the impls only
+// give the bindings each event's name and payload type.
+impl tauri_specta::Event for HistoryTrack {
+    const NAME: &'static str = TRACK_CHANGED_EVENT;
+}
+
+impl tauri_specta::Event for vdj_database::ImportProgress {
+    const NAME: &'static str = IMPORT_PROGRESS_EVENT;
+}
+
+impl tauri_specta::Event for sidecar::SidecarStatus {
+    const NAME: &'static str = sidecar::SIDECAR_STATUS_EVENT;
+}
+
+impl tauri_specta::Event for analysis_queue::JobProgress {
+    const NAME: &'static str = analysis_queue::ANALYSIS_PROGRESS_EVENT;
+}
+
+impl tauri_specta::Event for analysis_queue::JobCompletion {
+    const NAME: &'static str = analysis_queue::ANALYSIS_COMPLETE_EVENT;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specta_typescript::{BigIntExportBehavior, Typescript};
+    use std::path::Path;
+
+    /// Relative to this crate's manifest
+    const BINDINGS_PATH: &str = "../src/bindings.ts";
+
+    #[test]
+    fn test_bindings_are_up_to_date() {
+        // IDs, sizes and timestamps stay well below 2^53, so plain numbers are fine
+        let language = Typescript::default()
+            .bigint(BigIntExportBehavior::Number)
+            .header("// @ts-nocheck");
+        let rendered = builder().export_str(language).unwrap();
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(BINDINGS_PATH);
+        if std::env::var_os("PIKA_UPDATE_BINDINGS").is_some() {
+            std::fs::write(&path, &rendered).unwrap();
+        }
+        let checked_in = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            checked_in == rendered,
+            "{} is stale; regenerate with PIKA_UPDATE_BINDINGS=1 cargo test bindings",
+            path.display()
+        );
+    }
+}
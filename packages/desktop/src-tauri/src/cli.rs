@@ -0,0 +1,391 @@
+// Headless `pika-cli`: the app's library and history code, driven from a terminal
+//
+// For scripting library audits and reproducing user reports without launching
+// the GUI. Results go to stdout (or `--output`) as JSON or CSV; diagnostics go
+// to stderr so they never end up in the exported data.
+
+use crate::error::PikaError;
+use crate::vdj_database::MergedVdjIndex;
+use crate::{HistoryTrack, VirtualDJTrack};
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Debug, Parser)]
+#[command(name = "pika-cli", version, about = "Pika! library and history tools, without the GUI")]
+struct Cli {
+    /// VirtualDJ home folder (default: auto-detect, like the app)
+    #[arg(long, global = true)]
+    vdj_home: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Parse a VirtualDJ database.xml and print its tracks
+    Import {
+        /// database.xml to parse (default: the detected one)
+        database: Option<PathBuf>,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Print every history entry from the daily history files in a date range
+    History {
+        /// First day to include, YYYY-MM-DD (default: the oldest file)
+        #[arg(long)]
+        from: Option<NaiveDate>,
+        /// Last day to include, YYYY-MM-DD (default: the newest file)
+        #[arg(long)]
+        to: Option<NaiveDate>,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Look up a track's BPM, key and volume in every known database.xml
+    Lookup {
+        /// Audio file path as VirtualDJ knows it
+        file_path: String,
+        /// Fallback when the path is unknown (needs --title too)
+        #[arg(long)]
+        artist: Option<String>,
+        #[arg(long)]
+        title: Option<String>,
+        /// Extra database.xml or folder to search (repeatable)
+        #[arg(long = "database")]
+        databases: Vec<String>,
+    },
+    /// Export one set (a day's history file) with BPM and key from the databases
+    ExportSet {
+        /// History .m3u file, or the day it was played (YYYY-MM-DD)
+        set: String,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Show where VirtualDJ was looked for and why each folder was rejected
+    Locations,
+}
+
+#[derive(Debug, Args)]
+struct OutputArgs {
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+    /// Write to this file instead of stdout
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    Json,
+    Csv,
+}
+
+/// Parse the command line, run the command and report errors on stderr
+pub fn run() -> ExitCode {
+    let cli = Cli::parse();
+    match execute(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn execute(cli: Cli) -> Result<(), String> {
+    // The CLI builds without Tauri, so it brings its own runtime for the async lookups
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| format!("Failed to start async runtime: {}", e))?;
+
+    if let Some(home) = cli.vdj_home {
+        crate::set_vdj_home(Some(home.to_string_lossy().into_owned()))?;
+    }
+
+    match cli.command {
+        Command::Import { database, output } => {
+            let index = runtime.block_on(crate::get_cached_database(database, None))?;
+            let tracks: Vec<VirtualDJTrack> = index.songs().map(VirtualDJTrack::from).collect();
+            eprintln!("[CLI] {} tracks", tracks.len());
+            write_output(&output, &tracks, tracks.iter().map(TrackRow::from))
+        }
+        Command::History { from, to, output } => {
+            let history_dir = crate::resolve_history_dir(None)?;
+            let mut entries = Vec::new();
+            for (date, path) in crate::dated_history_files(&history_dir)? {
+                if from.is_some_and(|from| date < from) || to.is_some_and(|to| date > to) {
+                    continue;
+                }
+                entries.extend(read_history_file(&path)?.into_iter().map(|track| (date, track)));
+            }
+            eprintln!("[CLI] {} history entries", entries.len());
+            let tracks: Vec<&HistoryTrack> = entries.iter().map(|(_, track)| track).collect();
+            write_output(&output, &tracks, entries.iter().map(|(date, track)| HistoryRow::new(*date, track)))
+        }
+        Command::Lookup { file_path, artist, title, databases } => {
+            crate::set_vdj_extra_databases(databases)?;
+            let metadata = runtime.block_on(crate::lookup_vdj_track_metadata(file_path, artist, title))?;
+            print_json(&metadata)
+        }
+        Command::ExportSet { set, output } => {
+            let path = resolve_set(&set)?;
+            let databases = runtime.block_on(crate::get_all_databases())
+                .map_err(|e| eprintln!("[CLI] Exporting without BPM/key: {}", e))
+                .ok();
+            let export = SetExport::new(&path, read_history_file(&path)?, databases.as_ref());
+            write_output(&output, &export, export.tracks.iter())
+        }
+        Command::Locations => print_json(&crate::resolve_vdj_locations()),
+    }
+}
+
+fn read_history_file(path: &Path) -> Result<Vec<HistoryTrack>, PikaError> {
+    let content = std::fs::read(path).map_err(|e| PikaError::io("Failed to read history", path, e))?;
+    Ok(crate::parse_history_entries(&content).0)
+}
+
+/// A set given as a history file path, or as the day whose history file to use
+fn resolve_set(set: &str) -> Result<PathBuf, PikaError> {
+    let path = PathBuf::from(set);
+    if path.is_file() {
+        return Ok(path);
+    }
+    let Ok(date) = NaiveDate::parse_from_str(set, "%Y-%m-%d") else {
+        return Err(PikaError::not_found(format!("No such history file: {}", set), Some(&path)));
+    };
+    let history_dir = crate::resolve_history_dir(None)?;
+    crate::dated_history_files(&history_dir)?
+        .into_iter()
+        .find(|(day, _)| *day == date)
+        .map(|(_, path)| path)
+        .ok_or_else(|| PikaError::not_found(format!("No history for {}", date), Some(&history_dir)))
+}
+
+/// Local wall-clock time of a VDJ `lastplaytime` (0 means VDJ didn't record one)
+fn local_time(timestamp: u64) -> Option<String> {
+    if timestamp == 0 {
+        return None;
+    }
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// Library track flattened for CSV (cue points are only counted)
+#[derive(Debug, Serialize)]
+struct TrackRow<'a> {
+    file_path: &'a str,
+    artist: Option<&'a str>,
+    title: Option<&'a str>,
+    bpm: Option<&'a str>,
+    key: Option<&'a str>,
+    duration: Option<i32>,
+    album: Option<&'a str>,
+    genre: Option<&'a str>,
+    year: Option<i32>,
+    play_count: Option<u32>,
+    first_seen: Option<i64>,
+    pois: usize,
+}
+
+impl<'a> From<&'a VirtualDJTrack> for TrackRow<'a> {
+    fn from(track: &'a VirtualDJTrack) -> Self {
+        TrackRow {
+            file_path: &track.file_path,
+            artist: track.artist.as_deref(),
+            title: track.title.as_deref(),
+            bpm: track.bpm.as_deref(),
+            key: track.key.as_deref(),
+            duration: track.duration,
+            album: track.album.as_deref(),
+            genre: track.genre.as_deref(),
+            year: track.year,
+            play_count: track.play_count,
+            first_seen: track.first_seen,
+            pois: track.pois.len(),
+        }
+    }
+}
+
+/// History entry flattened for CSV (extra #EXTVDJ tags are dropped)
+#[derive(Debug, Serialize)]
+struct HistoryRow<'a> {
+    /// Day of the history file the entry came from
+    date: String,
+    played_at: Option<String>,
+    artist: &'a str,
+    title: &'a str,
+    remix: Option<&'a str>,
+    song_length: Option<f64>,
+    file_path: &'a str,
+}
+
+impl<'a> HistoryRow<'a> {
+    fn new(date: NaiveDate, track: &'a HistoryTrack) -> Self {
+        HistoryRow {
+            date: date.to_string(),
+            played_at: local_time(track.timestamp),
+            artist: &track.artist,
+            title: &track.title,
+            remix: track.remix.as_deref(),
+            song_length: track.song_length,
+            file_path: &track.file_path,
+        }
+    }
+}
+
+/// One played track of an exported set
+#[derive(Debug, Serialize)]
+struct SetTrack {
+    position: usize,
+    played_at: Option<String>,
+    artist: String,
+    title: String,
+    remix: Option<String>,
+    bpm: Option<f64>,
+    key: Option<String>,
+    duration: Option<f64>,
+    file_path: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SetExport {
+    /// History file the set was read from
+    source: String,
+    started_at: Option<String>,
+    ended_at: Option<String>,
+    tracks: Vec<SetTrack>,
+}
+
+impl SetExport {
+    /// Tracks in play order, with BPM and key looked up when databases are available
+    fn new(source: &Path, history: Vec<HistoryTrack>, databases: Option<&MergedVdjIndex>) -> Self {
+        let tracks: Vec<SetTrack> = history
+            .into_iter()
+            .enumerate()
+            .map(|(i, track)| {
+                let metadata = databases.and_then(|databases| {
+                    crate::find_track_metadata(databases, &track.file_path, Some(&track.artist), Some(&track.title))
+                });
+                SetTrack {
+                    position: i + 1,
+                    played_at: local_time(track.timestamp),
+                    bpm: metadata.as_ref().and_then(|m| m.bpm),
+                    key: metadata.and_then(|m| m.key),
+                    duration: track.song_length,
+                    artist: track.artist,
+                    title: track.title,
+                    remix: track.remix,
+                    file_path: track.file_path,
+                }
+            })
+            .collect();
+
+        SetExport {
+            source: source.to_string_lossy().into_owned(),
+            started_at: tracks.iter().find_map(|t| t.played_at.clone()),
+            ended_at: tracks.iter().rev().find_map(|t| t.played_at.clone()),
+            tracks,
+        }
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), String> {
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, value).map_err(|e| format!("Failed to write JSON: {}", e))?;
+    writeln!(stdout).map_err(|e| format!("Failed to write output: {}", e))
+}
+
+/// Write `value` as JSON, or `rows` as CSV, to stdout or the `--output` file
+fn write_output<T, R>(args: &OutputArgs, value: &T, rows: impl IntoIterator<Item = R>) -> Result<(), String>
+where
+    T: Serialize,
+    R: Serialize,
+{
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(std::io::BufWriter::new(
+            std::fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?,
+        )),
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    match args.format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, value).map_err(|e| format!("Failed to write JSON: {}", e))?;
+            writeln!(out).map_err(|e| format!("Failed to write output: {}", e))?;
+        }
+        Format::Csv => write_csv(&mut out, rows)?,
+    }
+    out.flush().map_err(|e| format!("Failed to write output: {}", e))
+}
+
+fn write_csv<R: Serialize>(out: impl Write, rows: impl IntoIterator<Item = R>) -> Result<(), String> {
+    let mut writer = csv::Writer::from_writer(out);
+    for row in rows {
+        writer.serialize(row).map_err(|e| format!("Failed to write CSV: {}", e))?;
+    }
+    writer.flush().map_err(|e| format!("Failed to write CSV: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from(["pika-cli", "history", "--from", "2026-01-01", "--format", "csv"]).unwrap();
+        match cli.command {
+            Command::History { from, to, output } => {
+                assert_eq!(from, NaiveDate::from_ymd_opt(2026, 1, 1));
+                assert_eq!(to, None);
+                assert_eq!(output.format, Format::Csv);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["pika-cli", "history", "--from", "yesterday"]).is_err());
+    }
+
+    #[test]
+    fn test_set_export_with_database_metadata() {
+        let history = b"#EXTVDJ:<lastplaytime>1767300000</lastplaytime><artist>Known</artist><title>Song</title><songlength>200.5</songlength>\n\
+C:\\Music\\known.mp3\n\
+#EXTVDJ:<lastplaytime>1767300200</lastplaytime><artist>Other, Artist</artist><title>Moved</title>\n\
+D:\\moved.mp3\n\
+#EXTVDJ:<artist>Ghost</artist><title>Track</title>\n\
+C:\\Music\\ghost.mp3\n";
+        let (entries, _) = crate::parse_history_entries(history);
+
+        let xml = r#"<VirtualDJ_Database Version="8.5">
+            <Song FilePath="C:\Music\known.mp3"><Tags Author="Known" Title="Song" /><Scan Bpm="0.5" Key="Am" /></Song>
+            <Song FilePath="C:\Music\moved.mp3"><Tags Author="Other, Artist" Title="Moved" /><Scan Bpm="0.48" Key="8A" /></Song>
+        </VirtualDJ_Database>"#;
+        let index = crate::vdj_database::parse_database(xml.as_bytes(), xml.len() as u64, None, |_| {}).unwrap();
+        let mut databases = MergedVdjIndex::default();
+        databases.push(PathBuf::from("database.xml"), Arc::new(index));
+
+        let export = SetExport::new(Path::new("2026-01-01.m3u"), entries, Some(&databases));
+        assert_eq!(export.tracks.len(), 3);
+        assert_eq!(export.tracks[0].position, 1);
+        assert_eq!(export.tracks[0].bpm, Some(120.0));
+        assert_eq!(export.tracks[0].duration, Some(200.5));
+        // Path unknown, found by artist + title
+        assert_eq!(export.tracks[1].key.as_deref(), Some("8A"));
+        assert_eq!(export.tracks[2].bpm, None);
+        assert_eq!(export.tracks[2].played_at, None);
+        assert_eq!(export.started_at, local_time(1767300000));
+        assert_eq!(export.ended_at, local_time(1767300200));
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, export.tracks.iter()).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "position,played_at,artist,title,remix,bpm,key,duration,file_path");
+        assert!(lines[2].contains(",\"Other, Artist\",Moved,,125.0,8A,,D:\\moved.mp3"));
+        assert_eq!(lines.len(), 4);
+    }
+}
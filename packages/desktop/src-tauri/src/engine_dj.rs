@@ -0,0 +1,415 @@
+// Denon Engine DJ library reader (Engine Library/Database2/m.db)
+//
+// Track paths are stored relative to the "Engine Library" folder. Cues, loops
+// and the sample rate needed to place them live in per-track blobs:
+// `trackData` and `quickCues` are zlib streams behind a 4-byte big-endian
+// length (Qt's qCompress format), `loops` is stored uncompressed.
+
+use flate2::read::ZlibDecoder;
+use rusqlite::Connection;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::library_db::{normalize_path, open_read_only, LibraryDbError};
+use crate::LibraryPlaylist;
+
+const LIBRARY: &str = "Engine DJ";
+/// Schema majors written by Engine DJ 2.x - 4.x. Engine Prime 1.x used a different layout.
+const SUPPORTED_SCHEMA_MAJOR: std::ops::RangeInclusive<i64> = 2..=3;
+const SUPPORTED_SCHEMA: &str = "2.x - 3.x (Engine DJ 2.0 and later)";
+
+/// A row from the Track table
+#[derive(Debug, Default, Clone)]
+pub(crate) struct EngineTrack {
+    pub(crate) id: i64,
+    pub(crate) file_path: String,
+    pub(crate) title: Option<String>,
+    pub(crate) artist: Option<String>,
+    pub(crate) album: Option<String>,
+    pub(crate) genre: Option<String>,
+    pub(crate) year: Option<i64>,
+    /// Seconds
+    pub(crate) length: Option<f64>,
+    pub(crate) bpm: Option<f64>,
+    /// Circle-of-fifths key id, see `keys::from_engine`
+    pub(crate) key: Option<i64>,
+    /// 0-100 (20 per star)
+    pub(crate) rating: Option<i64>,
+    /// Unix timestamp
+    pub(crate) date_added: Option<i64>,
+    pub(crate) cues: Vec<EngineCue>,
+}
+
+/// A hot cue or saved loop decoded from the track's blobs
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct EngineCue {
+    pub(crate) is_loop: bool,
+    /// Pad number, 1-based
+    pub(crate) number: u32,
+    pub(crate) label: Option<String>,
+    /// Seconds
+    pub(crate) start: f64,
+    /// Seconds (loops only)
+    pub(crate) end: Option<f64>,
+    pub(crate) color: Option<String>,
+}
+
+/// A parsed Engine DJ library
+#[derive(Debug, Default)]
+pub(crate) struct EngineLibrary {
+    pub(crate) schema_version: String,
+    pub(crate) tracks: Vec<EngineTrack>,
+    pub(crate) playlists: Vec<LibraryPlaylist>,
+}
+
+/// Big-endian reader over a decoded blob
+struct BlobReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BlobReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn i64(&mut self) -> Option<i64> {
+        self.take(8).map(|b| i64::from_be_bytes(b.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        self.take(8).map(|b| f64::from_be_bytes(b.try_into().unwrap()))
+    }
+
+    fn label(&mut self) -> Option<Option<String>> {
+        let len = self.u8()? as usize;
+        let text = String::from_utf8_lossy(self.take(len)?).into_owned();
+        Some(Some(text).filter(|t| !t.is_empty()))
+    }
+
+    /// ARGB, returned as "#RRGGBB"
+    fn color(&mut self) -> Option<String> {
+        let argb = self.take(4)?;
+        Some(format!("#{:02X}{:02X}{:02X}", argb[1], argb[2], argb[3]))
+    }
+}
+
+/// Undo Qt's qCompress: 4-byte big-endian length, then a zlib stream
+fn qt_uncompress(blob: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    ZlibDecoder::new(blob.get(4..)?).read_to_end(&mut out).ok()?;
+    Some(out)
+}
+
+/// trackData starts with the sample rate as a big-endian f64
+fn sample_rate(track_data: &[u8]) -> Option<f64> {
+    let data = qt_uncompress(track_data)?;
+    BlobReader { data: &data, pos: 0 }.f64().filter(|rate| *rate > 0.0)
+}
+
+/// quickCues: i64 count, then per cue a label, f64 sample offset (-1 = unset) and ARGB color
+fn parse_quick_cues(blob: &[u8], sample_rate: f64) -> Vec<EngineCue> {
+    let Some(data) = qt_uncompress(blob) else {
+        return Vec::new();
+    };
+    let mut reader = BlobReader { data: &data, pos: 0 };
+    let mut cues = Vec::new();
+    let count = reader.i64().unwrap_or(0);
+    for number in 1..=count.clamp(0, 64) as u32 {
+        let (Some(label), Some(offset), Some(color)) = (reader.label(), reader.f64(), reader.color()) else {
+            break;
+        };
+        if offset >= 0.0 {
+            cues.push(EngineCue { is_loop: false, number, label, start: offset / sample_rate, end: None, color: Some(color) });
+        }
+    }
+    cues
+}
+
+/// loops (uncompressed): i64 count, then per loop a label, f64 start/end, two "is set" flags and ARGB color
+fn parse_loops(blob: &[u8], sample_rate: f64) -> Vec<EngineCue> {
+    let mut reader = BlobReader { data: blob, pos: 0 };
+    let mut loops = Vec::new();
+    let count = reader.i64().unwrap_or(0);
+    for number in 1..=count.clamp(0, 64) as u32 {
+        let (Some(label), Some(start), Some(end), Some(start_set), Some(end_set), Some(color)) =
+            (reader.label(), reader.f64(), reader.f64(), reader.u8(), reader.u8(), reader.color())
+        else {
+            break;
+        };
+        if start_set != 0 && end_set != 0 {
+            loops.push(EngineCue {
+                is_loop: true,
+                number,
+                label,
+                start: start / sample_rate,
+                end: Some(end / sample_rate),
+                color: Some(color),
+            });
+        }
+    }
+    loops
+}
+
+/// Order items of an Engine linked list (each row names the id that follows it)
+fn linked_order(items: &[(i64, i64)]) -> Vec<i64> {
+    let next: HashMap<i64, i64> = items.iter().copied().collect();
+    let followers: HashSet<i64> = items.iter().map(|(_, next)| *next).collect();
+    let mut ordered = Vec::with_capacity(items.len());
+    let mut seen = HashSet::new();
+
+    for (head, _) in items.iter().filter(|(id, _)| !followers.contains(id)) {
+        let mut id = *head;
+        while seen.insert(id) {
+            ordered.push(id);
+            match next.get(&id) {
+                Some(following) if next.contains_key(following) => id = *following,
+                _ => break,
+            }
+        }
+    }
+    // Anything left is part of a broken chain - keep it, in id order
+    let mut rest: Vec<i64> = items.iter().map(|(id, _)| *id).filter(|id| !seen.contains(id)).collect();
+    rest.sort_unstable();
+    ordered.extend(rest);
+    ordered
+}
+
+fn schema_version(conn: &Connection) -> Result<String, LibraryDbError> {
+    let version = conn
+        .query_row(
+            "SELECT schemaVersionMajor, schemaVersionMinor, schemaVersionPatch FROM Information LIMIT 1",
+            [],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)),
+        )
+        .map_err(|_| LibraryDbError::UnsupportedSchema {
+            library: LIBRARY,
+            found: "unknown (no Information table)".to_string(),
+            supported: SUPPORTED_SCHEMA,
+        })?;
+
+    let text = format!("{}.{}.{}", version.0, version.1, version.2);
+    if !SUPPORTED_SCHEMA_MAJOR.contains(&version.0) {
+        return Err(LibraryDbError::UnsupportedSchema { library: LIBRARY, found: text, supported: SUPPORTED_SCHEMA });
+    }
+    Ok(text)
+}
+
+fn read_tracks(conn: &Connection, library_dir: &Path) -> rusqlite::Result<Vec<EngineTrack>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, path, title, artist, album, genre, year, length, bpmAnalyzed, bpm, key, rating, dateAdded,
+                trackData, quickCues, loops
+         FROM Track WHERE path IS NOT NULL AND path != ''",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let path: String = row.get(1)?;
+        let mut track = EngineTrack {
+            id: row.get(0)?,
+            file_path: normalize_path(&library_dir.join(path)).to_string_lossy().into_owned(),
+            title: row.get(2)?,
+            artist: row.get(3)?,
+            album: row.get(4)?,
+            genre: row.get(5)?,
+            year: row.get(6)?,
+            length: row.get(7)?,
+            bpm: row.get::<_, Option<f64>>(8)?.or(row.get::<_, Option<f64>>(9)?),
+            key: row.get(10)?,
+            rating: row.get(11)?,
+            date_added: row.get(12)?,
+            cues: Vec::new(),
+        };
+
+        let blob = |index: usize| row.get::<_, Option<Vec<u8>>>(index).map(Option::unwrap_or_default);
+        if let Some(rate) = sample_rate(&blob(13)?) {
+            track.cues = parse_quick_cues(&blob(14)?, rate);
+            track.cues.extend(parse_loops(&blob(15)?, rate));
+        }
+        Ok(track)
+    })?;
+    rows.collect()
+}
+
+fn read_playlists(conn: &Connection, paths_by_id: &HashMap<i64, String>) -> rusqlite::Result<Vec<LibraryPlaylist>> {
+    let mut lists: HashMap<i64, (String, i64, i64)> = HashMap::new();
+    let mut stmt = conn.prepare("SELECT id, title, parentListId, nextListId FROM Playlist")?;
+    for row in stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))? {
+        let (id, title, parent, next): (i64, Option<String>, Option<i64>, Option<i64>) = row?;
+        lists.insert(id, (title.unwrap_or_default(), parent.unwrap_or(0), next.unwrap_or(0)));
+    }
+
+    let mut entities: HashMap<i64, Vec<(i64, i64, i64)>> = HashMap::new();
+    let mut stmt = conn.prepare("SELECT id, listId, trackId, nextEntityId FROM PlaylistEntity")?;
+    for row in stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))? {
+        let (id, list, track, next): (i64, i64, i64, Option<i64>) = row?;
+        entities.entry(list).or_default().push((id, track, next.unwrap_or(0)));
+    }
+
+    fn build(
+        parent: i64,
+        lists: &HashMap<i64, (String, i64, i64)>,
+        entities: &HashMap<i64, Vec<(i64, i64, i64)>>,
+        paths_by_id: &HashMap<i64, String>,
+    ) -> Vec<LibraryPlaylist> {
+        let siblings: Vec<(i64, i64)> = lists
+            .iter()
+            .filter(|(id, (_, p, _))| *p == parent && **id != parent)
+            .map(|(id, (_, _, next))| (*id, *next))
+            .collect();
+
+        linked_order(&siblings)
+            .into_iter()
+            .map(|id| {
+                let rows = entities.get(&id).map(Vec::as_slice).unwrap_or_default();
+                let track_by_entity: HashMap<i64, i64> = rows.iter().map(|(e, t, _)| (*e, *t)).collect();
+                let order: Vec<(i64, i64)> = rows.iter().map(|(e, _, next)| (*e, *next)).collect();
+                let children = build(id, lists, entities, paths_by_id);
+                LibraryPlaylist {
+                    name: lists[&id].0.clone(),
+                    is_folder: !children.is_empty(),
+                    children,
+                    track_paths: linked_order(&order)
+                        .into_iter()
+                        .filter_map(|e| paths_by_id.get(&track_by_entity[&e]).cloned())
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
+    Ok(build(0, &lists, &entities, paths_by_id))
+}
+
+/// Find m.db from the file itself, its Database2 folder or the Engine Library folder
+pub(crate) fn resolve_database_path(path: &Path) -> PathBuf {
+    if path.is_file() {
+        return path.to_path_buf();
+    }
+    let nested = path.join("Database2").join("m.db");
+    if nested.is_file() {
+        nested
+    } else {
+        path.join("m.db")
+    }
+}
+
+/// Read tracks, playlists and cues from an Engine DJ m.db
+pub(crate) fn load_library(db_path: &Path) -> Result<EngineLibrary, LibraryDbError> {
+    let conn = open_read_only(db_path)?;
+    let schema_version = schema_version(&conn)?;
+    let query_err = |e: rusqlite::Error| LibraryDbError::Query {
+        library: LIBRARY,
+        schema_version: schema_version.clone(),
+        message: e.to_string(),
+    };
+
+    // Database2 sits inside the Engine Library folder that paths are relative to
+    let library_dir = db_path
+        .parent()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let tracks = read_tracks(&conn, &library_dir).map_err(query_err)?;
+    let paths_by_id: HashMap<i64, String> = tracks.iter().map(|t| (t.id, t.file_path.clone())).collect();
+    let playlists = read_playlists(&conn, &paths_by_id).map_err(query_err)?;
+
+    Ok(EngineLibrary { schema_version, tracks, playlists })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use std::io::Write;
+
+    fn qt_compress(data: &[u8]) -> Vec<u8> {
+        let mut out = (data.len() as u32).to_be_bytes().to_vec();
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        out.extend(encoder.finish().unwrap());
+        out
+    }
+
+    #[test]
+    fn test_load_library_fixture() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let db_dir = dir.join("Engine Library").join("Database2");
+        std::fs::create_dir_all(&db_dir).unwrap();
+        let db_path = db_dir.join("m.db");
+
+        let mut track_data = 44100f64.to_be_bytes().to_vec();
+        track_data.extend(0i64.to_be_bytes());
+        let mut quick_cues = 2i64.to_be_bytes().to_vec();
+        quick_cues.extend([4]);
+        quick_cues.extend(b"Drop");
+        quick_cues.extend((44100.0 * 32.5f64).to_be_bytes());
+        quick_cues.extend([255, 40, 226, 20]);
+        quick_cues.extend([0]);
+        quick_cues.extend((-1f64).to_be_bytes());
+        quick_cues.extend([255, 0, 0, 0]);
+        let mut loops = 1i64.to_be_bytes().to_vec();
+        loops.extend([0]);
+        loops.extend((44100.0 * 60f64).to_be_bytes());
+        loops.extend((44100.0 * 68f64).to_be_bytes());
+        loops.extend([1, 1, 255, 0, 0, 255]);
+
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE Information (id INTEGER PRIMARY KEY, uuid TEXT, schemaVersionMajor INTEGER,
+                 schemaVersionMinor INTEGER, schemaVersionPatch INTEGER);
+             INSERT INTO Information VALUES (1, 'x', 2, 20, 3);
+             CREATE TABLE Track (id INTEGER PRIMARY KEY, path TEXT, title TEXT, artist TEXT, album TEXT,
+                 genre TEXT, year INTEGER, length REAL, bpmAnalyzed REAL, bpm INTEGER, key INTEGER,
+                 rating INTEGER, dateAdded INTEGER, trackData BLOB, quickCues BLOB, loops BLOB);
+             CREATE TABLE Playlist (id INTEGER PRIMARY KEY, title TEXT, parentListId INTEGER, nextListId INTEGER);
+             CREATE TABLE PlaylistEntity (id INTEGER PRIMARY KEY, listId INTEGER, trackId INTEGER, nextEntityId INTEGER);
+             INSERT INTO Playlist VALUES (1, 'Sets', 0, 3), (2, 'Saturday', 1, 0), (3, 'Warmup', 0, 0);
+             INSERT INTO PlaylistEntity VALUES (10, 2, 2, 0), (11, 2, 1, 10);",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO Track VALUES (1, '../Music/Slow Burn.mp3', 'Slow Burn', 'Kacey', NULL, 'WCS', 2018,
+                 214.8, 96.02, 96, 1, 80, 1682899200, ?1, ?2, ?3)",
+            rusqlite::params![qt_compress(&track_data), qt_compress(&quick_cues), loops],
+        )
+        .unwrap();
+        conn.execute("INSERT INTO Track (id, path, title) VALUES (2, '../Music/b.mp3', 'B')", []).unwrap();
+        drop(conn);
+
+        let library = load_library(&resolve_database_path(&dir.join("Engine Library"))).unwrap();
+        assert_eq!(library.schema_version, "2.20.3");
+        assert_eq!(library.tracks.len(), 2);
+        let track = library.tracks.iter().find(|t| t.id == 1).unwrap();
+        assert_eq!(Path::new(&track.file_path), dir.join("Music").join("Slow Burn.mp3"));
+        assert_eq!(track.key, Some(1));
+        assert_eq!(track.cues.len(), 2);
+        assert_eq!(track.cues[0].label.as_deref(), Some("Drop"));
+        assert_eq!(track.cues[0].start, 32.5);
+        assert_eq!(track.cues[0].color.as_deref(), Some("#28E214"));
+        assert!(track.cues[1].is_loop);
+        assert_eq!(track.cues[1].end, Some(68.0));
+
+        // Top level keeps the linked-list order; the entity chain starts at 11
+        assert_eq!(library.playlists.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), ["Sets", "Warmup"]);
+        let saturday = &library.playlists[0].children[0];
+        assert!(library.playlists[0].is_folder);
+        assert_eq!(saturday.track_paths[0], track.file_path);
+        assert_eq!(saturday.track_paths.len(), 2);
+
+        // Engine Prime 1.x databases are rejected with the version found
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute("UPDATE Information SET schemaVersionMajor = 1, schemaVersionMinor = 18", []).unwrap();
+        drop(conn);
+        assert_eq!(
+            load_library(&db_path).unwrap_err(),
+            LibraryDbError::UnsupportedSchema { library: LIBRARY, found: "1.18.3".to_string(), supported: SUPPORTED_SCHEMA }
+        );
+    }
+}
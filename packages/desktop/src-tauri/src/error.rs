@@ -0,0 +1,205 @@
+// Structured errors for Tauri commands
+//
+// Commands used to reject with a formatted string, so the frontend could not
+// tell a missing database.xml from a malformed one or from a permissions
+// problem. `PikaError` serializes as `{ "code": "...", "message": "...", ... }`
+// where `code` is stable and safe to match on, `message` is for display, and
+// the remaining fields say which file (and for XML, where in it) was at fault.
+
+use serde::Serialize;
+use specta::Type;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum PikaError {
+    /// A file or folder we need does not exist (or was never configured)
+    NotFound { message: String, path: Option<String> },
+    /// The file exists but the OS refused access
+    PermissionDenied { message: String, path: String },
+    /// Any other I/O failure
+    Io { message: String, path: String },
+    /// Malformed XML. `line`/`column` are 1-based and filled in when the file can be re-read.
+    XmlParse {
+        message: String,
+        path: Option<String>,
+        byte_offset: Option<u64>,
+        line: Option<u64>,
+        column: Option<u64>,
+    },
+    /// Well-formed, but not the kind of file we expected
+    InvalidFormat { message: String, path: Option<String> },
+    /// The request itself can't be carried out (e.g. conflicting options)
+    InvalidInput { message: String },
+    /// Another app is writing the file right now; retrying later may work
+    Busy { message: String, path: String },
+    /// The user cancelled the operation
+    Cancelled { message: String },
+    /// A background task or lock failed; not the user's fault
+    Internal { message: String },
+}
+
+impl PikaError {
+    pub fn not_found(message: impl Into<String>, path: Option<&Path>) -> Self {
+        PikaError::NotFound { message: message.into(), path: path.map(display) }
+    }
+
+    pub fn invalid_format(message: impl Into<String>, path: Option<&Path>) -> Self {
+        PikaError::InvalidFormat { message: message.into(), path: path.map(display) }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        PikaError::InvalidInput { message: message.into() }
+    }
+
+    pub fn busy(message: impl Into<String>, path: &Path) -> Self {
+        PikaError::Busy { message: message.into(), path: display(path) }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        PikaError::Internal { message: message.into() }
+    }
+
+    /// Classify an I/O error by kind. `action` reads like "Failed to read history".
+    pub fn io(action: &str, path: &Path, e: std::io::Error) -> Self {
+        let message = format!("{}: {}", action, e);
+        match e.kind() {
+            std::io::ErrorKind::NotFound => PikaError::NotFound { message, path: Some(display(path)) },
+            std::io::ErrorKind::PermissionDenied => PikaError::PermissionDenied { message, path: display(path) },
+            _ => PikaError::Io { message, path: display(path) },
+        }
+    }
+
+    /// XML error at a byte offset in a stream whose file is not known yet
+    pub fn xml_at(byte_offset: u64, message: impl Into<String>) -> Self {
+        PikaError::XmlParse { message: message.into(), path: None, byte_offset: Some(byte_offset), line: None, column: None }
+    }
+
+    /// Attach the file an error came from. For XML errors the byte offset is
+    /// turned into a line and column by re-reading the start of the file.
+    pub fn in_file(self, file: &Path) -> Self {
+        match self {
+            PikaError::XmlParse { message, path: None, byte_offset, .. } => {
+                let position = byte_offset.and_then(|offset| line_column(file, offset));
+                PikaError::XmlParse {
+                    message,
+                    path: Some(display(file)),
+                    byte_offset,
+                    line: position.map(|(line, _)| line),
+                    column: position.map(|(_, column)| column),
+                }
+            }
+            PikaError::InvalidFormat { message, path: None } => {
+                PikaError::InvalidFormat { message, path: Some(display(file)) }
+            }
+            other => other,
+        }
+    }
+
+    /// Stable machine-readable code, as serialized
+    pub fn code(&self) -> &'static str {
+        match self {
+            PikaError::NotFound { .. } => "not_found",
+            PikaError::PermissionDenied { .. } => "permission_denied",
+            PikaError::Io { .. } => "io",
+            PikaError::XmlParse { .. } => "xml_parse",
+            PikaError::InvalidFormat { .. } => "invalid_format",
+            PikaError::InvalidInput { .. } => "invalid_input",
+            PikaError::Busy { .. } => "busy",
+            PikaError::Cancelled { .. } => "cancelled",
+            PikaError::Internal { .. } => "internal",
+        }
+    }
+}
+
+impl std::fmt::Display for PikaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PikaError::XmlParse { message, path, line, column, .. } => {
+                write!(f, "{}", message)?;
+                match (path, line, column) {
+                    (Some(path), Some(line), Some(column)) => write!(f, " ({}:{}:{})", path, line, column),
+                    (Some(path), _, _) => write!(f, " ({})", path),
+                    _ => Ok(()),
+                }
+            }
+            PikaError::NotFound { message, .. }
+            | PikaError::PermissionDenied { message, .. }
+            | PikaError::Io { message, .. }
+            | PikaError::InvalidFormat { message, .. }
+            | PikaError::InvalidInput { message }
+            | PikaError::Busy { message, .. }
+            | PikaError::Cancelled { message }
+            | PikaError::Internal { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for PikaError {}
+
+/// Lets code that reports plain strings (the CLI, now-playing sources) use `?` on PikaError
+impl From<PikaError> for String {
+    fn from(e: PikaError) -> String {
+        e.to_string()
+    }
+}
+
+fn display(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// 1-based line and column (in bytes) of `offset` within a file
+fn line_column(path: &Path, offset: u64) -> Option<(u64, u64)> {
+    let mut reader = BufReader::new(std::fs::File::open(path).ok()?);
+    let mut line_start = 0u64;
+    let mut line = 1u64;
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        let read = reader.read_until(b'\n', &mut buf).ok()? as u64;
+        if read == 0 || line_start + read > offset {
+            return Some((line, offset - line_start + 1));
+        }
+        line_start += read;
+        line += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialized_codes_and_xml_position() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let xml = dir.join("database.xml");
+        let content = "<VirtualDJ_Database>\n <Song FilePath=\"a.mp3\">\n  <Tags Author=\"x\" </Song>\n";
+        std::fs::write(&xml, content).unwrap();
+
+        // The `<` of `</Song>` on line 3, after `  <Tags Author="x" `
+        let offset = content.find("</Song>").unwrap() as u64;
+        let error = PikaError::xml_at(offset, "XML parsing error: bad attribute").in_file(&xml);
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["code"], "xml_parse");
+        assert_eq!(json["line"], 3);
+        assert_eq!(json["column"], 20);
+        assert_eq!(json["path"], xml.to_string_lossy().as_ref());
+        assert!(error.to_string().ends_with(":3:20)"));
+
+        let missing = PikaError::io("Failed to read history", &dir.join("nope.m3u"), std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(missing.code(), "not_found");
+        assert_eq!(serde_json::to_value(&missing).unwrap()["code"], missing.code());
+        assert_eq!(String::from(PikaError::internal("boom")), "boom");
+
+        let schema = crate::library_db::LibraryDbError::UnsupportedSchema {
+            library: "Mixxx",
+            found: "99".to_string(),
+            supported: "28-39",
+        };
+        let schema = PikaError::from(schema).in_file(&dir.join("mixxxdb.sqlite"));
+        assert_eq!(schema.code(), "invalid_format");
+        assert!(matches!(schema, PikaError::InvalidFormat { path: Some(_), .. }));
+    }
+}
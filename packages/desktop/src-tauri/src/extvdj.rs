@@ -0,0 +1,118 @@
+// #EXTVDJ history line parser
+//
+// VDJ writes one `#EXTVDJ:` line per played track followed by the file path, e.g.
+// #EXTVDJ:<filesize>8123456</filesize><artist>A &amp; B</artist><title>Song</title>
+//         <songlength>215.3</songlength><lastplaytime>1700000000</lastplaytime>
+
+use std::collections::HashMap;
+
+/// All fields VDJ writes for a single history entry
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ExtVdjEntry {
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub remix: Option<String>,
+    /// File size in bytes
+    pub file_size: Option<u64>,
+    /// Track length in seconds
+    pub song_length: Option<f64>,
+    /// Unix timestamp of when the track was played
+    pub last_play_time: Option<u64>,
+    /// Wall-clock time of the play as written by VDJ (e.g. "21:43")
+    pub time: Option<String>,
+    /// Any tags this parser does not know about, keyed by tag name
+    pub extras: HashMap<String, String>,
+}
+
+impl ExtVdjEntry {
+    /// Parse a `#EXTVDJ:` line. Returns None if the line is not an EXTVDJ entry.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut rest = line.trim().strip_prefix("#EXTVDJ:")?;
+        let mut entry = ExtVdjEntry::default();
+
+        while let Some(open) = rest.find('<') {
+            let after_open = &rest[open + 1..];
+            let Some(name_end) = after_open.find('>') else { break };
+            let name = &after_open[..name_end];
+            let body = &after_open[name_end + 1..];
+
+            // Stray '<' or a closing tag outside any element - skip past it
+            if name.is_empty() || name.starts_with('/') || name.contains(char::is_whitespace) {
+                rest = after_open;
+                continue;
+            }
+
+            // Match on this tag's own end tag so values containing '<' or '>' survive
+            let close = format!("</{}>", name);
+            let Some(value_end) = body.find(&close) else { break };
+            entry.set(name, decode_entities(&body[..value_end]));
+            rest = &body[value_end + close.len()..];
+        }
+
+        Some(entry)
+    }
+
+    fn set(&mut self, name: &str, value: String) {
+        match name {
+            "artist" => self.artist = non_empty(value),
+            "title" => self.title = non_empty(value),
+            "remix" => self.remix = non_empty(value),
+            "filesize" => self.file_size = value.trim().parse().ok(),
+            "songlength" => self.song_length = value.trim().parse().ok(),
+            "lastplaytime" => self.last_play_time = value.trim().parse().ok(),
+            "time" => self.time = non_empty(value),
+            _ => {
+                self.extras.insert(name.to_string(), value);
+            }
+        }
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    if value.trim().is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+/// Decode XML entities (&amp;, &lt;, &#39;, &#x27; ...), keeping the raw text if malformed
+fn decode_entities(raw: &str) -> String {
+    quick_xml::escape::unescape(raw)
+        .map(|s| s.into_owned())
+        .unwrap_or_else(|_| raw.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_all_known_tags() {
+        let entry = ExtVdjEntry::parse(
+            "#EXTVDJ:<filesize>8123456</filesize><artist>Tom &amp; Jerry</artist><title>Love &lt;3</title>\
+             <remix>Extended Mix</remix><songlength>215.3</songlength><lastplaytime>1700000000</lastplaytime>\
+             <time>21:43</time><deck>2</deck>",
+        )
+        .unwrap();
+
+        assert_eq!(entry.artist.as_deref(), Some("Tom & Jerry"));
+        assert_eq!(entry.title.as_deref(), Some("Love <3"));
+        assert_eq!(entry.remix.as_deref(), Some("Extended Mix"));
+        assert_eq!(entry.file_size, Some(8123456));
+        assert_eq!(entry.song_length, Some(215.3));
+        assert_eq!(entry.last_play_time, Some(1700000000));
+        assert_eq!(entry.time.as_deref(), Some("21:43"));
+        assert_eq!(entry.extras.get("deck").map(String::as_str), Some("2"));
+    }
+
+    #[test]
+    fn test_parse_unescaped_angle_brackets_in_title() {
+        let entry = ExtVdjEntry::parse("#EXTVDJ:<artist>DJ</artist><title>Intro <Live> Edit</title>").unwrap();
+        assert_eq!(entry.artist.as_deref(), Some("DJ"));
+        assert_eq!(entry.title.as_deref(), Some("Intro <Live> Edit"));
+        assert!(entry.extras.is_empty());
+
+        assert!(ExtVdjEntry::parse("C:\\Music\\track.mp3").is_none());
+    }
+}
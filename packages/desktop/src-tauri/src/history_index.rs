@@ -0,0 +1,270 @@
+// Date-range queries over the whole VDJ History folder
+//
+// A year of daily .m3u files is a few hundred files, and most queries only
+// touch a handful of days. The index keeps every file's parsed entries and
+// play-time range (re-reading a file only when its size or mtime changes), so
+// paging through a query re-parses nothing and only the files overlapping the
+// requested range are filtered.
+
+use specta::Type;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::error::PikaError;
+use crate::history_sessions::history_files;
+use crate::HistoryTrack;
+
+/// Entries without a play time only match when neither `from` nor `to` is set
+#[derive(Debug, Clone, Deserialize, Type)]
+#[serde(default)]
+pub struct HistoryQuery {
+    /// Unix timestamp, inclusive
+    pub from: Option<u64>,
+    /// Unix timestamp, inclusive
+    pub to: Option<u64>,
+    /// Case-insensitive substring of the artist
+    pub artist: Option<String>,
+    /// Case-insensitive substring of the title
+    pub title: Option<String>,
+    /// Matching entries to skip (entries are in play order)
+    pub offset: usize,
+    /// Page size
+    pub limit: usize,
+}
+
+impl Default for HistoryQuery {
+    fn default() -> Self {
+        Self {
+            from: None,
+            to: None,
+            artist: None,
+            title: None,
+            offset: 0,
+            limit: 100,
+        }
+    }
+}
+
+/// One page of matching history entries plus counts over every match
+#[derive(Debug, Serialize, Type)]
+pub struct HistoryPage {
+    entries: Vec<HistoryTrack>,
+    /// Matching entries across all pages
+    total: usize,
+    offset: usize,
+    /// Distinct artist + title pairs among the matches
+    unique_tracks: usize,
+    unique_artists: usize,
+    /// Play time of the first and last match
+    first_played: Option<u64>,
+    last_played: Option<u64>,
+    /// History files that overlapped the range and were read
+    files_read: usize,
+}
+
+/// What the index knows about one history file
+#[derive(Debug, Clone)]
+struct IndexedFile {
+    len: u64,
+    modified: Option<SystemTime>,
+    /// Earliest and latest play time; None when no entry has one
+    range: Option<(u64, u64)>,
+    entries: Vec<HistoryTrack>,
+}
+
+/// History file → parsed entries and play-time range, kept across queries
+#[derive(Debug, Default)]
+pub(crate) struct HistoryIndex {
+    files: HashMap<PathBuf, IndexedFile>,
+}
+
+impl HistoryIndex {
+    /// Bring the index in line with the folder: new or changed files are
+    /// re-read, deleted (or unreadable) ones dropped
+    fn refresh(&mut self, history_dir: &Path) -> Result<(), PikaError> {
+        let paths = history_files(history_dir)?;
+        let current: HashSet<&PathBuf> = paths.iter().collect();
+        self.files.retain(|path, _| current.contains(path));
+
+        for path in &paths {
+            let Ok(metadata) = std::fs::metadata(path) else { continue };
+            let modified = metadata.modified().ok();
+            let unchanged = self
+                .files
+                .get(path)
+                .is_some_and(|file| file.len == metadata.len() && file.modified == modified);
+            if unchanged {
+                continue;
+            }
+
+            match read_entries(path) {
+                Ok(entries) => {
+                    let file = IndexedFile { len: metadata.len(), modified, range: time_range(&entries), entries };
+                    self.files.insert(path.clone(), file);
+                }
+                Err(e) => {
+                    eprintln!("[History] Skipping unreadable file {}: {}", path.display(), e);
+                    self.files.remove(path);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a query over the files whose range overlaps `from..=to`
+    pub(crate) fn query(&mut self, history_dir: &Path, query: &HistoryQuery) -> Result<HistoryPage, PikaError> {
+        self.refresh(history_dir)?;
+
+        // Without a play time an entry can't be placed in a range
+        let bounded = query.from.is_some() || query.to.is_some();
+        let overlapping: Vec<&IndexedFile> = self
+            .files
+            .values()
+            .filter(|file| match file.range {
+                Some((first, last)) => {
+                    query.from.is_none_or(|from| last >= from) && query.to.is_none_or(|to| first <= to)
+                }
+                None => !bounded,
+            })
+            .collect();
+
+        let mut entries: Vec<&HistoryTrack> = overlapping.iter().flat_map(|file| &file.entries).collect();
+        crate::history_sessions::dedup_plays(&mut entries, |track| track);
+
+        let artist = query.artist.as_deref().map(str::to_lowercase).filter(|s| !s.is_empty());
+        let title = query.title.as_deref().map(str::to_lowercase).filter(|s| !s.is_empty());
+        entries.retain(|track| {
+            (!bounded || track.timestamp > 0)
+                && query.from.is_none_or(|from| track.timestamp >= from)
+                && query.to.is_none_or(|to| track.timestamp <= to)
+                && artist.as_ref().is_none_or(|a| track.artist.to_lowercase().contains(a))
+                && title.as_ref().is_none_or(|t| track.title.to_lowercase().contains(t))
+        });
+
+        let unique_tracks = entries
+            .iter()
+            .map(|track| crate::vdj_database::track_key(&track.artist, &track.title))
+            .collect::<HashSet<_>>()
+            .len();
+        let unique_artists = entries
+            .iter()
+            .map(|track| crate::vdj_database::fold_tag(&track.artist))
+            .collect::<HashSet<_>>()
+            .len();
+        let (first_played, last_played) = match time_range(entries.iter().copied()) {
+            Some((first, last)) => (Some(first), Some(last)),
+            None => (None, None),
+        };
+
+        Ok(HistoryPage {
+            total: entries.len(),
+            offset: query.offset,
+            unique_tracks,
+            unique_artists,
+            first_played,
+            last_played,
+            files_read: overlapping.len(),
+            entries: entries.into_iter().skip(query.offset).take(query.limit).cloned().collect(),
+        })
+    }
+}
+
+fn read_entries(path: &Path) -> Result<Vec<HistoryTrack>, PikaError> {
+    let content = std::fs::read(path).map_err(|e| PikaError::io("Failed to read history", path, e))?;
+    Ok(crate::parse_history_entries(&content).0)
+}
+
+/// Earliest and latest play time, ignoring entries without one
+fn time_range<'a>(entries: impl IntoIterator<Item = &'a HistoryTrack>) -> Option<(u64, u64)> {
+    let mut times = entries.into_iter().map(|track| track.timestamp).filter(|&t| t > 0);
+    let first = times.next()?;
+    Some(times.fold((first, first), |(min, max), t| (min.min(t), max.max(t))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: u64, artist: &str, title: &str) -> String {
+        format!(
+            "#EXTVDJ:<lastplaytime>{}</lastplaytime><artist>{}</artist><title>{}</title>\n/music/{}.mp3\n",
+            timestamp, artist, title, title
+        )
+    }
+
+    #[test]
+    fn test_query_reads_only_overlapping_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let month = dir.join("2026").join("02");
+        std::fs::create_dir_all(&month).unwrap();
+
+        let day = 86_400;
+        let start = 1_769_904_000; // 2026-02-01 00:00 UTC
+        for d in 0..5u64 {
+            let content: String = (0..4u64)
+                .map(|i| entry(start + d * day + i * 300, if i % 2 == 0 { "Alpha" } else { "Beta" }, &format!("Song {}", i)))
+                .collect();
+            std::fs::write(month.join(format!("2026-02-0{}.m3u", d + 1)), content).unwrap();
+        }
+
+        let mut index = HistoryIndex::default();
+        let query = HistoryQuery { from: Some(start + day), to: Some(start + 2 * day + 600), limit: 3, ..Default::default() };
+        let page = index.query(dir, &query).unwrap();
+        assert_eq!(page.files_read, 2);
+        assert_eq!(page.total, 7);
+        assert_eq!(page.entries.len(), 3);
+        assert_eq!(page.unique_tracks, 4);
+        assert_eq!(page.unique_artists, 2);
+        assert_eq!(page.first_played, Some(start + day));
+        assert_eq!(page.last_played, Some(start + 2 * day + 600));
+
+        let second = index.query(dir, &HistoryQuery { offset: 6, ..query.clone() }).unwrap();
+        assert_eq!(second.entries.len(), 1);
+        assert_eq!(second.entries[0].timestamp, start + 2 * day + 600);
+
+        let filtered = HistoryQuery { artist: Some("BETA".to_string()), title: Some("song 3".to_string()), ..Default::default() };
+        let page = index.query(dir, &filtered).unwrap();
+        assert_eq!(page.files_read, 5);
+        assert_eq!(page.total, 5);
+        assert_eq!(page.unique_tracks, 1);
+
+        // Appending to a file moves its range; the index notices the change
+        let last_day = month.join("2026-02-05.m3u");
+        let mut content = std::fs::read_to_string(&last_day).unwrap();
+        content.push_str(&entry(start + 10 * day, "Gamma", "Late"));
+        std::fs::write(&last_day, content).unwrap();
+        let late = HistoryQuery { from: Some(start + 9 * day), ..Default::default() };
+        let page = index.query(dir, &late).unwrap();
+        assert_eq!(page.files_read, 1);
+        assert_eq!(page.entries[0].artist, "Gamma");
+
+        // Pages come from the parsed entries kept in the index: a rewrite that keeps
+        // the size and mtime isn't noticed
+        let modified = std::fs::metadata(&last_day).unwrap().modified().unwrap();
+        let content = std::fs::read_to_string(&last_day).unwrap().replace("Gamma", "Delta");
+        std::fs::write(&last_day, content).unwrap();
+        std::fs::File::options().write(true).open(&last_day).unwrap().set_modified(modified).unwrap();
+        assert_eq!(index.query(dir, &late).unwrap().entries[0].artist, "Gamma");
+
+        // Artists are counted like tracks: case and spacing don't make a new one
+        std::fs::write(month.join("2026-02-20.m3u"), entry(start + 19 * day, "  alpha ", "Song 0")).unwrap();
+        let page = index.query(dir, &HistoryQuery { from: Some(start + 19 * day), ..Default::default() }).unwrap();
+        assert_eq!((page.unique_artists, page.unique_tracks), (1, 1));
+        let page = index.query(dir, &HistoryQuery { artist: Some("alpha".to_string()), ..Default::default() }).unwrap();
+        assert_eq!(page.unique_artists, 1);
+
+        // Entries without a play time only show up when the query has no bounds
+        // (and repeat plays of the same file aren't mistaken for copies)
+        let untimed = "#EXTVDJ:<artist>Zeta</artist><title>Untimed</title>\n/music/u.mp3\n";
+        std::fs::write(month.join("untimed.m3u"), untimed.repeat(2)).unwrap();
+        let everything = index.query(dir, &HistoryQuery { limit: 1000, ..Default::default() }).unwrap();
+        assert_eq!(everything.entries.iter().filter(|track| track.artist == "Zeta").count(), 2);
+        let until = HistoryQuery { to: Some(start + 30 * day), limit: 1000, ..Default::default() };
+        let bounded = index.query(dir, &until).unwrap();
+        assert_eq!(bounded.total, everything.total - 2);
+        assert!(bounded.entries.iter().all(|track| track.timestamp > 0));
+    }
+}
@@ -0,0 +1,237 @@
+// Past DJ sessions rebuilt from VDJ's History folder
+//
+// VDJ writes one .m3u per day (newer versions file them under year/month
+// subfolders), so a set that runs past midnight is split across two files and a
+// day with an afternoon practice and an evening gig shares one. Entries from
+// every file are merged into a single timeline and cut wherever nothing was
+// played for longer than the idle gap.
+
+use specta::Type;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::error::PikaError;
+use crate::HistoryTrack;
+
+#[derive(Debug, Clone, Deserialize, Type)]
+#[serde(default)]
+pub struct SessionOptions {
+    /// Start a new session after this long without a new track
+    pub idle_gap_minutes: u32,
+    /// Drop sessions with fewer tracks (pre-listening, quick tests)
+    pub min_tracks: usize,
+    /// Only sessions ending at or after this Unix timestamp
+    pub since: Option<u64>,
+    /// Only sessions starting at or before this Unix timestamp
+    pub until: Option<u64>,
+}
+
+impl Default for SessionOptions {
+    fn default() -> Self {
+        Self {
+            idle_gap_minutes: 30,
+            min_tracks: 3,
+            since: None,
+            until: None,
+        }
+    }
+}
+
+/// A stretch of continuous playing, ready to be imported into the Logbook
+#[derive(Debug, Serialize, Type)]
+pub struct SessionCandidate {
+    /// Unix timestamp of the first track
+    started_at: u64,
+    /// Unix timestamp the last track ended (or started, if its length is unknown)
+    ended_at: u64,
+    /// History files the tracks came from, in order
+    source_files: Vec<String>,
+    /// Tracks in play order
+    tracks: Vec<HistoryTrack>,
+}
+
+/// Every .m3u under the History folder, including year/month subfolders.
+/// Subfolders that can't be listed are skipped with a warning.
+pub(crate) fn history_files(history_dir: &Path) -> Result<Vec<PathBuf>, PikaError> {
+    let mut files = Vec::new();
+    let mut pending = vec![history_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if dir != history_dir => {
+                eprintln!("[History] Skipping unreadable folder {}: {}", dir.display(), e);
+                continue;
+            }
+            Err(e) => return Err(PikaError::io("Failed to read history directory", &dir, e)),
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else { continue };
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "m3u") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Read every history file under `history_dir` and split the timeline into sessions, newest first
+pub(crate) fn find_sessions(history_dir: &Path, options: &SessionOptions) -> Result<Vec<SessionCandidate>, PikaError> {
+    let mut entries = Vec::new();
+    for path in history_files(history_dir)? {
+        // One locked or unreadable day shouldn't hide every other session
+        let content = match std::fs::read(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("[History] Skipping unreadable file {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let source = path.to_string_lossy().into_owned();
+        let (tracks, _) = crate::parse_history_entries(&content);
+        entries.extend(tracks.into_iter().map(|track| (source.clone(), track)));
+    }
+    Ok(split_sessions(entries, options))
+}
+
+/// Sort plays by time and drop copies of the same play. The same play can be logged
+/// in more than one file (e.g. a copied History folder); sorting on the path too puts
+/// copies next to each other when several plays share a second. Untimed entries can't
+/// be told apart from a replay of the same file, so they are all kept.
+pub(crate) fn dedup_plays<T>(entries: &mut Vec<T>, track: impl Fn(&T) -> &HistoryTrack) {
+    entries.sort_by(|a, b| {
+        let (a, b) = (track(a), track(b));
+        (a.timestamp, &a.file_path).cmp(&(b.timestamp, &b.file_path))
+    });
+    entries.dedup_by(|a, b| {
+        let (a, b) = (track(a), track(b));
+        a.timestamp > 0 && a.timestamp == b.timestamp && a.file_path == b.file_path
+    });
+}
+
+/// Merge `(source file, entry)` pairs into one timeline and cut it at idle gaps
+pub(crate) fn split_sessions(entries: Vec<(String, HistoryTrack)>, options: &SessionOptions) -> Vec<SessionCandidate> {
+    // Without a play time an entry can't be placed on the timeline
+    let (mut entries, untimed): (Vec<_>, Vec<_>) = entries.into_iter().partition(|(_, track)| track.timestamp > 0);
+    if !untimed.is_empty() {
+        eprintln!("[History] Skipping {} entries without a play time", untimed.len());
+    }
+
+    dedup_plays(&mut entries, |(_, track)| track);
+
+    let idle_gap = u64::from(options.idle_gap_minutes) * 60;
+    let mut sessions: Vec<Vec<(String, HistoryTrack)>> = Vec::new();
+    for entry in entries {
+        match sessions.last_mut() {
+            Some(current) if entry.1.timestamp - current.last().unwrap().1.timestamp <= idle_gap => current.push(entry),
+            _ => sessions.push(vec![entry]),
+        }
+    }
+
+    sessions
+        .into_iter()
+        .rev()
+        .filter(|session| session.len() >= options.min_tracks.max(1))
+        .map(|session| {
+            let mut source_files = Vec::new();
+            let mut seen = BTreeSet::new();
+            let tracks: Vec<HistoryTrack> = session
+                .into_iter()
+                .map(|(source, track)| {
+                    if seen.insert(source.clone()) {
+                        source_files.push(source);
+                    }
+                    track
+                })
+                .collect();
+
+            let first = &tracks[0];
+            let last = &tracks[tracks.len() - 1];
+            let last_length = last.song_length.map(|s| s.max(0.0).round() as u64).unwrap_or(0);
+            SessionCandidate {
+                started_at: first.timestamp,
+                ended_at: last.timestamp + last_length,
+                source_files,
+                tracks,
+            }
+        })
+        .filter(|session| options.since.is_none_or(|since| session.ended_at >= since))
+        .filter(|session| options.until.is_none_or(|until| session.started_at <= until))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: u64, title: &str) -> String {
+        format!(
+            "#EXTVDJ:<lastplaytime>{}</lastplaytime><artist>Artist</artist><title>{}</title><songlength>240</songlength>\nC:\\Music\\{}.mp3\n",
+            timestamp, title, title
+        )
+    }
+
+    #[test]
+    fn test_sessions_merge_across_midnight_and_split_on_idle_gaps() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let month = dir.join("2026").join("01");
+        std::fs::create_dir_all(&month).unwrap();
+
+        // 2026-01-02 23:00 UTC onwards
+        let evening = 1767394800;
+        // Afternoon practice on the same day, then the gig runs past midnight into the next file
+        let day_one: String = [entry(evening - 6 * 3600, "Practice 1"), entry(evening - 6 * 3600 + 240, "Practice 2")]
+            .into_iter()
+            .chain((0..4).map(|i| entry(evening + i * 300, &format!("Gig {}", i))))
+            .collect();
+        let day_two: String = (1..4).map(|i| entry(evening + 900 + i * 1200, &format!("Gig {}", i + 3))).collect();
+        std::fs::write(month.join("2026-01-02.m3u"), day_one).unwrap();
+        std::fs::write(month.join("2026-01-03.m3u"), &day_two).unwrap();
+        // A duplicate copy of the second day in the top-level folder
+        std::fs::write(dir.join("copy.m3u"), &day_two).unwrap();
+
+        let options = SessionOptions { min_tracks: 2, ..Default::default() };
+        let sessions = find_sessions(dir, &options).unwrap();
+        assert_eq!(sessions.len(), 2);
+
+        let gig = &sessions[0];
+        assert_eq!(gig.tracks.len(), 7);
+        assert_eq!(gig.started_at, evening);
+        assert_eq!(gig.ended_at, evening + 900 + 3 * 1200 + 240);
+        assert_eq!(gig.tracks[6].title, "Gig 6");
+        assert_eq!(gig.source_files.len(), 2);
+        assert!(gig.source_files[0].ends_with("2026-01-02.m3u"));
+
+        let practice = &sessions[1];
+        assert_eq!(practice.tracks.len(), 2);
+        assert_eq!(practice.started_at, evening - 6 * 3600);
+
+        // A shorter gap splits the gig where tracks are 20 minutes apart
+        let strict = SessionOptions { idle_gap_minutes: 15, min_tracks: 2, ..Default::default() };
+        let sessions = find_sessions(dir, &strict).unwrap();
+        assert_eq!(sessions.iter().map(|s| s.tracks.len()).collect::<Vec<_>>(), vec![4, 2]);
+
+        let recent = SessionOptions { since: Some(evening), min_tracks: 2, ..Default::default() };
+        assert_eq!(find_sessions(dir, &recent).unwrap().len(), 1);
+
+        // Two tracks logged in the same second, in two copies of the same file
+        let same_second = format!("{}{}", entry(evening + 10_000, "B Side"), entry(evening + 10_000, "A Side"));
+        std::fs::write(month.join("2026-01-04.m3u"), &same_second).unwrap();
+        std::fs::write(dir.join("copy-2.m3u"), &same_second).unwrap();
+        let options = SessionOptions { min_tracks: 1, ..Default::default() };
+        let sessions = find_sessions(dir, &options).unwrap();
+        assert_eq!(sessions[0].tracks.len(), 2);
+
+        // A file that can't be read is skipped rather than failing the scan
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.join("missing"), month.join("2026-01-05.m3u")).unwrap();
+            assert_eq!(find_sessions(dir, &options).unwrap().len(), sessions.len());
+        }
+    }
+}
@@ -0,0 +1,121 @@
+// Now-playing follower
+//
+// Watches a now-playing source (VDJ's History folder by default) on a
+// background thread and hands every newly played track to a callback. This
+// replaces the webview polling loop, which re-read the whole file every tick
+// and stalled whenever the window was throttled in the background.
+
+use notify::{RecommendedWatcher, Watcher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::error::PikaError;
+use crate::now_playing::NowPlayingSource;
+use crate::{find_latest_history_file, HistoryTrack};
+
+/// Safety net for missed filesystem events (network shares, some macOS volumes)
+const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Which history file the follower should track
+#[derive(Debug, Clone)]
+pub enum HistorySource {
+    /// Follow whichever .m3u is newest, switching when VDJ rolls over to a new day
+    Latest,
+    /// Stick to one explicitly configured file
+    Pinned(PathBuf),
+}
+
+impl HistorySource {
+    pub(crate) fn resolve(&self) -> Result<PathBuf, PikaError> {
+        match self {
+            HistorySource::Latest => find_latest_history_file(),
+            HistorySource::Pinned(path) => Ok(path.clone()),
+        }
+    }
+}
+
+/// Handle to a running follower thread
+pub struct HistoryWatcher {
+    stop: Arc<AtomicBool>,
+    wake: mpsc::Sender<()>,
+    thread: Option<JoinHandle<()>>,
+    // Dropping the watcher unregisters the OS watch
+    _watcher: RecommendedWatcher,
+}
+
+impl HistoryWatcher {
+    /// Start following `source`, invoking `on_track` for each newly played track.
+    /// Tracks already played when the watcher starts are not replayed.
+    pub fn start<F>(mut source: Box<dyn NowPlayingSource>, on_track: F) -> Result<Self, PikaError>
+    where
+        F: Fn(HistoryTrack) + Send + 'static,
+    {
+        let (watch_target, mode) = source.watch_target();
+
+        let (tx, rx) = mpsc::channel::<()>();
+        let event_tx = tx.clone();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if res.is_ok() {
+                let _ = event_tx.send(());
+            }
+        })
+        .map_err(|e| PikaError::internal(format!("Failed to create history watcher: {}", e)))?;
+        watcher
+            .watch(&watch_target, mode)
+            .map_err(|e| PikaError::internal(format!("Failed to watch {}: {}", watch_target.display(), e)))?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+
+        let thread = std::thread::Builder::new()
+            .name("vdj-history-watcher".into())
+            .spawn(move || {
+                while !thread_stop.load(Ordering::Relaxed) {
+                    // Wake on a filesystem event or after the rescan interval, whichever comes first
+                    match rx.recv_timeout(RESCAN_INTERVAL) {
+                        Ok(()) => while rx.try_recv().is_ok() {},
+                        Err(mpsc::RecvTimeoutError::Timeout) => {}
+                        Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                    }
+                    if thread_stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+
+                    match source.poll() {
+                        Ok(tracks) => tracks.into_iter().for_each(&on_track),
+                        Err(e) => eprintln!("[{} Watcher] {}", source.name(), e),
+                    }
+                }
+            })
+            .map_err(|e| PikaError::internal(format!("Failed to spawn history watcher thread: {}", e)))?;
+
+        Ok(Self {
+            stop,
+            wake: tx,
+            thread: Some(thread),
+            _watcher: watcher,
+        })
+    }
+
+    /// Signal the follower thread to exit and wait for it
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.wake.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for HistoryWatcher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
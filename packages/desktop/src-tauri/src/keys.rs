@@ -0,0 +1,168 @@
+// Musical key conversion
+//
+// Every DJ app encodes keys differently: Traktor and Mixxx number them
+// chromatically, Engine DJ walks the circle of fifths, and text fields may hold
+// standard names, Camelot ("8A") or Open Key ("1m"). Everything is mapped onto
+// one chromatic index (0-11 major from C, 12-23 minor from Cm) and shown with
+// the names below, which match what VDJ writes.
+
+use serde::Deserialize;
+use specta::Type;
+
+/// Key names by chromatic index
+pub(crate) const KEY_NAMES: [&str; 24] = [
+    "C", "Db", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B",
+    "Cm", "C#m", "Dm", "Ebm", "Em", "Fm", "F#m", "Gm", "G#m", "Am", "Bbm", "Bm",
+];
+
+/// Chromatic index of the relative minor of a major key
+fn relative_minor(major: usize) -> usize {
+    12 + (major + 9) % 12
+}
+
+/// Traktor MUSICAL_KEY VALUE: already a chromatic index
+pub(crate) fn from_chromatic(value: i64) -> Option<&'static str> {
+    usize::try_from(value).ok().and_then(|v| KEY_NAMES.get(v)).copied()
+}
+
+/// Engine DJ Track.key: circle of fifths from C, each major followed by its relative minor
+/// (0 = C, 1 = Am, 2 = G, 3 = Em, ...)
+pub(crate) fn from_engine(value: i64) -> Option<&'static str> {
+    if !(0..24).contains(&value) {
+        return None;
+    }
+    let major = (value as usize / 2 * 7) % 12;
+    let index = if value % 2 == 0 { major } else { relative_minor(major) };
+    Some(KEY_NAMES[index])
+}
+
+/// Mixxx library.key_id: 1-12 major from C, 13-24 minor from Cm, 0 = unknown
+pub(crate) fn from_mixxx_id(value: i64) -> Option<&'static str> {
+    from_chromatic(value - 1).filter(|_| value > 0)
+}
+
+/// Camelot wheel number (1-12) and mode to a chromatic index. 8B = C, 8A = Am.
+fn from_camelot(number: usize, minor: bool) -> usize {
+    let major = ((number + 4) * 7) % 12;
+    if minor { relative_minor(major) } else { major }
+}
+
+/// Parse a key written as a name ("Am", "F#", "Bb minor"), Camelot ("8A") or Open Key ("1m")
+pub(crate) fn parse(text: &str) -> Option<&'static str> {
+    let text = text.trim();
+    let lower = text.to_ascii_lowercase();
+
+    // Camelot / Open Key: a number then a single letter
+    let digits = lower.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 && lower.len() == digits + 1 {
+        let number: usize = lower[..digits].parse().ok()?;
+        if !(1..=12).contains(&number) {
+            return None;
+        }
+        let index = match &lower[digits..] {
+            "a" => from_camelot(number, true),
+            "b" => from_camelot(number, false),
+            // Open Key 1d/1m is Camelot 8B/8A
+            "d" => from_camelot((number + 6) % 12 + 1, false),
+            "m" => from_camelot((number + 6) % 12 + 1, true),
+            _ => return None,
+        };
+        return Some(KEY_NAMES[index]);
+    }
+
+    let mut chars = text.chars();
+    let root: i32 = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    // No mode word starts with "b", so a leading "b" is always a flat
+    let (accidental, mode) = match rest.chars().next() {
+        Some(c @ ('#' | '♯')) => (1, &rest[c.len_utf8()..]),
+        Some(c @ ('b' | '♭')) => (-1, &rest[c.len_utf8()..]),
+        _ => (0, rest),
+    };
+    let minor = match mode.trim().to_ascii_lowercase().as_str() {
+        "" | "maj" | "major" => false,
+        "m" | "min" | "minor" => true,
+        _ => return None,
+    };
+
+    let pitch = (root + accidental).rem_euclid(12) as usize;
+    Some(KEY_NAMES[if minor { 12 + pitch } else { pitch }])
+}
+
+/// How a key is written back into files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyNotation {
+    /// "Am", "F#"
+    #[default]
+    Standard,
+    /// "8A", "2B"
+    Camelot,
+    /// "1m", "7d"
+    OpenKey,
+}
+
+/// Re-write a key in any notation `parse` understands into `notation`
+pub(crate) fn format(text: &str, notation: KeyNotation) -> Option<String> {
+    let name = parse(text)?;
+    let index = KEY_NAMES.iter().position(|k| *k == name)?;
+    let minor = index >= 12;
+    let major = if minor { (index - 12 + 3) % 12 } else { index };
+    let camelot = (1..=12).find(|n| (n + 4) * 7 % 12 == major)?;
+    Some(match notation {
+        KeyNotation::Standard => name.to_string(),
+        KeyNotation::Camelot => format!("{}{}", camelot, if minor { 'A' } else { 'B' }),
+        KeyNotation::OpenKey => format!("{}{}", (camelot + 4) % 12 + 1, if minor { 'm' } else { 'd' }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_conversions() {
+        assert_eq!(from_chromatic(21), Some("Am"));
+        assert_eq!(from_chromatic(24), None);
+        assert_eq!(from_engine(0), Some("C"));
+        assert_eq!(from_engine(1), Some("Am"));
+        assert_eq!(from_engine(12), Some("F#"));
+        assert_eq!(from_engine(23), Some("Dm"));
+        assert_eq!(from_mixxx_id(22), Some("Am"));
+        assert_eq!(from_mixxx_id(0), None);
+
+        assert_eq!(parse("8A"), Some("Am"));
+        assert_eq!(parse("8B"), Some("C"));
+        assert_eq!(parse("12a"), Some("C#m"));
+        assert_eq!(parse("1m"), Some("Am"));
+        assert_eq!(parse("1d"), Some("C"));
+        assert_eq!(parse("A#m"), Some("Bbm"));
+        assert_eq!(parse("Bbm"), Some("Bbm"));
+        assert_eq!(parse("Bb"), Some("Bb"));
+        assert_eq!(parse("b"), Some("B"));
+        assert_eq!(parse("F# minor"), Some("F#m"));
+        assert_eq!(parse("Eb major"), Some("Eb"));
+        assert_eq!(parse("13A"), None);
+        assert_eq!(parse("unknown"), None);
+
+        assert_eq!(format("Am", KeyNotation::Camelot).as_deref(), Some("8A"));
+        assert_eq!(format("Am", KeyNotation::OpenKey).as_deref(), Some("1m"));
+        assert_eq!(format("8B", KeyNotation::Standard).as_deref(), Some("C"));
+        assert_eq!(format("F#m", KeyNotation::Camelot).as_deref(), Some("11A"));
+        assert_eq!(format("E", KeyNotation::OpenKey).as_deref(), Some("5d"));
+        for name in KEY_NAMES {
+            for notation in [KeyNotation::Camelot, KeyNotation::OpenKey] {
+                assert_eq!(parse(&format(name, notation).unwrap()), Some(name));
+            }
+        }
+    }
+}
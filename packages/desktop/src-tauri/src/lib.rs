@@ -1,125 +1,121 @@
 // Pika! Desktop Application
 
+// Without the `app` feature most commands have no caller; only `pika-cli` is built
+#![cfg_attr(not(feature = "app"), allow(dead_code))]
+
+mod analysis;
+#[cfg(feature = "app")]
+mod analysis_queue;
+mod audio_tags;
+#[cfg(feature = "app")]
+mod bindings;
+pub mod cli;
+mod engine_dj;
+mod error;
+mod extvdj;
+mod history_index;
+mod history_sessions;
+mod history_watcher;
+mod keys;
+mod library_db;
+mod mixxx;
+mod now_playing;
+mod rekordbox;
+mod serato;
+#[cfg(feature = "app")]
+mod sidecar;
+mod tag_writeback;
+#[cfg(test)]
+mod test_support;
+mod traktor;
+mod vdj_database;
+mod vdj_discovery;
+mod vdj_locations;
+mod vdj_snapshot;
+mod vdj_writeback;
+
 use serde::{Deserialize, Serialize};
+use specta::Type;
 use std::collections::HashMap;
 use once_cell::sync::Lazy;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "app")]
+use tauri::{Emitter, Manager};
+use std::sync::Arc;
+use error::PikaError;
+use vdj_database::{MergedVdjIndex, VdjIndex, VirtualDJPoi, VirtualDJSong};
 
-#[derive(Debug, Deserialize)]
-#[serde(rename = "VirtualDJ_Database")]
-struct VirtualDJDatabase {
-    #[serde(rename = "@Version", default)]
-    version: Option<String>,
-    #[serde(rename = "Song", default)]
-    songs: Vec<VirtualDJSong>,
-}
-
-#[derive(Debug, Deserialize, Clone)]
-struct VirtualDJSong {
-    #[serde(rename = "@FilePath")]
-    file_path: String,
-    #[serde(rename = "@FileSize", default)]
-    file_size: Option<String>,
-    #[serde(rename = "Tags", default)]
-    tags: Option<VirtualDJTags>,
-    #[serde(rename = "Scan", default)]
-    scan: Option<VirtualDJScan>,
-    #[serde(rename = "Infos", default)]
-    infos: Option<VirtualDJInfos>,
-    // 🛡️ Issue 43 Fix: IgnoredAny wastes deserialization. 
-    // Removed _comment field entirely (Serde ignores unknown fields by default).
-    #[serde(rename = "Poi", default)]
-    pois: Vec<VirtualDJPoi>,
-}
-
-#[derive(Debug, Deserialize, Default, Clone)]
-struct VirtualDJPoi {
-    #[serde(rename = "@Name", default)]
-    name: Option<String>,
-    #[serde(rename = "@Pos", default)]
-    pos: Option<String>,
-    #[serde(rename = "@Type", default)]
-    _type: Option<String>,
-}
-
-#[derive(Debug, Deserialize, Default, Clone)]
-struct VirtualDJInfos {
-    #[serde(rename = "@SongLength", default)]
-    song_length: Option<String>,
-    #[serde(rename = "@FirstSeen", default)]
-    first_seen: Option<String>,
-    #[serde(rename = "@PlayCount", default)]
-    play_count: Option<String>,
-}
-
-#[derive(Debug, Deserialize, Default, Clone)]
-struct VirtualDJTags {
-    #[serde(rename = "@Author", default)]
-    author: Option<String>,
-    #[serde(rename = "@Title", default)]
-    title: Option<String>,
-    #[serde(rename = "@Genre", default)]
-    genre: Option<String>,
-    #[serde(rename = "@Album", default)]
-    album: Option<String>,
-    #[serde(rename = "@TrackNumber", default)]
-    track_number: Option<String>,
-    #[serde(rename = "@Year", default)]
-    year: Option<String>,
-    #[serde(rename = "@Flag", default)]
-    flag: Option<String>,
-}
-
-#[derive(Debug, Deserialize, Default, Clone)]
-struct VirtualDJScan {
-    #[serde(rename = "@Version", default)]
-    version: Option<String>,
-    #[serde(rename = "@Bpm", default)]
-    bpm: Option<String>,
-    #[serde(rename = "@AltBpm", default)]
-    alt_bpm: Option<String>,
-    #[serde(rename = "@Volume", default)]
-    volume: Option<String>,
-    #[serde(rename = "@Key", default)]
-    key: Option<String>,
-    #[serde(rename = "@AudioSig", default)]
-    audio_sig: Option<String>,
-    #[serde(rename = "@Flag", default)]
-    flag: Option<String>,
-}
-
-/// Global cache for the VirtualDJ database to avoid repeated disk I/O and parsing
+/// Global cache for the VirtualDJ databases to avoid repeated disk I/O and parsing
 struct VdjCache {
-    /// Original file path of the database
-    path: PathBuf,
     /// Last modified time to detect changes
     last_modified: std::time::SystemTime,
-    /// Cached songs indexed by file path for O(1) lookups
-    songs: HashMap<String, VirtualDJSong>,
+    /// Parsed songs with secondary indices, shared with callers without copying
+    index: Arc<VdjIndex>,
 }
 
-static VDJ_CACHE: Lazy<tokio::sync::RwLock<Option<VdjCache>>> = Lazy::new(|| tokio::sync::RwLock::new(None));
+/// One entry per database.xml (home folder plus every external drive), keyed by path
+static VDJ_CACHE: Lazy<tokio::sync::RwLock<HashMap<PathBuf, VdjCache>>> =
+    Lazy::new(|| tokio::sync::RwLock::new(HashMap::new()));
+
+/// Databases found by the last discovery scan and when it ran. Scanning probes
+/// every mounted volume, so lookups reuse it until it expires or a setting changes.
+static DISCOVERED_DATABASES: Lazy<std::sync::RwLock<Option<DiscoveryScan>>> = Lazy::new(|| std::sync::RwLock::new(None));
+
+type DiscoveryScan = (std::time::Instant, Vec<vdj_discovery::DiscoveredDatabase>);
+
+/// Rescan volumes this often so plugged-in drives are picked up
+const DISCOVERY_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Extra database.xml files / folders configured in Settings
+static EXTRA_DATABASES: Lazy<std::sync::RwLock<Vec<PathBuf>>> = Lazy::new(|| std::sync::RwLock::new(Vec::new()));
+
+/// VDJ home folder chosen in Settings (takes priority over every detected location)
+static VDJ_HOME_OVERRIDE: Lazy<std::sync::RwLock<Option<PathBuf>>> = Lazy::new(|| std::sync::RwLock::new(None));
+
+/// Which app the history watcher follows (Settings > Now playing source)
+static NOW_PLAYING_SOURCE: Lazy<std::sync::RwLock<now_playing::NowPlayingConfig>> =
+    Lazy::new(|| std::sync::RwLock::new(now_playing::NowPlayingConfig::default()));
+
+/// Where parsed index snapshots are kept (set from the app data dir at startup)
+static SNAPSHOT_DIR: once_cell::sync::OnceCell<PathBuf> = once_cell::sync::OnceCell::new();
+
+/// Where undo journals for audio tag writes are kept (set from the app data dir at startup)
+static TAG_JOURNAL_DIR: once_cell::sync::OnceCell<PathBuf> = once_cell::sync::OnceCell::new();
 
-/// Internal helper to load and parse the VDJ database with caching
-async fn get_cached_database(custom_path: Option<PathBuf>) -> Result<HashMap<String, VirtualDJSong>, String> {
+/// Event emitted while database.xml is being parsed during an import
+const IMPORT_PROGRESS_EVENT: &str = "vdj://import-progress";
+
+/// Set by `cancel_virtualdj_import` to abort an in-flight import parse
+static IMPORT_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Receives parse progress for `get_cached_database`
+type ImportProgressFn = Box<dyn Fn(vdj_database::ImportProgress) + Send>;
+
+/// Internal helper to load and parse the VDJ database with caching.
+/// When `progress` is given, it receives parse progress and the parse can be cancelled.
+async fn get_cached_database(
+    custom_path: Option<PathBuf>,
+    progress: Option<ImportProgressFn>,
+) -> Result<Arc<VdjIndex>, PikaError> {
     let db_path = if let Some(path) = custom_path {
         path
     } else {
         find_vdj_database_path()
-            .ok_or_else(|| "VirtualDJ database.xml not found".to_string())?
+            .ok_or_else(|| PikaError::not_found("VirtualDJ database.xml not found", None))?
     };
     
     let metadata = std::fs::metadata(&db_path)
-        .map_err(|e| format!("Failed to get database metadata: {}", e))?;
+        .map_err(|e| PikaError::io("Failed to get database metadata", &db_path, e))?;
     let current_modified = metadata.modified()
-        .map_err(|e| format!("Failed to get modification time: {}", e))?;
+        .map_err(|e| PikaError::io("Failed to get modification time", &db_path, e))?;
 
     // Check if we have a valid cache hit
     {
         let cache = VDJ_CACHE.read().await;
-        if let Some(ref c) = *cache {
-            if c.path == db_path && c.last_modified == current_modified {
-                return Ok(c.songs.clone());
+        if let Some(c) = cache.get(&db_path) {
+            if c.last_modified == current_modified {
+                return Ok(c.index.clone());
             }
         }
     }
@@ -129,30 +125,114 @@ async fn get_cached_database(custom_path: Option<PathBuf>) -> Result<HashMap<Str
     
     // println!("[VDJ] Cache miss or stale. Loading database from: {:?}", db_path);
     
-    let content = tokio::fs::read_to_string(&db_path)
-        .await
-        .map_err(|e| format!("Failed to read database.xml: {}", e))?;
-    
-    let database: VirtualDJDatabase = quick_xml::de::from_str(&content)
-        .map_err(|e| format!("XML parsing error: {}", e))?;
-    
-    let mut song_map = HashMap::with_capacity(database.songs.len());
-    for song in database.songs {
-        song_map.insert(song.file_path.clone(), song);
-    }
+    // Streaming parse is CPU-bound on large libraries - keep it off the async runtime
+    let parse_path = db_path.clone();
+    let snapshot_dir = SNAPSHOT_DIR.get().cloned();
+    let index = tokio::task::spawn_blocking(move || {
+        // A snapshot from a previous run is valid as long as database.xml is unchanged
+        if let Some(ref dir) = snapshot_dir {
+            if let Some(index) = vdj_snapshot::load(dir, &parse_path, &metadata) {
+                return Ok(index);
+            }
+        }
+
+        let cancel = progress.as_ref().map(|_| &IMPORT_CANCELLED);
+        let index = vdj_database::load_database(&parse_path, cancel, |update| {
+            if let Some(ref progress) = progress {
+                progress(update);
+            }
+        })?;
 
-    *cache = Some(VdjCache {
-        path: db_path,
+        if let Some(ref dir) = snapshot_dir {
+            if let Err(e) = vdj_snapshot::save(dir, &parse_path, &metadata, &index) {
+                eprintln!("[VDJ] Failed to save index snapshot: {}", e);
+            }
+        }
+        Ok::<_, PikaError>(index)
+    })
+    .await
+    .map_err(|e| PikaError::internal(format!("Database parse task failed: {}", e)))??;
+    let index = Arc::new(index);
+
+    cache.insert(db_path, VdjCache {
         last_modified: current_modified,
-        songs: song_map.clone(),
+        index: index.clone(),
+    });
+
+    // println!("[VDJ] Database indexed: {} tracks", index.len());
+    Ok(index)
+}
+
+/// Every database.xml on this machine, from the last scan unless it is older than
+/// `DISCOVERY_TTL` or `rescan` is set. A fresh scan also drops cached indexes
+/// whose database.xml has gone (e.g. the drive was unplugged).
+async fn discovered_databases(rescan: bool) -> Vec<vdj_discovery::DiscoveredDatabase> {
+    if !rescan {
+        if let Ok(cached) = DISCOVERED_DATABASES.read() {
+            if let Some((scanned_at, databases)) = cached.as_ref() {
+                if scanned_at.elapsed() < DISCOVERY_TTL {
+                    return databases.clone();
+                }
+            }
+        }
+    }
+
+    let extra_paths = EXTRA_DATABASES.read().map(|p| p.clone()).unwrap_or_default();
+    let discovered = tokio::task::spawn_blocking(move || {
+        vdj_discovery::discover_databases(find_vdj_database_path(), &extra_paths)
+    })
+    .await
+    .unwrap_or_else(|e| {
+        eprintln!("[VDJ] Database discovery task failed: {}", e);
+        Vec::new()
+    });
+    if let Ok(mut cached) = DISCOVERED_DATABASES.write() {
+        *cached = Some((std::time::Instant::now(), discovered.clone()));
+    }
+
+    drop_missing_databases(&mut *VDJ_CACHE.write().await);
+    discovered
+}
+
+/// Drop cached indexes whose database.xml has gone (e.g. the drive was unplugged)
+fn drop_missing_databases(cache: &mut HashMap<PathBuf, VdjCache>) {
+    cache.retain(|path, _| {
+        let present = path.is_file();
+        if !present {
+            println!("[VDJ] Dropping cached index for {}", path.display());
+        }
+        present
     });
+}
 
-    // println!("[VDJ] Database indexed: {} tracks", song_map.len());
-    Ok(song_map)
+/// Forget the last discovery scan so the next lookup rescans
+fn invalidate_discovered_databases() {
+    if let Ok(mut cached) = DISCOVERED_DATABASES.write() {
+        *cached = None;
+    }
+}
+
+/// Every discovered database (home, external drives, configured extras) merged for lookups.
+/// Databases that fail to load are skipped so one bad USB stick doesn't break lookups.
+async fn get_all_databases() -> Result<MergedVdjIndex, PikaError> {
+    let discovered = discovered_databases(false).await;
+
+    let mut merged = MergedVdjIndex::default();
+    for database in discovered {
+        match get_cached_database(Some(database.path.clone()), None).await {
+            Ok(index) => merged.push(database.path, index),
+            Err(e) => eprintln!("[VDJ] Skipping database {}: {}", database.path.display(), e),
+        }
+    }
+
+    if merged.is_empty() {
+        return Err(PikaError::not_found("VirtualDJ database.xml not found", None));
+    }
+    Ok(merged)
 }
 
 // Output type that matches what the frontend expects
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Type)]
 pub struct VirtualDJTrack {
     file_path: String,
     artist: Option<String>,
@@ -160,6 +240,57 @@ pub struct VirtualDJTrack {
     bpm: Option<String>,
     key: Option<String>,
     duration: Option<i32>,
+    album: Option<String>,
+    genre: Option<String>,
+    year: Option<i32>,
+    track_number: Option<String>,
+    /// VDJ tag flag bitmask
+    flag: Option<u32>,
+    play_count: Option<u32>,
+    /// Unix timestamp of when VDJ first saw the file
+    first_seen: Option<i64>,
+    /// Star rating, 1-5. Empty for VirtualDJ and Serato imports
+    rating: Option<u8>,
+    /// Cue points, loops, beatgrid anchors and automix markers
+    pois: Vec<VirtualDJPoint>,
+}
+
+/// Structured form of a VDJ `<Poi>` element
+#[derive(Debug, Serialize, Type)]
+pub struct VirtualDJPoint {
+    /// "cue", "loop", "beatgrid", "automix", "remix", ...
+    kind: String,
+    name: Option<String>,
+    /// Position in seconds
+    position: Option<f64>,
+    /// Hotcue / slot number
+    number: Option<u32>,
+    /// Loop length (loops only)
+    size: Option<f64>,
+    /// Automix point name, e.g. "realStart" / "realEnd"
+    point: Option<String>,
+    color: Option<String>,
+    /// Actual BPM at a beatgrid anchor (converted from VDJ's beat period)
+    bpm: Option<f64>,
+}
+
+impl From<&VirtualDJPoi> for VirtualDJPoint {
+    fn from(poi: &VirtualDJPoi) -> Self {
+        let parse_f64 = |v: &Option<String>| v.as_ref().and_then(|s| s.trim().parse::<f64>().ok());
+
+        VirtualDJPoint {
+            kind: poi._type.clone().unwrap_or_else(|| "cue".to_string()),
+            name: poi.name.clone(),
+            position: parse_f64(&poi.pos),
+            number: poi.num.as_ref().and_then(|n| n.trim().parse().ok()),
+            size: parse_f64(&poi.size),
+            point: poi.point.clone(),
+            color: poi.color.clone(),
+            bpm: parse_f64(&poi.bpm)
+                .and_then(convert_virtualdj_bpm_f64)
+                .and_then(|b| b.parse().ok()),
+        }
+    }
 }
 
 /// Convert VirtualDJ BPM format to actual BPM
@@ -183,8 +314,8 @@ fn convert_virtualdj_bpm_f64(beat_period: f64) -> Option<String> {
     Some(format!("{:.1}", actual_bpm))
 }
 
-impl From<VirtualDJSong> for VirtualDJTrack {
-    fn from(song: VirtualDJSong) -> Self {
+impl From<&VirtualDJSong> for VirtualDJTrack {
+    fn from(song: &VirtualDJSong) -> Self {
         // Convert BPM from VirtualDJ format
         let bpm = song.scan.as_ref()
             .and_then(|s| s.bpm.as_ref())
@@ -199,7 +330,9 @@ impl From<VirtualDJSong> for VirtualDJTrack {
             
         if duration_secs <= 0.0 {
             // Secondary fallback: check Pois for 'realEnd' or the last point
-            if let Some(poi) = song.pois.iter().find(|p| p.name.as_deref() == Some("realEnd")) {
+            if let Some(poi) = song.pois.iter().find(|p| {
+                p.name.as_deref() == Some("realEnd") || p.point.as_deref() == Some("realEnd")
+            }) {
                 duration_secs = poi.pos.as_ref().and_then(|p| p.parse::<f64>().ok()).unwrap_or(0.0);
             } else if let Some(last_poi) = song.pois.last() {
                 // Last ditch effort: use the position of the last POI
@@ -210,142 +343,663 @@ impl From<VirtualDJSong> for VirtualDJTrack {
         let duration = if duration_secs > 0.1 { Some(duration_secs.round() as i32) } else { None };
         
         if duration.is_none() {
-            println!("[VDJ] Warning: No duration found for track: {}", song.file_path);
+            eprintln!("[VDJ] Warning: No duration found for track: {}", song.file_path);
         }
 
+        let tags = song.tags.as_ref();
+        let infos = song.infos.as_ref();
+
         VirtualDJTrack {
-            file_path: song.file_path,
-            artist: song.tags.as_ref().and_then(|t| t.author.clone()),
-            title: song.tags.as_ref().and_then(|t| t.title.clone()),
+            artist: tags.and_then(|t| t.author.clone()),
+            title: tags.and_then(|t| t.title.clone()),
             bpm,
             key: song.scan.as_ref().and_then(|s| s.key.clone()),
             duration,
+            album: tags.and_then(|t| t.album.clone()),
+            genre: tags.and_then(|t| t.genre.clone()),
+            year: tags.and_then(|t| t.year.as_ref()).and_then(|y| y.trim().parse().ok()),
+            track_number: tags.and_then(|t| t.track_number.clone()),
+            flag: tags.and_then(|t| t.flag.as_ref()).and_then(|f| f.trim().parse().ok()),
+            play_count: infos.and_then(|i| i.play_count.as_ref()).and_then(|c| c.trim().parse().ok()),
+            first_seen: infos.and_then(|i| i.first_seen.as_ref()).and_then(|f| f.trim().parse().ok()),
+            rating: None,
+            pois: song.pois.iter().map(VirtualDJPoint::from).collect(),
+            file_path: song.file_path.clone(),
         }
     }
 }
 
+/// Import a VDJ library. Emits `vdj://import-progress` while parsing;
+/// `cancel_virtualdj_import` aborts it.
+#[cfg(feature = "app")]
 #[tauri::command]
-async fn import_virtualdj_library(xml_path: String) -> Result<Vec<VirtualDJTrack>, String> {
+#[specta::specta]
+async fn import_virtualdj_library(app: tauri::AppHandle, xml_path: String) -> Result<Vec<VirtualDJTrack>, PikaError> {
     let path = PathBuf::from(xml_path);
-    let song_map = get_cached_database(Some(path)).await?;
+    IMPORT_CANCELLED.store(false, Ordering::Relaxed);
+    let progress: ImportProgressFn = Box::new(move |update| {
+        let _ = app.emit(IMPORT_PROGRESS_EVENT, &update);
+    });
+    let index = get_cached_database(Some(path), Some(progress)).await?;
     
     // Convert VirtualDJSong to VirtualDJTrack
-    let tracks: Vec<VirtualDJTrack> = song_map.into_values().map(|s| s.into()).collect();
+    let tracks: Vec<VirtualDJTrack> = index.songs().map(VirtualDJTrack::from).collect();
     
     Ok(tracks)
 }
 
+/// Abort an in-flight `import_virtualdj_library` parse
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+fn cancel_virtualdj_import() {
+    IMPORT_CANCELLED.store(true, Ordering::Relaxed);
+}
+
+/// Rekordbox and Traktor store ratings as 0-255 in steps of 51; 0 means unrated
+fn stars_from_255(rating: &Option<String>) -> Option<u8> {
+    rating.as_ref()
+        .and_then(|r| r.trim().parse::<u32>().ok())
+        .map(|r| ((r + 25) / 51).min(5) as u8)
+        .filter(|r| *r > 0)
+}
+
+/// Unix timestamp (midnight UTC) of a date string such as "2023-05-01"
+fn date_timestamp(date: &Option<String>, format: &str) -> Option<i64> {
+    date.as_ref()
+        .and_then(|d| chrono::NaiveDate::parse_from_str(d.trim(), format).ok())
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|d| d.and_utc().timestamp())
+}
+
+impl From<&rekordbox::RekordboxTrack> for VirtualDJTrack {
+    fn from(track: &rekordbox::RekordboxTrack) -> Self {
+        let parse_f64 = |v: &Option<String>| v.as_ref().and_then(|s| s.trim().parse::<f64>().ok());
+
+        let beatgrid = track.tempos.iter().map(|tempo| VirtualDJPoint {
+            kind: "beatgrid".to_string(),
+            name: None,
+            position: parse_f64(&tempo.inizio),
+            number: None,
+            size: None,
+            point: None,
+            color: None,
+            bpm: parse_f64(&tempo.bpm),
+        });
+        let marks = track.position_marks.iter().map(|mark| {
+            let start = parse_f64(&mark.start);
+            let kind = match mark.mark_type.as_deref().map(str::trim) {
+                Some("1") => "fadein",
+                Some("2") => "fadeout",
+                Some("3") => "load",
+                Some("4") => "loop",
+                _ => "cue",
+            };
+            let rgb = [&mark.red, &mark.green, &mark.blue].map(|c| c.as_ref().and_then(|v| v.trim().parse::<u8>().ok()));
+            VirtualDJPoint {
+                kind: kind.to_string(),
+                name: mark.name.clone().filter(|n| !n.is_empty()),
+                position: start,
+                // Rekordbox hot cues are 0-based (A = 0), memory cues are -1
+                number: mark.num.as_ref()
+                    .and_then(|n| n.trim().parse::<i32>().ok())
+                    .filter(|n| *n >= 0)
+                    .map(|n| n as u32 + 1),
+                size: parse_f64(&mark.end).zip(start).map(|(end, start)| end - start),
+                point: None,
+                color: match rgb {
+                    [Some(r), Some(g), Some(b)] => Some(format!("#{:02X}{:02X}{:02X}", r, g, b)),
+                    _ => None,
+                },
+                bpm: None,
+            }
+        });
+
+        VirtualDJTrack {
+            file_path: track.file_path.clone(),
+            artist: track.artist.clone().filter(|a| !a.is_empty()),
+            title: track.name.clone().filter(|t| !t.is_empty()),
+            // AverageBpm is already in BPM, unlike VDJ's beat period
+            bpm: parse_f64(&track.average_bpm).filter(|b| *b > 0.0).map(|b| format!("{:.1}", b)),
+            key: track.tonality.clone().filter(|k| !k.is_empty()),
+            duration: parse_f64(&track.total_time).filter(|d| *d > 0.0).map(|d| d.round() as i32),
+            album: track.album.clone().filter(|a| !a.is_empty()),
+            genre: track.genre.clone().filter(|g| !g.is_empty()),
+            year: track.year.as_ref().and_then(|y| y.trim().parse().ok()).filter(|y| *y > 0),
+            track_number: track.track_number.clone().filter(|n| !n.is_empty() && n != "0"),
+            flag: None,
+            play_count: track.play_count.as_ref().and_then(|c| c.trim().parse().ok()),
+            first_seen: date_timestamp(&track.date_added, "%Y-%m-%d"),
+            rating: stars_from_255(&track.rating),
+            pois: beatgrid.chain(marks).collect(),
+        }
+    }
+}
+
+impl From<&traktor::TraktorEntry> for VirtualDJTrack {
+    fn from(entry: &traktor::TraktorEntry) -> Self {
+        let parse_f64 = |v: &Option<String>| v.as_ref().and_then(|s| s.trim().parse::<f64>().ok());
+        let bpm = parse_f64(&entry.bpm).filter(|b| *b > 0.0);
+
+        let pois = entry.cues.iter().map(|cue| {
+            // CUE_V2 positions and lengths are in milliseconds
+            let kind = match cue.cue_type.as_deref().map(str::trim) {
+                Some("1") => "fadein",
+                Some("2") => "fadeout",
+                Some("3") => "load",
+                Some("4") => "beatgrid",
+                Some("5") => "loop",
+                _ => "cue",
+            };
+            VirtualDJPoint {
+                kind: kind.to_string(),
+                // Traktor names unnamed cues "n.n."
+                name: cue.name.clone().filter(|n| !n.is_empty() && n != "n.n." && n != "AutoGrid"),
+                position: parse_f64(&cue.start).map(|ms| ms / 1000.0),
+                // Traktor hot cues are 0-based, -1 = not assigned
+                number: cue.hotcue.as_ref()
+                    .and_then(|n| n.trim().parse::<i32>().ok())
+                    .filter(|n| *n >= 0)
+                    .map(|n| n as u32 + 1),
+                size: parse_f64(&cue.len).filter(|l| *l > 0.0).map(|ms| ms / 1000.0),
+                point: None,
+                color: None,
+                bpm: if kind == "beatgrid" { bpm } else { None },
+            }
+        });
+
+        VirtualDJTrack {
+            file_path: entry.file_path.clone(),
+            artist: entry.artist.clone().filter(|a| !a.is_empty()),
+            title: entry.title.clone().filter(|t| !t.is_empty()),
+            bpm: bpm.map(|b| format!("{:.1}", b)),
+            key: entry.key_name(),
+            duration: parse_f64(&entry.playtime_float)
+                .or_else(|| parse_f64(&entry.playtime))
+                .filter(|d| *d > 0.0)
+                .map(|d| d.round() as i32),
+            album: entry.album.clone().filter(|a| !a.is_empty()),
+            genre: entry.genre.clone().filter(|g| !g.is_empty()),
+            year: entry.release_date.as_ref()
+                .and_then(|d| d.split('/').next())
+                .and_then(|y| y.trim().parse().ok())
+                .filter(|y| *y > 0),
+            track_number: entry.track_number.clone().filter(|n| !n.is_empty()),
+            flag: None,
+            play_count: entry.playcount.as_ref().and_then(|c| c.trim().parse().ok()),
+            first_seen: date_timestamp(&entry.import_date, "%Y/%m/%d"),
+            rating: stars_from_255(&entry.ranking),
+            pois: pois.collect(),
+        }
+    }
+}
+
+impl From<&serato::SeratoTrack> for VirtualDJTrack {
+    fn from(track: &serato::SeratoTrack) -> Self {
+        // Serato shows lengths as "mm:ss.xx"
+        let duration = track.length.as_ref().and_then(|len| {
+            let (minutes, seconds) = len.trim().split_once(':')?;
+            Some(minutes.parse::<f64>().ok()? * 60.0 + seconds.parse::<f64>().ok()?)
+        });
+
+        VirtualDJTrack {
+            file_path: track.file_path.clone(),
+            artist: track.artist.clone().filter(|a| !a.is_empty()),
+            title: track.title.clone().filter(|t| !t.is_empty()),
+            bpm: track.bpm.as_ref()
+                .and_then(|b| b.trim().parse::<f64>().ok())
+                .filter(|b| *b > 0.0)
+                .map(|b| format!("{:.1}", b)),
+            key: track.key.clone().filter(|k| !k.is_empty()),
+            duration: duration.filter(|d| *d > 0.0).map(|d| d.round() as i32),
+            album: track.album.clone().filter(|a| !a.is_empty()),
+            genre: track.genre.clone().filter(|g| !g.is_empty()),
+            year: track.year.as_ref().and_then(|y| y.trim().parse().ok()).filter(|y| *y > 0),
+            track_number: None,
+            flag: None,
+            play_count: None,
+            first_seen: track.date_added.map(i64::from),
+            rating: None,
+            pois: Vec::new(),
+        }
+    }
+}
+
+impl From<&engine_dj::EngineTrack> for VirtualDJTrack {
+    fn from(track: &engine_dj::EngineTrack) -> Self {
+        let pois = track.cues.iter().map(|cue| VirtualDJPoint {
+            kind: if cue.is_loop { "loop" } else { "cue" }.to_string(),
+            name: cue.label.clone(),
+            position: Some(cue.start),
+            number: Some(cue.number),
+            size: cue.end.map(|end| end - cue.start),
+            point: None,
+            color: cue.color.clone(),
+            bpm: None,
+        });
+
+        VirtualDJTrack {
+            file_path: track.file_path.clone(),
+            artist: track.artist.clone().filter(|a| !a.is_empty()),
+            title: track.title.clone().filter(|t| !t.is_empty()),
+            bpm: track.bpm.filter(|b| *b > 0.0).map(|b| format!("{:.1}", b)),
+            key: track.key.and_then(keys::from_engine).map(str::to_string),
+            duration: track.length.filter(|d| *d > 0.0).map(|d| d.round() as i32),
+            album: track.album.clone().filter(|a| !a.is_empty()),
+            genre: track.genre.clone().filter(|g| !g.is_empty()),
+            year: track.year.and_then(|y| i32::try_from(y).ok()).filter(|y| *y > 0),
+            track_number: None,
+            flag: None,
+            play_count: None,
+            first_seen: track.date_added,
+            // 0-100, 20 per star
+            rating: track.rating.map(|r| (r / 20).clamp(0, 5) as u8).filter(|r| *r > 0),
+            pois: pois.collect(),
+        }
+    }
+}
+
+impl From<&mixxx::MixxxTrack> for VirtualDJTrack {
+    fn from(track: &mixxx::MixxxTrack) -> Self {
+        // The key text follows the user's notation setting (names, Camelot, Open Key);
+        // key_id is the fallback when the text is empty or unrecognised
+        let key = track.key.as_deref().and_then(keys::parse)
+            .or_else(|| track.key_id.and_then(keys::from_mixxx_id))
+            .map(str::to_string)
+            .or_else(|| track.key.clone().filter(|k| !k.trim().is_empty()));
+
+        let pois = track.cues.iter().map(|cue| VirtualDJPoint {
+            kind: match cue.cue_type {
+                4 => "loop",
+                5 => "jump",
+                6 => "intro",
+                7 => "outro",
+                _ => "cue",
+            }.to_string(),
+            name: cue.label.clone().or_else(|| (cue.cue_type == 2).then(|| "Main cue".to_string())),
+            position: Some(cue.position),
+            number: u32::try_from(cue.hotcue).ok().map(|n| n + 1),
+            size: cue.length,
+            point: None,
+            color: cue.color.map(|c| format!("#{:06X}", c & 0xFFFFFF)),
+            bpm: None,
+        });
+
+        VirtualDJTrack {
+            file_path: track.file_path.clone(),
+            artist: track.artist.clone().filter(|a| !a.is_empty()),
+            title: track.title.clone().filter(|t| !t.is_empty()),
+            bpm: track.bpm.filter(|b| *b > 0.0).map(|b| format!("{:.1}", b)),
+            key,
+            duration: track.duration.filter(|d| *d > 0.0).map(|d| d.round() as i32),
+            album: track.album.clone().filter(|a| !a.is_empty()),
+            genre: track.genre.clone().filter(|g| !g.is_empty()),
+            year: track.year.as_ref()
+                .and_then(|y| y.trim().get(..4))
+                .and_then(|y| y.parse().ok())
+                .filter(|y| *y > 0),
+            track_number: track.track_number.clone().filter(|n| !n.is_empty()),
+            flag: None,
+            play_count: track.times_played.and_then(|c| u32::try_from(c).ok()),
+            first_seen: track.datetime_added.as_ref().and_then(|d| {
+                chrono::NaiveDateTime::parse_from_str(d.trim(), "%Y-%m-%d %H:%M:%S")
+                    .map(|dt| dt.and_utc().timestamp())
+                    .or_else(|_| chrono::DateTime::parse_from_rfc3339(d.trim()).map(|dt| dt.timestamp()))
+                    .ok()
+            }),
+            rating: track.rating.and_then(|r| u8::try_from(r).ok()).filter(|r| (1..=5).contains(r)),
+            pois: pois.collect(),
+        }
+    }
+}
+
+/// A folder or playlist from another DJ app's library
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct LibraryPlaylist {
+    name: String,
+    is_folder: bool,
+    /// Sub-folders and playlists
+    children: Vec<LibraryPlaylist>,
+    /// File paths of the playlist's tracks, in order.
+    /// Usually empty for folders, but a Serato parent crate has tracks of its own.
+    track_paths: Vec<String>,
+}
+
+/// A Rekordbox / Traktor / Serato import: tracks in the same shape as a VDJ import, plus the playlist tree
+#[derive(Debug, Serialize, Type)]
+pub struct ImportedLibrary {
+    tracks: Vec<VirtualDJTrack>,
+    /// Top-level folders and playlists, to become Pika saved sets
+    playlists: Vec<LibraryPlaylist>,
+}
+
+/// Import a Rekordbox library from its XML export (File > Export Collection in xml format)
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+async fn import_rekordbox_library(xml_path: String) -> Result<ImportedLibrary, PikaError> {
+    let path = PathBuf::from(xml_path);
+    let collection = tokio::task::spawn_blocking(move || rekordbox::load_collection(&path))
+        .await
+        .map_err(|e| PikaError::internal(format!("Rekordbox import task failed: {}", e)))??;
+
+    Ok(ImportedLibrary {
+        tracks: collection.tracks.iter().map(VirtualDJTrack::from).collect(),
+        playlists: collection.playlists,
+    })
+}
+
+/// Import a Traktor library from its collection.nml
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+async fn import_traktor_library(nml_path: String) -> Result<ImportedLibrary, PikaError> {
+    let path = PathBuf::from(nml_path);
+    let collection = tokio::task::spawn_blocking(move || traktor::load_collection(&path))
+        .await
+        .map_err(|e| PikaError::internal(format!("Traktor import task failed: {}", e)))??;
+
+    Ok(ImportedLibrary {
+        tracks: collection.entries.iter().map(VirtualDJTrack::from).collect(),
+        playlists: collection.playlists,
+    })
+}
+
+/// Import a Serato library. `serato_path` is the `_Serato_` folder (or its database V2 file);
+/// crates come back as playlists, with sub-crates nested under their parent.
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+async fn import_serato_library(serato_path: String) -> Result<ImportedLibrary, PikaError> {
+    let mut path = PathBuf::from(serato_path);
+    if path.is_file() {
+        path.pop();
+    }
+    let library = tokio::task::spawn_blocking(move || serato::load_library(&path))
+        .await
+        .map_err(|e| PikaError::internal(format!("Serato import task failed: {}", e)))??;
+
+    Ok(ImportedLibrary {
+        tracks: library.tracks.iter().map(VirtualDJTrack::from).collect(),
+        playlists: library.crates,
+    })
+}
+
+/// Import an Engine DJ library. `db_path` may be m.db, its Database2 folder or the Engine Library folder.
+/// Unsupported schema versions come back as `invalid_format`.
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+async fn import_engine_dj_library(db_path: String) -> Result<ImportedLibrary, PikaError> {
+    let path = engine_dj::resolve_database_path(&PathBuf::from(db_path));
+    let library = tokio::task::spawn_blocking(move || {
+        engine_dj::load_library(&path).map_err(|e| PikaError::from(e).in_file(&path))
+    })
+    .await
+    .map_err(|e| PikaError::internal(format!("Engine DJ import task failed: {}", e)))??;
+
+    println!("[Engine DJ] Imported {} tracks (schema {})", library.tracks.len(), library.schema_version);
+    Ok(ImportedLibrary {
+        tracks: library.tracks.iter().map(VirtualDJTrack::from).collect(),
+        playlists: library.playlists,
+    })
+}
+
+/// Import a Mixxx library. `db_path` may be mixxxdb.sqlite or the Mixxx settings folder.
+/// Unsupported schema versions come back as `invalid_format`.
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+async fn import_mixxx_library(db_path: String) -> Result<ImportedLibrary, PikaError> {
+    let path = mixxx::resolve_database_path(&PathBuf::from(db_path));
+    let library = tokio::task::spawn_blocking(move || {
+        mixxx::load_library(&path).map_err(|e| PikaError::from(e).in_file(&path))
+    })
+    .await
+    .map_err(|e| PikaError::internal(format!("Mixxx import task failed: {}", e)))??;
+
+    println!("[Mixxx] Imported {} tracks (schema {})", library.tracks.len(), library.schema_version);
+    Ok(ImportedLibrary {
+        tracks: library.tracks.iter().map(VirtualDJTrack::from).collect(),
+        playlists: library.playlists,
+    })
+}
+
 /// Read the VirtualDJ history file for the current day
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone, Type)]
 pub struct HistoryTrack {
     artist: String,
     title: String,
     file_path: String,
     timestamp: u64,
+    remix: Option<String>,
+    /// Track length in seconds
+    song_length: Option<f64>,
+    file_size: Option<u64>,
+    /// Wall-clock play time as written by VDJ
+    time: Option<String>,
+    /// #EXTVDJ tags without a dedicated field
+    extras: HashMap<String, String>,
 }
 
-/// Read ALL entries from the VirtualDJ history file (not just the last one)
-#[tauri::command]
-fn read_virtualdj_history_full(
-    custom_path: Option<String>,
-    max_entries: Option<usize>
-) -> Result<Vec<HistoryTrack>, String> {
-    let history_path = if let Some(ref path_str) = custom_path {
-        if path_str != "auto" && !path_str.is_empty() {
-            let custom = std::path::PathBuf::from(path_str);
-            if custom.exists() {
-                custom
-            } else {
-                find_latest_history_file()?
+/// Parse one #EXTVDJ line plus the file path line that follows it
+fn parse_history_entry(ext_line: &str, file_path: &str) -> Option<HistoryTrack> {
+    let entry = extvdj::ExtVdjEntry::parse(ext_line)?;
+
+    Some(HistoryTrack {
+        artist: entry.artist.unwrap_or_else(|| "Unknown".to_string()),
+        title: entry.title.unwrap_or_else(|| "Unknown".to_string()),
+        file_path: file_path.to_string(),
+        timestamp: entry.last_play_time.unwrap_or(0),
+        remix: entry.remix,
+        song_length: entry.song_length,
+        file_size: entry.file_size,
+        time: entry.time,
+        extras: entry.extras,
+    })
+}
+
+/// Parse every complete entry in a chunk of history bytes.
+/// Returns the entries and how many bytes were fully consumed; an unfinished
+/// trailing line or an #EXTVDJ line still waiting for its path is left unconsumed.
+fn parse_history_entries(buf: &[u8]) -> (Vec<HistoryTrack>, usize) {
+    let mut tracks = Vec::new();
+    let mut consumed = 0;
+    let mut pos = 0;
+    let mut pending_ext: Option<String> = None;
+
+    while let Some(newline) = buf[pos..].iter().position(|&b| b == b'\n') {
+        let next = pos + newline + 1;
+        let raw = String::from_utf8_lossy(&buf[pos..pos + newline]);
+        let line = raw.trim_start_matches('\u{feff}').trim();
+
+        if line.starts_with("#EXTVDJ:") {
+            // Entries may be split across reads - don't consume until the path arrives
+            pending_ext = Some(line.to_string());
+        } else if line.is_empty() || line.starts_with('#') {
+            if pending_ext.is_none() {
+                consumed = next;
             }
         } else {
-            find_latest_history_file()?
+            if let Some(ext_line) = pending_ext.take() {
+                if let Some(track) = parse_history_entry(&ext_line, line) {
+                    tracks.push(track);
+                }
+            }
+            consumed = next;
         }
-    } else {
-        find_latest_history_file()?
+        pos = next;
+    }
+
+    (tracks, consumed)
+}
+
+/// Stable identity of a history file so a replaced file is not mistaken for an appended one
+/// (inode on Unix, creation time elsewhere)
+fn history_file_id(metadata: &std::fs::Metadata) -> Option<u64> {
+    #[cfg(unix)]
+    let id = {
+        use std::os::unix::fs::MetadataExt;
+        Some(metadata.ino())
     };
-    
-    let content = std::fs::read_to_string(&history_path)
-        .map_err(|e| format!("Failed to read history: {}", e))?;
-    
-    let mut tracks = Vec::new();
-    let lines: Vec<&str> = content.lines().collect();
-    
-    // Process in reverse (most recent first), but pairs of lines
-    let mut i = lines.len();
-    while i >= 2 {
-        i -= 2;
-        let ext_line = lines[i];
-        let file_path_line = lines[i + 1];
-        
-        if !ext_line.starts_with("#EXTVDJ:") {
-            continue;
+    #[cfg(not(unix))]
+    let id = metadata
+        .created()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64);
+    id.map(js_safe_id)
+}
+
+/// Keep an id within the 53 bits a JS number holds exactly. The cursor round-trips
+/// through the webview, and a rounded id would look like a replaced file on every read.
+fn js_safe_id(id: u64) -> u64 {
+    id & ((1 << 53) - 1)
+}
+
+/// Position in a VDJ history file, so reads only return entries appended since last time.
+/// Serializable so the frontend can persist it and resume after an app restart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct HistoryCursor {
+    path: PathBuf,
+    file_id: Option<u64>,
+    size: u64,
+    byte_offset: u64,
+}
+
+impl HistoryCursor {
+    /// Cursor at the start of the file (the next read returns every entry)
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            file_id: None,
+            size: 0,
+            byte_offset: 0,
         }
-        
-        let extract_tag = |content: &str, start_tag: &str, end_tag: &str| -> Option<String> {
-            let start_idx = content.find(start_tag)? + start_tag.len();
-            let end_idx = content.find(end_tag)?;
-            if start_idx >= end_idx { return None; }
-            Some(content[start_idx..end_idx].to_string())
-        };
-        
-        let artist = extract_tag(ext_line, "<artist>", "</artist>")
-            .unwrap_or_else(|| "Unknown".to_string());
-        let title = extract_tag(ext_line, "<title>", "</title>")
-            .unwrap_or_else(|| "Unknown".to_string());
-        let timestamp: u64 = extract_tag(ext_line, "<lastplaytime>", "</lastplaytime>")
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(0);
-        
-        tracks.push(HistoryTrack {
-            artist,
-            title,
-            file_path: file_path_line.to_string(),
-            timestamp,
-        });
-        
-        // Respect max_entries limit (default 100)
-        if let Some(max) = max_entries {
-            if tracks.len() >= max {
-                break;
-            }
+    }
+
+    /// Cursor at the current end of the file (the next read returns only new entries)
+    pub fn at_end(path: PathBuf) -> Self {
+        let mut cursor = Self::new(path);
+        if let Ok(metadata) = std::fs::metadata(&cursor.path) {
+            cursor.file_id = history_file_id(&metadata);
+            cursor.size = metadata.len();
+            cursor.byte_offset = metadata.len();
         }
+        cursor
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Read entries appended since the cursor position and advance past them.
+    /// Starts over from the top if the file was truncated or replaced.
+    pub fn read_new(&mut self) -> Result<Vec<HistoryTrack>, PikaError> {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let mut file = std::fs::File::open(&self.path)
+            .map_err(|e| PikaError::io("Failed to open history", &self.path, e))?;
+        let metadata = file
+            .metadata()
+            .map_err(|e| PikaError::io("Failed to get history metadata", &self.path, e))?;
+        let len = metadata.len();
+        let file_id = history_file_id(&metadata);
+
+        let replaced = self.file_id.is_some() && file_id != self.file_id;
+        if replaced || len < self.byte_offset {
+            self.byte_offset = 0;
+        }
+        self.file_id = file_id;
+        self.size = len;
+
+        if len == self.byte_offset {
+            return Ok(Vec::new());
+        }
+
+        file.seek(SeekFrom::Start(self.byte_offset))
+            .map_err(|e| PikaError::io("Failed to seek history", &self.path, e))?;
+        let mut appended = Vec::with_capacity((len - self.byte_offset) as usize);
+        file.take(len - self.byte_offset)
+            .read_to_end(&mut appended)
+            .map_err(|e| PikaError::io("Failed to read history", &self.path, e))?;
+
+        let (tracks, consumed) = parse_history_entries(&appended);
+        self.byte_offset += consumed as u64;
+        Ok(tracks)
     }
-    
-    // Return in chronological order (oldest first)
-    tracks.reverse();
-    
-    Ok(tracks)
 }
 
-#[tauri::command]
-fn read_virtualdj_history(custom_path: Option<String>) -> Result<Option<HistoryTrack>, String> {
-    // If custom path is provided and not "auto", use it directly
-    let history_path = if let Some(ref path_str) = custom_path {
-        if path_str != "auto" && !path_str.is_empty() {
-            let custom = std::path::PathBuf::from(path_str);
+/// Resolve a custom history path setting ("auto", empty or missing file = auto-detect)
+fn resolve_history_path(custom_path: Option<&str>) -> Result<PathBuf, PikaError> {
+    match custom_path {
+        Some(path_str) if path_str != "auto" && !path_str.is_empty() => {
+            let custom = PathBuf::from(path_str);
             if custom.exists() {
-                println!("[VDJ] Using custom history path: {:?}", custom);
-                custom
+                Ok(custom)
             } else {
                 println!("[VDJ] Custom path not found, falling back to auto-detect: {:?}", custom);
-                find_latest_history_file()?
+                find_latest_history_file()
             }
-        } else {
-            find_latest_history_file()?
         }
-    } else {
-        find_latest_history_file()?
-    };
-    
-    // Read the file
-    let content = match std::fs::read_to_string(&history_path) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("[VDJ History] Failed to read {}: {}", history_path.display(), e);
-            return Ok(None);
+        _ => find_latest_history_file(),
+    }
+}
+
+/// Read ALL entries from the VirtualDJ history file (not just the last one)
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+fn read_virtualdj_history_full(
+    custom_path: Option<String>,
+    max_entries: Option<usize>
+) -> Result<Vec<HistoryTrack>, PikaError> {
+    let history_path = resolve_history_path(custom_path.as_deref())?;
+
+    let content = std::fs::read(&history_path)
+        .map_err(|e| PikaError::io("Failed to read history", &history_path, e))?;
+
+    // Parse forwards so entries stay aligned regardless of blank or extra lines,
+    // then keep only the most recent `max_entries` (in chronological order)
+    let (mut tracks, _) = parse_history_entries(&content);
+    if let Some(max) = max_entries {
+        if tracks.len() > max {
+            tracks.drain(..tracks.len() - max);
+        }
+    }
+
+    Ok(tracks)
+}
+
+/// New history entries plus the cursor to pass on the next call
+#[derive(Debug, Serialize, Type)]
+pub struct HistoryDelta {
+    entries: Vec<HistoryTrack>,
+    cursor: HistoryCursor,
+}
+
+/// Read only the history entries appended since `cursor`.
+/// Without a cursor the whole current file is returned. When VDJ has rolled over to a
+/// new day's file, the rest of the old file is drained before moving to the new one.
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+fn read_virtualdj_history_since(
+    custom_path: Option<String>,
+    cursor: Option<HistoryCursor>,
+) -> Result<HistoryDelta, PikaError> {
+    let current_path = resolve_history_path(custom_path.as_deref())?;
+
+    let mut entries = Vec::new();
+    let mut cursor = match cursor {
+        Some(mut previous) if previous.path != current_path => {
+            if previous.path.exists() {
+                entries.extend(previous.read_new()?);
+            }
+            HistoryCursor::new(current_path)
         }
+        Some(previous) => previous,
+        None => HistoryCursor::new(current_path),
     };
+    entries.extend(cursor.read_new()?);
+
+    Ok(HistoryDelta { entries, cursor })
+}
+
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+fn read_virtualdj_history(custom_path: Option<String>) -> Result<Option<HistoryTrack>, PikaError> {
+    let history_path = resolve_history_path(custom_path.as_deref())?;
+    read_last_history_entry(&history_path)
+}
+
+/// Parse the last entry of a VDJ history file
+fn read_last_history_entry(history_path: &std::path::Path) -> Result<Option<HistoryTrack>, PikaError> {
+    let content = std::fs::read_to_string(history_path)
+        .map_err(|e| PikaError::io("Failed to read history", history_path, e))?;
     
     // Parse the last entry using reverse iterator to avoid collecting all lines
     // 🛡️ Issue 37 Fix: Use iterator methods instead of collecting lines
@@ -363,155 +1017,473 @@ fn read_virtualdj_history(custom_path: Option<String>) -> Result<Option<HistoryT
         None => return Ok(None),
     };
     
-    if !ext_line.starts_with("#EXTVDJ:") {
-        return Ok(None);
+    Ok(parse_history_entry(ext_line, file_path_line))
+}
+
+/// Event emitted to the webview for every new history entry
+const TRACK_CHANGED_EVENT: &str = "vdj://track-changed";
+
+/// The running history follower, if any (at most one per app)
+static HISTORY_WATCHER: Lazy<std::sync::Mutex<Option<history_watcher::HistoryWatcher>>> =
+    Lazy::new(|| std::sync::Mutex::new(None));
+
+/// Start (or restart) the Rust-side history follower for the configured
+/// now-playing source. New entries are pushed as `vdj://track-changed` events
+/// whichever app is playing; the current last entry is returned so the caller
+/// can seed its state without waiting. `custom_path` pins the VDJ history file.
+#[cfg(feature = "app")]
+#[tauri::command]
+#[specta::specta]
+fn start_history_watcher(
+    app: tauri::AppHandle,
+    custom_path: Option<String>,
+) -> Result<Option<HistoryTrack>, PikaError> {
+    let config = NOW_PLAYING_SOURCE
+        .read()
+        .map_err(|_| PikaError::internal("Now playing source lock poisoned"))?
+        .clone();
+    let mut source = config.build(custom_path)?;
+
+    let mut slot = HISTORY_WATCHER.lock().map_err(|_| PikaError::internal("History watcher lock poisoned"))?;
+    if let Some(previous) = slot.take() {
+        previous.stop();
     }
 
-    let file_path = file_path_line.to_string();
-    
-    // 🛡️ Issue 36 Fix: Avoid format! allocations for known tags
-    let extract_tag = |content: &str, start_tag: &str, end_tag: &str| -> Option<String> {
-        let start_idx = content.find(start_tag)? + start_tag.len();
-        let end_idx = content.find(end_tag)?;
-        if start_idx >= end_idx { return None; }
-        Some(content[start_idx..end_idx].to_string())
-    };
-    
-    let artist = extract_tag(ext_line, "<artist>", "</artist>").unwrap_or_else(|| "Unknown".to_string());
-    let title = extract_tag(ext_line, "<title>", "</title>").unwrap_or_else(|| "Unknown".to_string());
-    let timestamp: u64 = extract_tag(ext_line, "<lastplaytime>", "</lastplaytime>")
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(0);
-    
-    Ok(Some(HistoryTrack {
-        artist,
-        title,
-        file_path,
-        timestamp,
-    }))
+    let current = source.current().unwrap_or_else(|e| {
+        eprintln!("[{} History] {}", source.name(), e);
+        None
+    });
+    let name = source.name();
+    let watcher = history_watcher::HistoryWatcher::start(source, move |track| {
+        if let Err(e) = app.emit(TRACK_CHANGED_EVENT, &track) {
+            eprintln!("[{} Watcher] Failed to emit track change: {}", name, e);
+        }
+    })?;
+    *slot = Some(watcher);
+
+    Ok(current)
 }
 
-/// Find the latest VDJ history file using auto-detection
-fn find_latest_history_file() -> Result<std::path::PathBuf, String> {
-    // Determine home directory securely across OS
-    let home = std::env::var("HOME")
-        .or_else(|_| std::env::var("USERPROFILE"))
-        .map_err(|_| "Could not determine home directory".to_string())?;
-
-    let path = std::path::PathBuf::from(&home);
-
-    // Potential history locations (Priority order)
-    let candidates = vec![
-        // 1. Standard Documents location (Windows & macOS Modern)
-        path.join("Documents").join("VirtualDJ").join("History"),
-        
-        // 2. macOS Legacy / Root location
-        path.join("Library").join("Application Support").join("VirtualDJ").join("History"),
-    ];
+/// Choose which app the history watcher follows. Takes effect on the next
+/// `start_history_watcher`.
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+fn set_now_playing_source(config: now_playing::NowPlayingConfig) -> Result<(), PikaError> {
+    let mut source = NOW_PLAYING_SOURCE
+        .write()
+        .map_err(|_| PikaError::internal("Now playing source lock poisoned"))?;
+    *source = config;
+    Ok(())
+}
 
-    // Find first existing directory
-    let history_dir = candidates.into_iter()
-        .find(|p| p.exists())
-        .ok_or_else(|| "VirtualDJ History folder not found".to_string())?;
+/// Stop the history follower. Safe to call when it is not running.
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+fn stop_history_watcher() -> Result<(), PikaError> {
+    let mut slot = HISTORY_WATCHER.lock().map_err(|_| PikaError::internal("History watcher lock poisoned"))?;
+    if let Some(watcher) = slot.take() {
+        watcher.stop();
+    }
+    Ok(())
+}
+
+/// Find the latest VDJ history file using auto-detection
+fn find_latest_history_file() -> Result<std::path::PathBuf, PikaError> {
+    let history_dir = resolve_vdj_locations()
+        .history_dir
+        .ok_or_else(|| PikaError::not_found("VirtualDJ History folder not found", None))?;
 
-    // println!("[VDJ] Reading history from: {:?}", history_dir);
-    
-    // Find the most recently modified .m3u file
     // Find the most recently modified .m3u file
     // 🛡️ Issue 42 Fix: Use DirEntry::metadata() to avoid re-stating every file
     let history_path = std::fs::read_dir(&history_dir)
-        .map_err(|e| format!("Failed to read history directory: {}", e))?
+        .map_err(|e| PikaError::io("Failed to read history directory", &history_dir, e))?
         .filter_map(|entry| entry.ok())
         .filter(|entry| {
             let path = entry.path();
-            path.is_file() && path.extension().map_or(false, |ext| ext == "m3u")
+            path.is_file() && path.extension().is_some_and(|ext| ext == "m3u")
         })
         .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
         .map(|entry| entry.path())
-        .ok_or_else(|| "No history files found".to_string())?;
-    
+        .ok_or_else(|| PikaError::not_found("No history files found", Some(&history_dir)))?;
+
     Ok(history_path)
 }
 
+/// VDJ's daily history files (named like `2026-01-31.m3u`, including those in
+/// year/month subfolders), oldest first. Files whose name is not a date are skipped.
+fn dated_history_files(history_dir: &std::path::Path) -> Result<Vec<(chrono::NaiveDate, PathBuf)>, PikaError> {
+    let mut files: Vec<_> = history_sessions::history_files(history_dir)?
+        .into_iter()
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_str()?;
+            let date = chrono::NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok()?;
+            Some((date, path))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Rebuild past DJ sessions from every VDJ history file (including year/month
+/// subfolders): entries are merged across midnight and split wherever nothing was
+/// played for longer than the idle gap. Newest first. `history_dir` overrides auto-detection.
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+async fn find_history_sessions(
+    history_dir: Option<String>,
+    options: Option<history_sessions::SessionOptions>,
+) -> Result<Vec<history_sessions::SessionCandidate>, PikaError> {
+    let history_dir = resolve_history_dir(history_dir)?;
+    let options = options.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || history_sessions::find_sessions(&history_dir, &options))
+        .await
+        .map_err(|e| PikaError::internal(format!("History scan task failed: {}", e)))?
+}
+
+/// File → play-time range index behind `query_virtualdj_history`
+static HISTORY_INDEX: Lazy<std::sync::Mutex<history_index::HistoryIndex>> =
+    Lazy::new(|| std::sync::Mutex::new(history_index::HistoryIndex::default()));
+
+/// Page through history across every VDJ history file: entries played between
+/// `from` and `to`, optionally filtered by artist/title, with counts over all matches.
+/// Only files whose play-time range overlaps the query are read.
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+async fn query_virtualdj_history(
+    history_dir: Option<String>,
+    query: history_index::HistoryQuery,
+) -> Result<history_index::HistoryPage, PikaError> {
+    let history_dir = resolve_history_dir(history_dir)?;
+
+    tokio::task::spawn_blocking(move || {
+        let mut index = HISTORY_INDEX
+            .lock()
+            .map_err(|_| PikaError::internal("History index lock poisoned"))?;
+        index.query(&history_dir, &query)
+    })
+    .await
+    .map_err(|e| PikaError::internal(format!("History query task failed: {}", e)))?
+}
+
+/// The given History folder, or VDJ's detected one
+fn resolve_history_dir(history_dir: Option<String>) -> Result<PathBuf, PikaError> {
+    match history_dir.filter(|dir| !dir.is_empty()) {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => resolve_vdj_locations()
+            .history_dir
+            .ok_or_else(|| PikaError::not_found("VirtualDJ History folder not found", None)),
+    }
+}
+
 /// Metadata returned from VDJ database lookup (for ghost tracks)
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Type)]
 pub struct VdjTrackMetadata {
     bpm: Option<f64>,
     key: Option<String>,
     volume: Option<f64>,
+    /// database.xml the track was found in
+    database: String,
 }
 
 /// Find VirtualDJ database.xml location
 fn find_vdj_database_path() -> Option<std::path::PathBuf> {
-    let home = std::env::var("HOME")
-        .or_else(|_| std::env::var("USERPROFILE"))
-        .ok()?;
-    
-    let path = std::path::PathBuf::from(&home);
-    
-    let candidates = vec![
-        // 1. Standard Documents location (Windows & macOS Modern)
-        path.join("Documents").join("VirtualDJ").join("database.xml"),
-        // 2. macOS Legacy location
-        path.join("Library").join("Application Support").join("VirtualDJ").join("database.xml"),
-        // 3. Windows AppData
-        path.join("AppData").join("Local").join("VirtualDJ").join("database.xml"),
-    ];
-    
-    candidates.into_iter().find(|p| p.exists())
+    resolve_vdj_locations().database
+}
+
+/// Resolved VDJ locations. Resolving stats a dozen candidate folders, and every
+/// lookup and history read needs it, so it's kept until the VDJ home setting changes.
+/// Only complete results are kept: VDJ may be installed (or its drive plugged in)
+/// after Pika! starts, and a cached miss would hide it until restart.
+static VDJ_LOCATIONS: Lazy<std::sync::RwLock<Option<vdj_locations::VdjLocations>>> =
+    Lazy::new(|| std::sync::RwLock::new(None));
+
+/// Resolve VDJ's home folder, database and History folder, honouring the Settings override
+fn resolve_vdj_locations() -> vdj_locations::VdjLocations {
+    if let Some(locations) = VDJ_LOCATIONS.read().ok().and_then(|cached| cached.clone()) {
+        return locations;
+    }
+    resolve_vdj_locations_fresh()
+}
+
+/// Resolve without the cache, keeping the result if both database and History were found
+fn resolve_vdj_locations_fresh() -> vdj_locations::VdjLocations {
+    let explicit = VDJ_HOME_OVERRIDE.read().ok().and_then(|p| p.clone());
+    let locations = vdj_locations::VdjLocations::resolve(explicit.as_deref());
+    if let Ok(mut cached) = VDJ_LOCATIONS.write() {
+        let complete = locations.database.is_some() && locations.history_dir.is_some();
+        *cached = complete.then(|| locations.clone());
+    }
+    locations
+}
+
+/// Set (or clear, with None) the VDJ home folder configured in Settings
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+fn set_vdj_home(path: Option<String>) -> Result<(), PikaError> {
+    let mut home = VDJ_HOME_OVERRIDE.write().map_err(|_| PikaError::internal("VDJ home lock poisoned"))?;
+    *home = path.filter(|p| !p.is_empty()).map(PathBuf::from);
+    if let Ok(mut cached) = VDJ_LOCATIONS.write() {
+        *cached = None;
+    }
+    invalidate_discovered_databases();
+    Ok(())
+}
+
+/// Every folder checked for VDJ's home, database and history, with why each was rejected.
+/// Shown in Settings when VirtualDJ can't be found.
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+fn get_vdj_locations() -> vdj_locations::VdjLocations {
+    resolve_vdj_locations_fresh()
 }
 
 /// Lookup track metadata from VDJ database.xml by file path
-/// Used to get BPM/key for tracks not imported into Pika! library
-#[tauri::command]
-async fn lookup_vdj_track_metadata(file_path: String) -> Result<Option<VdjTrackMetadata>, String> {
-    let song_map = get_cached_database(None).await?;
-    
-    // Find matching song by file path (case-insensitive on Windows)
-    let song = if cfg!(target_os = "windows") {
-        // Linear search for case-insensitivity on Windows if map lookup fails
-        // In practice, VirtualDJ usually keeps consistent casing in its own DB,
-        // but it's safer to have a fallback or normalization.
-        song_map.get(&file_path).cloned().or_else(|| {
-            song_map.values().find(|s| s.file_path.eq_ignore_ascii_case(&file_path)).cloned()
-        })
-    } else {
-        song_map.get(&file_path).cloned()
-    };
-    
-    match song {
-        Some(s) => {
-            let bpm = s.scan.as_ref()
-                .and_then(|scan| scan.bpm.as_ref())
-                .and_then(|b| b.parse::<f64>().ok())
-                .and_then(|bp| convert_virtualdj_bpm_f64(bp))
-                .and_then(|bpm_str| bpm_str.parse::<f64>().ok());
-            
-            let key = s.scan.as_ref()
-                .and_then(|scan| scan.key.clone());
-            
-            let volume = s.scan.as_ref()
-                .and_then(|scan| scan.volume.as_ref())
-                .and_then(|v| v.parse::<f64>().ok());
-            
-            // println!("[VDJ] Found metadata: bpm={:?}, key={:?}, volume={:?}", bpm, key, volume);
-            
-            Ok(Some(VdjTrackMetadata { bpm, key, volume }))
-        }
-        None => {
-            // println!("[VDJ] Track not found in database: {}", file_path);
-            Ok(None)
-        }
+/// Used to get BPM/key for tracks not imported into Pika! library.
+/// Falls back to artist + title when the path is unknown (e.g. the file was moved).
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+async fn lookup_vdj_track_metadata(
+    file_path: String,
+    artist: Option<String>,
+    title: Option<String>,
+) -> Result<Option<VdjTrackMetadata>, PikaError> {
+    let databases = get_all_databases().await?;
+    Ok(find_track_metadata(&databases, &file_path, artist.as_deref(), title.as_deref()))
+}
+
+/// BPM, key and volume of a track from the first database that knows it
+fn find_track_metadata(
+    databases: &MergedVdjIndex,
+    file_path: &str,
+    artist: Option<&str>,
+    title: Option<&str>,
+) -> Option<VdjTrackMetadata> {
+    // Exact, separator-normalized and case-folded path lookups are all O(1) per database
+    let (s, database) = databases.get(file_path).or_else(|| match (artist, title) {
+        (Some(artist), Some(title)) => databases.find_by_track(artist, title),
+        _ => None,
+    })?;
+
+    let bpm = s.scan.as_ref()
+        .and_then(|scan| scan.bpm.as_ref())
+        .and_then(|b| b.parse::<f64>().ok())
+        .and_then(convert_virtualdj_bpm_f64)
+        .and_then(|bpm_str| bpm_str.parse::<f64>().ok());
+
+    let key = s.scan.as_ref()
+        .and_then(|scan| scan.key.clone());
+
+    let volume = s.scan.as_ref()
+        .and_then(|scan| scan.volume.as_ref())
+        .and_then(|v| v.parse::<f64>().ok());
+
+    Some(VdjTrackMetadata {
+        bpm,
+        key,
+        volume,
+        database: database.to_string_lossy().into_owned(),
+    })
+}
+
+/// Read BPM, key and descriptive tags embedded in the audio file itself.
+/// Fallback for tracks no DJ database knows about; needs no sidecar.
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+async fn read_audio_tags(file_path: String) -> Result<audio_tags::AudioTagMetadata, PikaError> {
+    tokio::task::spawn_blocking(move || audio_tags::read_tags(std::path::Path::new(&file_path)))
+        .await
+        .map_err(|e| PikaError::internal(format!("Tag read task failed: {}", e)))?
+}
+
+/// Analyze BPM, key, energy and the fingerprint metrics natively, without the sidecar.
+/// Returns the same fields as the sidecar's `/analyze`; failures are reported in `error`.
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+async fn analyze_track(file_path: String) -> analysis::AnalysisResult {
+    tokio::task::spawn_blocking(move || analysis::analyze_file(std::path::Path::new(&file_path)))
+        .await
+        .unwrap_or_else(|e| analysis::AnalysisResult::failed(format!("Analysis task failed: {}", e)))
+}
+
+/// A database.xml found on this machine, for the Settings screen
+#[derive(Debug, Serialize, Type)]
+pub struct VdjDatabaseInfo {
+    path: String,
+    source: vdj_discovery::DatabaseSource,
+    track_count: Option<usize>,
+    /// Why the database could not be read, if it couldn't
+    error: Option<String>,
+}
+
+/// Replace the user-configured extra database.xml paths (files or folders)
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+fn set_vdj_extra_databases(paths: Vec<String>) -> Result<(), PikaError> {
+    let mut extra = EXTRA_DATABASES.write().map_err(|_| PikaError::internal("Extra databases lock poisoned"))?;
+    *extra = paths.into_iter().filter(|p| !p.is_empty()).map(PathBuf::from).collect();
+    invalidate_discovered_databases();
+    Ok(())
+}
+
+/// Rescan for VDJ databases (home, external drives, extras) and list each with its track count.
+/// Later lookups use the result of this scan.
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+async fn list_vdj_databases() -> Result<Vec<VdjDatabaseInfo>, String> {
+    let discovered = discovered_databases(true).await;
+
+    let mut databases = Vec::with_capacity(discovered.len());
+    for database in discovered {
+        let (track_count, error) = match get_cached_database(Some(database.path.clone()), None).await {
+            Ok(index) => (Some(index.len()), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        databases.push(VdjDatabaseInfo {
+            path: database.path.to_string_lossy().into_owned(),
+            source: database.source,
+            track_count,
+            error,
+        });
     }
+    Ok(databases)
+}
+
+/// Write Pika tags, notes and ratings into VirtualDJ's database.xml.
+/// A timestamped backup is taken first; unknown elements and attributes are preserved.
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+async fn write_pika_tags_to_virtualdj(
+    updates: Vec<vdj_writeback::VdjTagUpdate>,
+    options: Option<vdj_writeback::VdjWriteBackOptions>,
+    xml_path: Option<String>,
+) -> Result<vdj_writeback::VdjWriteBackReport, PikaError> {
+    let db_path = match xml_path {
+        Some(path) => PathBuf::from(path),
+        None => find_vdj_database_path()
+            .ok_or_else(|| PikaError::not_found("VirtualDJ database.xml not found", None))?,
+    };
+    let options = options.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || vdj_writeback::write_back(&db_path, &updates, &options))
+        .await
+        .map_err(|e| PikaError::internal(format!("Write-back task failed: {}", e)))?
+}
+
+/// Write BPM, key, energy and Pika tags into the audio files' own tags
+/// (ID3v2, FLAC/Ogg Vorbis comments, MP4 atoms). With `dry_run` only the diff is
+/// returned; otherwise the previous values are journaled for `undo_audio_tag_write`.
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+async fn write_audio_tags(
+    updates: Vec<tag_writeback::AudioTagUpdate>,
+    options: Option<tag_writeback::AudioTagWriteOptions>,
+) -> Result<tag_writeback::AudioTagWriteReport, PikaError> {
+    let options = options.unwrap_or_default();
+    let journal_dir = TAG_JOURNAL_DIR
+        .get()
+        .cloned()
+        .ok_or_else(|| PikaError::internal("Tag journal folder not available"))?;
+
+    tokio::task::spawn_blocking(move || tag_writeback::write_tags(&updates, &options, &journal_dir))
+        .await
+        .map_err(|e| PikaError::internal(format!("Tag write task failed: {}", e)))?
+}
+
+/// Revert an audio tag write using the journal path from its report
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
+async fn undo_audio_tag_write(journal_path: String) -> Result<tag_writeback::AudioTagWriteReport, PikaError> {
+    tokio::task::spawn_blocking(move || tag_writeback::undo(std::path::Path::new(&journal_path)))
+        .await
+        .map_err(|e| PikaError::internal(format!("Tag undo task failed: {}", e)))?
+}
+
+/// Current state of the analysis sidecar (updates arrive as `sidecar://status` events)
+#[cfg(feature = "app")]
+#[tauri::command]
+#[specta::specta]
+fn get_sidecar_status() -> sidecar::SidecarStatus {
+    sidecar::status()
+}
+
+/// Kill and respawn the analysis sidecar, resetting the restart backoff
+#[cfg(feature = "app")]
+#[tauri::command]
+#[specta::specta]
+fn restart_sidecar(app: tauri::AppHandle) -> sidecar::SidecarStatus {
+    sidecar::restart(app);
+    sidecar::status()
+}
+
+/// Queue a track for analysis by the sidecar. A track that is already queued
+/// keeps its job, moved up if the new priority is higher.
+#[cfg(feature = "app")]
+#[tauri::command]
+#[specta::specta]
+fn enqueue_analysis_job(
+    app: tauri::AppHandle,
+    track_id: i64,
+    file_path: String,
+    priority: Option<analysis_queue::JobPriority>,
+) -> analysis_queue::AnalysisJob {
+    let priority = priority.unwrap_or(analysis_queue::JobPriority::Background);
+    analysis_queue::enqueue(&app, track_id, file_path, priority)
+}
+
+/// Cancel a pending or running analysis job; false if it already finished
+#[cfg(feature = "app")]
+#[tauri::command]
+#[specta::specta]
+fn cancel_analysis_job(app: tauri::AppHandle, job_id: u64) -> bool {
+    analysis_queue::cancel(&app, job_id)
+}
+
+/// Cancel every queued and running analysis job
+#[cfg(feature = "app")]
+#[tauri::command]
+#[specta::specta]
+fn clear_analysis_queue(app: tauri::AppHandle) -> analysis_queue::QueueSnapshot {
+    analysis_queue::clear(&app);
+    analysis_queue::snapshot()
+}
+
+/// Stop starting new analysis jobs (running ones finish) or resume
+#[cfg(feature = "app")]
+#[tauri::command]
+#[specta::specta]
+fn set_analysis_paused(paused: bool) -> analysis_queue::QueueSnapshot {
+    analysis_queue::set_paused(paused)
+}
+
+/// Apply the `analysis.cpuPriority` setting to the queue's concurrency
+#[cfg(feature = "app")]
+#[tauri::command]
+#[specta::specta]
+fn set_analysis_cpu_priority(priority: analysis_queue::CpuPriority) -> analysis_queue::QueueSnapshot {
+    analysis_queue::set_cpu_priority(priority)
+}
+
+/// Pending and running analysis jobs
+#[cfg(feature = "app")]
+#[tauri::command]
+#[specta::specta]
+fn get_analysis_queue() -> analysis_queue::QueueSnapshot {
+    analysis_queue::snapshot()
+}
+
+/// Finished analysis jobs whose results haven't been acknowledged yet (e.g.
+/// ones that completed while the window was closed), oldest first
+#[cfg(feature = "app")]
+#[tauri::command]
+#[specta::specta]
+fn get_analysis_results() -> Vec<analysis_queue::JobCompletion> {
+    analysis_queue::results()
+}
+
+/// Mark finished jobs' results as stored so they aren't delivered again.
+/// Returns how many were removed.
+#[cfg(feature = "app")]
+#[tauri::command]
+#[specta::specta]
+fn ack_analysis_results(job_ids: Vec<u64>) -> usize {
+    analysis_queue::ack(&job_ids)
 }
 
 /// Get the local network IP address for LAN sharing
 /// Returns the first non-loopback IPv4 address found
-#[tauri::command]
+#[cfg_attr(feature = "app", tauri::command, specta::specta)]
 fn get_local_ip() -> Option<String> {
     local_ip_address::local_ip().ok().map(|ip| ip.to_string())
 }
 
+#[cfg(feature = "app")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -521,15 +1493,30 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_sql::Builder::default().build())
-        .invoke_handler(tauri::generate_handler![
-            import_virtualdj_library, 
-            read_virtualdj_history,
-            read_virtualdj_history_full,
-            lookup_vdj_track_metadata, 
-            get_local_ip
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .setup(|app| {
+            if let Ok(data_dir) = app.path().app_data_dir() {
+                let _ = SNAPSHOT_DIR.set(data_dir.join("vdj-index"));
+                let _ = TAG_JOURNAL_DIR.set(data_dir.join("tag-journal"));
+                analysis_queue::start(app.handle().clone(), data_dir.join("analysis-queue.json"));
+            }
+            // The analysis sidecar belongs to the app, not to a webview that may reload
+            sidecar::start(app.handle().clone());
+            // Warm the VDJ cache (from the snapshot when possible) before the first lookup
+            tauri::async_runtime::spawn(async {
+                if let Err(e) = get_all_databases().await {
+                    println!("[VDJ] Skipping index preload: {}", e);
+                }
+            });
+            Ok(())
+        })
+        .invoke_handler(bindings::builder().invoke_handler())
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                sidecar::stop();
+            }
+        });
 }
 
 #[cfg(test)]
@@ -544,5 +1531,189 @@ mod tests {
         // 60 / 0.479 = 125.26... -> 125.3
         assert_eq!(convert_virtualdj_bpm("0.479"), Some("125.3".to_string()));
     }
-}
 
+    #[test]
+    fn test_virtualdj_track_keeps_full_metadata() {
+        let xml = r##"<VirtualDJ_Database Version="8.5">
+            <Song FilePath="C:\Music\song.mp3" FileSize="8123456">
+                <Tags Author="Artist" Title="Title" Genre="WCS" Album="Album" TrackNumber="3" Year="2019" Flag="1" />
+                <Infos SongLength="215.4" FirstSeen="1600000000" PlayCount="42" />
+                <Scan Version="801" Bpm="0.5" Key="Am" />
+                <Poi Pos="0.071" Type="beatgrid" Bpm="0.5" />
+                <Poi Name="Drop" Pos="32.5" Num="1" Type="cue" Color="#FF0000" />
+                <Poi Pos="64.0" Type="loop" Size="8" />
+                <Poi Pos="210.2" Type="automix" Point="realEnd" />
+            </Song>
+        </VirtualDJ_Database>"##;
+        let index = vdj_database::parse_database(xml.as_bytes(), xml.len() as u64, None, |_| {}).unwrap();
+        let track = VirtualDJTrack::from(index.songs().next().unwrap());
+
+        assert_eq!(track.bpm.as_deref(), Some("120.0"));
+        assert_eq!(track.duration, Some(215));
+        assert_eq!(track.album.as_deref(), Some("Album"));
+        assert_eq!(track.genre.as_deref(), Some("WCS"));
+        assert_eq!(track.year, Some(2019));
+        assert_eq!(track.track_number.as_deref(), Some("3"));
+        assert_eq!(track.flag, Some(1));
+        assert_eq!(track.play_count, Some(42));
+        assert_eq!(track.first_seen, Some(1600000000));
+
+        assert_eq!(track.pois.len(), 4);
+        assert_eq!(track.pois[0].kind, "beatgrid");
+        assert_eq!(track.pois[0].bpm, Some(120.0));
+        assert_eq!(track.pois[1].name.as_deref(), Some("Drop"));
+        assert_eq!(track.pois[1].number, Some(1));
+        assert_eq!(track.pois[1].position, Some(32.5));
+        assert_eq!(track.pois[2].size, Some(8.0));
+        assert_eq!(track.pois[3].point.as_deref(), Some("realEnd"));
+    }
+
+    #[test]
+    fn test_rekordbox_track_conversion() {
+        let xml = r#"<DJ_PLAYLISTS Version="1.0.0"><COLLECTION Entries="1">
+  <TRACK TrackID="1" Name="Slow Burn" Artist="Kacey" TotalTime="215" AverageBpm="96.00" Tonality="Am"
+         Rating="204" TrackNumber="0" DateAdded="2023-05-01" Location="file://localhost/Users/dj/a.mp3">
+    <TEMPO Inizio="0.025" Bpm="96.00" Metro="4/4" Battito="1"/>
+    <POSITION_MARK Name="" Type="0" Start="12.5" Num="0" Red="40" Green="226" Blue="20"/>
+    <POSITION_MARK Name="" Type="4" Start="30.0" End="38.0" Num="-1"/>
+  </TRACK>
+</COLLECTION></DJ_PLAYLISTS>"#;
+        let collection = rekordbox::parse_collection(xml.as_bytes()).unwrap();
+        let track = VirtualDJTrack::from(&collection.tracks[0]);
+
+        assert_eq!(track.bpm.as_deref(), Some("96.0"));
+        assert_eq!(track.key.as_deref(), Some("Am"));
+        assert_eq!(track.duration, Some(215));
+        assert_eq!(track.rating, Some(4));
+        assert_eq!(track.track_number, None);
+        assert_eq!(track.first_seen, Some(1682899200));
+        assert_eq!(track.pois.len(), 3);
+        assert_eq!(track.pois[0].kind, "beatgrid");
+        assert_eq!(track.pois[0].bpm, Some(96.0));
+        assert_eq!(track.pois[1].number, Some(1));
+        assert_eq!(track.pois[1].color.as_deref(), Some("#28E214"));
+        assert_eq!(track.pois[2].kind, "loop");
+        assert_eq!(track.pois[2].number, None);
+        assert_eq!(track.pois[2].size, Some(8.0));
+    }
+
+    #[test]
+    fn test_traktor_entry_conversion() {
+        let nml = r#"<NML VERSION="19"><COLLECTION ENTRIES="1">
+<ENTRY TITLE="Slow Burn" ARTIST="Kacey">
+<LOCATION DIR="/:Music/:" FILE="a.mp3" VOLUME="C:"></LOCATION>
+<INFO PLAYTIME="215" PLAYTIME_FLOAT="214.8" RANKING="255" IMPORT_DATE="2023/5/1" RELEASE_DATE="2018/3/30"></INFO>
+<TEMPO BPM="96.000"></TEMPO>
+<MUSICAL_KEY VALUE="18"></MUSICAL_KEY>
+<CUE_V2 NAME="AutoGrid" TYPE="4" START="25.3" LEN="0" HOTCUE="0"></CUE_V2>
+<CUE_V2 NAME="n.n." TYPE="5" START="60000" LEN="8000" HOTCUE="-1"></CUE_V2>
+</ENTRY></COLLECTION></NML>"#;
+        let collection = traktor::parse_collection(nml.as_bytes()).unwrap();
+        let track = VirtualDJTrack::from(&collection.entries[0]);
+
+        assert_eq!(track.file_path, "C:\\Music\\a.mp3");
+        assert_eq!(track.bpm.as_deref(), Some("96.0"));
+        assert_eq!(track.key.as_deref(), Some("F#m"));
+        assert_eq!(track.duration, Some(215));
+        assert_eq!(track.year, Some(2018));
+        assert_eq!(track.rating, Some(5));
+        assert_eq!(track.first_seen, Some(1682899200));
+        assert_eq!(track.pois[0].kind, "beatgrid");
+        assert_eq!(track.pois[0].bpm, Some(96.0));
+        assert_eq!(track.pois[0].name, None);
+        assert_eq!(track.pois[1].kind, "loop");
+        assert_eq!(track.pois[1].position, Some(60.0));
+        assert_eq!(track.pois[1].size, Some(8.0));
+        assert_eq!(track.pois[1].number, None);
+    }
+
+    #[test]
+    fn test_history_cursor_reads_only_appended_entries() {
+        use std::io::Write;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("2026-01-01.m3u");
+        let append = |content: &str| {
+            let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+            file.write_all(content.as_bytes()).unwrap();
+        };
+
+        std::fs::write(&path, "#EXTVDJ:<artist>Old</artist><title>Song</title>\nC:\\old.mp3\n").unwrap();
+        let mut cursor = HistoryCursor::at_end(path.clone());
+        assert!(cursor.read_new().unwrap().is_empty());
+
+        // Entry written in two chunks, split mid-line
+        append("#EXTVDJ:<lastplaytime>1700000000</lastplaytime><artist>New</artist>");
+        assert!(cursor.read_new().unwrap().is_empty());
+        append("<title>Track</title>\nC:\\new.mp3\n");
+
+        let tracks = cursor.read_new().unwrap();
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].artist, "New");
+        assert_eq!(tracks[0].title, "Track");
+        assert_eq!(tracks[0].file_path, "C:\\new.mp3");
+        assert_eq!(tracks[0].timestamp, 1700000000);
+
+        // A cursor restored from JSON picks up where it left off
+        let json = serde_json::to_string(&cursor).unwrap();
+        append("#EXTVDJ:<artist>Next</artist><title>One</title>\n\n/next.mp3\n");
+        let mut restored: HistoryCursor = serde_json::from_str(&json).unwrap();
+        let tracks = restored.read_new().unwrap();
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].file_path, "/next.mp3");
+
+        // Truncated file is re-read from the start
+        std::fs::write(&path, "#EXTVDJ:<artist>A</artist><title>B</title>\n/a.mp3\n").unwrap();
+        let tracks = restored.read_new().unwrap();
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].artist, "A");
+    }
+
+    #[test]
+    fn test_history_cursor_survives_js_number_round_trip() {
+        // What the webview does to every number: parse it as an f64
+        let through_js = |cursor: &HistoryCursor| -> HistoryCursor {
+            let mut json = serde_json::to_value(cursor).unwrap();
+            if let Some(id) = json["file_id"].as_u64() {
+                json["file_id"] = serde_json::json!(id as f64 as u64);
+            }
+            serde_json::from_value(json).unwrap()
+        };
+
+        // A Windows creation time in nanoseconds is ~1.7e18, far past 2^53
+        let id = js_safe_id(1_767_225_600_123_456_789);
+        assert_eq!(id as f64 as u64, id);
+        assert_eq!(js_safe_id(u64::MAX) as f64 as u64, js_safe_id(u64::MAX));
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("2026-01-01.m3u");
+        std::fs::write(&path, "#EXTVDJ:<artist>Old</artist><title>Song</title>\nC:\\old.mp3\n").unwrap();
+
+        let cursor = HistoryCursor { file_id: Some(id), ..HistoryCursor::at_end(path.clone()) };
+        assert_eq!(through_js(&cursor), cursor);
+
+        // A restored cursor for the same file must not replay it
+        let mut restored = through_js(&HistoryCursor::at_end(path));
+        assert!(restored.read_new().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rescan_drops_indexes_of_removed_databases() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let db_path = dir.join("database.xml");
+        std::fs::write(&db_path, "<VirtualDJ_Database />").unwrap();
+
+        let mut cache = HashMap::new();
+        cache.insert(db_path.clone(), VdjCache { last_modified: std::time::SystemTime::now(), index: Arc::default() });
+        drop_missing_databases(&mut cache);
+        assert!(cache.contains_key(&db_path));
+
+        // The drive holding it is unplugged
+        std::fs::remove_file(&db_path).unwrap();
+        drop_missing_databases(&mut cache);
+        assert!(cache.is_empty());
+    }
+}
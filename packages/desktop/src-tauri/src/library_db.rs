@@ -0,0 +1,100 @@
+// Shared plumbing for DJ apps that keep their library in SQLite (Engine DJ, Mixxx)
+//
+// Both databases are owned by a running app, so they are only ever opened
+// read-only. Their schemas change between releases; anything we don't
+// understand is reported as a structured error the UI can explain, rather than
+// an import that silently comes back empty.
+
+use rusqlite::{Connection, OpenFlags};
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::PikaError;
+
+/// Why a SQLite library could not be imported
+#[derive(Debug, Clone, PartialEq)]
+pub enum LibraryDbError {
+    /// The file is missing or is not a SQLite database
+    Open { path: String, message: String },
+    /// The schema version is outside the range this importer understands
+    UnsupportedSchema {
+        library: &'static str,
+        found: String,
+        supported: &'static str,
+    },
+    /// A table or column we rely on is missing or unreadable
+    Query {
+        library: &'static str,
+        schema_version: String,
+        message: String,
+    },
+}
+
+impl std::fmt::Display for LibraryDbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LibraryDbError::Open { path, message } => write!(f, "Failed to open {}: {}", path, message),
+            LibraryDbError::UnsupportedSchema { library, found, supported } => write!(
+                f,
+                "Unsupported {} database schema {} (supported: {})",
+                library, found, supported
+            ),
+            LibraryDbError::Query { library, schema_version, message } => write!(
+                f,
+                "Failed to read {} database (schema {}): {}",
+                library, schema_version, message
+            ),
+        }
+    }
+}
+
+/// Commands report library errors like every other failure; an unsupported or
+/// unreadable schema is a file we can't use rather than an I/O problem
+impl From<LibraryDbError> for PikaError {
+    fn from(e: LibraryDbError) -> Self {
+        let message = e.to_string();
+        match e {
+            LibraryDbError::Open { path, .. } if !Path::new(&path).exists() => {
+                PikaError::not_found(message, Some(Path::new(&path)))
+            }
+            LibraryDbError::Open { path, .. } => PikaError::Io { message, path },
+            LibraryDbError::UnsupportedSchema { .. } | LibraryDbError::Query { .. } => {
+                PikaError::invalid_format(message, None)
+            }
+        }
+    }
+}
+
+/// Open a database read-only; it may be in use by the DJ app
+pub(crate) fn open_read_only(path: &Path) -> Result<Connection, LibraryDbError> {
+    let open_err = |message: String| LibraryDbError::Open {
+        path: path.to_string_lossy().into_owned(),
+        message,
+    };
+    if !path.is_file() {
+        return Err(open_err("File not found".to_string()));
+    }
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)
+        .map_err(|e| open_err(e.to_string()))?;
+    // Opening is lazy - touch the schema so a non-database file fails here
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+        .map_err(|e| open_err(e.to_string()))?;
+    Ok(conn)
+}
+
+/// Resolve `..` and `.` in a joined path without touching the filesystem
+/// (the referenced drive may not be mounted)
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push(component);
+                }
+            }
+            Component::CurDir => {}
+            other => out.push(other),
+        }
+    }
+    out
+}
@@ -0,0 +1,316 @@
+// Mixxx library reader (mixxxdb.sqlite)
+//
+// Tracks live in `library`, joined to `track_locations` for the absolute path.
+// Cue positions are stored in interleaved stereo samples, so they are divided
+// by twice the track's sample rate. Playlists and crates are returned as two
+// top-level folders; Auto DJ and the set log (hidden playlists) are skipped.
+
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::library_db::{open_read_only, LibraryDbError};
+use crate::LibraryPlaylist;
+
+const LIBRARY: &str = "Mixxx";
+/// Mixxx 2.2 and later (cue colors and labels)
+const MIN_SCHEMA_VERSION: i64 = 30;
+const SUPPORTED_SCHEMA: &str = "30 and later (Mixxx 2.2+)";
+
+/// A row from `library`
+#[derive(Debug, Default, Clone)]
+pub(crate) struct MixxxTrack {
+    pub(crate) id: i64,
+    pub(crate) file_path: String,
+    pub(crate) title: Option<String>,
+    pub(crate) artist: Option<String>,
+    pub(crate) album: Option<String>,
+    pub(crate) genre: Option<String>,
+    pub(crate) year: Option<String>,
+    pub(crate) track_number: Option<String>,
+    /// Seconds
+    pub(crate) duration: Option<f64>,
+    pub(crate) bpm: Option<f64>,
+    /// Key as displayed in Mixxx (notation depends on the user's setting)
+    pub(crate) key: Option<String>,
+    /// 1-12 major from C, 13-24 minor from Cm
+    pub(crate) key_id: Option<i64>,
+    /// 0-5 stars
+    pub(crate) rating: Option<i64>,
+    pub(crate) times_played: Option<i64>,
+    pub(crate) datetime_added: Option<String>,
+    pub(crate) cues: Vec<MixxxCue>,
+}
+
+/// A row from `cues`, with the position already converted to seconds
+#[derive(Debug, Default, Clone)]
+pub(crate) struct MixxxCue {
+    /// 1 = hot cue, 2 = main cue, 4 = loop, 5 = jump, 6 = intro, 7 = outro
+    pub(crate) cue_type: i64,
+    /// Seconds
+    pub(crate) position: f64,
+    /// Seconds (loops, intro and outro ranges)
+    pub(crate) length: Option<f64>,
+    /// 0-based, -1 = not a hot cue
+    pub(crate) hotcue: i64,
+    pub(crate) label: Option<String>,
+    /// 0xRRGGBB
+    pub(crate) color: Option<i64>,
+}
+
+/// A parsed Mixxx library
+#[derive(Debug, Default)]
+pub(crate) struct MixxxLibrary {
+    pub(crate) schema_version: i64,
+    pub(crate) tracks: Vec<MixxxTrack>,
+    pub(crate) playlists: Vec<LibraryPlaylist>,
+}
+
+fn schema_version(conn: &Connection) -> Result<i64, LibraryDbError> {
+    let found: Option<String> = conn
+        .query_row("SELECT value FROM settings WHERE name = 'mixxx.schema.version'", [], |row| row.get(0))
+        .ok();
+    let version = found.as_deref().and_then(|v| v.trim().parse::<i64>().ok());
+
+    match version {
+        Some(version) if version >= MIN_SCHEMA_VERSION => Ok(version),
+        _ => Err(LibraryDbError::UnsupportedSchema {
+            library: LIBRARY,
+            found: found.unwrap_or_else(|| "unknown (no schema version setting)".to_string()),
+            supported: SUPPORTED_SCHEMA,
+        }),
+    }
+}
+
+fn read_tracks(conn: &Connection) -> rusqlite::Result<Vec<MixxxTrack>> {
+    let mut stmt = conn.prepare(
+        "SELECT l.id, tl.location, l.title, l.artist, l.album, l.genre, l.year, l.tracknumber, l.duration,
+                l.bpm, l.key, l.key_id, l.rating, l.timesplayed, l.datetime_added, l.samplerate
+         FROM library l JOIN track_locations tl ON tl.id = l.location
+         WHERE l.mixxx_deleted = 0",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let track = MixxxTrack {
+            id: row.get(0)?,
+            file_path: row.get(1)?,
+            title: row.get(2)?,
+            artist: row.get(3)?,
+            album: row.get(4)?,
+            genre: row.get(5)?,
+            year: row.get(6)?,
+            track_number: row.get(7)?,
+            duration: row.get(8)?,
+            bpm: row.get(9)?,
+            key: row.get(10)?,
+            key_id: row.get(11)?,
+            rating: row.get(12)?,
+            times_played: row.get(13)?,
+            datetime_added: row.get(14)?,
+            cues: Vec::new(),
+        };
+        Ok((track, row.get::<_, Option<f64>>(15)?))
+    })?;
+
+    let mut tracks = Vec::new();
+    let mut sample_rates = HashMap::new();
+    for row in rows {
+        let (track, sample_rate) = row?;
+        if let Some(rate) = sample_rate.filter(|r| *r > 0.0) {
+            sample_rates.insert(track.id, rate);
+        }
+        tracks.push(track);
+    }
+
+    let mut cues_by_track: HashMap<i64, Vec<MixxxCue>> = HashMap::new();
+    let mut stmt = conn.prepare(
+        "SELECT track_id, type, position, length, hotcue, label, color FROM cues ORDER BY track_id, position",
+    )?;
+    for row in stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, f64>(2)?,
+            row.get::<_, Option<f64>>(3)?,
+            row.get::<_, Option<i64>>(4)?,
+            row.get::<_, Option<String>>(5)?,
+            row.get::<_, Option<i64>>(6)?,
+        ))
+    })? {
+        let (track_id, cue_type, position, length, hotcue, label, color) = row?;
+        // Positions are in interleaved stereo samples
+        let Some(samples_per_second) = sample_rates.get(&track_id).map(|rate| rate * 2.0) else {
+            continue;
+        };
+        if position < 0.0 {
+            continue;
+        }
+        cues_by_track.entry(track_id).or_default().push(MixxxCue {
+            cue_type,
+            position: position / samples_per_second,
+            length: length.filter(|l| *l > 0.0).map(|l| l / samples_per_second),
+            hotcue: hotcue.unwrap_or(-1),
+            label: label.filter(|l| !l.is_empty()),
+            color,
+        });
+    }
+
+    for track in &mut tracks {
+        track.cues = cues_by_track.remove(&track.id).unwrap_or_default();
+    }
+    Ok(tracks)
+}
+
+/// Playlists or crates: one query for the lists, one for their (ordered) track paths
+fn read_lists(conn: &Connection, lists_sql: &str, tracks_sql: &str) -> rusqlite::Result<Vec<LibraryPlaylist>> {
+    let mut paths_by_list: HashMap<i64, Vec<String>> = HashMap::new();
+    let mut stmt = conn.prepare(tracks_sql)?;
+    for row in stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))? {
+        let (list_id, path) = row?;
+        paths_by_list.entry(list_id).or_default().push(path);
+    }
+
+    let mut stmt = conn.prepare(lists_sql)?;
+    let lists = stmt.query_map([], |row| {
+        let id: i64 = row.get(0)?;
+        Ok(LibraryPlaylist {
+            name: row.get(1)?,
+            is_folder: false,
+            children: Vec::new(),
+            track_paths: paths_by_list.remove(&id).unwrap_or_default(),
+        })
+    })?;
+    lists.collect()
+}
+
+fn read_playlists(conn: &Connection) -> rusqlite::Result<Vec<LibraryPlaylist>> {
+    let playlists = read_lists(
+        conn,
+        // hidden: 1 = Auto DJ queue, 2 = set log
+        "SELECT id, name FROM Playlists WHERE hidden = 0 ORDER BY position",
+        "SELECT pt.playlist_id, tl.location FROM PlaylistTracks pt
+         JOIN library l ON l.id = pt.track_id JOIN track_locations tl ON tl.id = l.location
+         ORDER BY pt.playlist_id, pt.position",
+    )?;
+    let crates = read_lists(
+        conn,
+        "SELECT id, name FROM crates ORDER BY name COLLATE NOCASE",
+        "SELECT ct.crate_id, tl.location FROM crate_tracks ct
+         JOIN library l ON l.id = ct.track_id JOIN track_locations tl ON tl.id = l.location
+         WHERE l.mixxx_deleted = 0
+         ORDER BY ct.crate_id, tl.location",
+    )?;
+
+    let folder = |name: &str, children: Vec<LibraryPlaylist>| LibraryPlaylist {
+        name: name.to_string(),
+        is_folder: true,
+        children,
+        track_paths: Vec::new(),
+    };
+    Ok(vec![folder("Playlists", playlists), folder("Crates", crates)])
+}
+
+/// Where Mixxx keeps mixxxdb.sqlite by default on this OS
+pub(crate) fn default_database_path() -> Option<PathBuf> {
+    let home = PathBuf::from(std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")).ok()?);
+    let candidates = [
+        home.join(".mixxx"),
+        home.join("Library").join("Containers").join("org.mixxx.mixxx").join("Data")
+            .join("Library").join("Application Support").join("Mixxx"),
+        home.join("Library").join("Application Support").join("Mixxx"),
+        home.join("AppData").join("Local").join("Mixxx"),
+    ];
+    candidates.into_iter().map(|dir| dir.join("mixxxdb.sqlite")).find(|p| p.is_file())
+}
+
+/// Find mixxxdb.sqlite from the file itself or the Mixxx settings folder
+pub(crate) fn resolve_database_path(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.join("mixxxdb.sqlite")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Read tracks, cues, playlists and crates from a Mixxx database
+pub(crate) fn load_library(db_path: &Path) -> Result<MixxxLibrary, LibraryDbError> {
+    let conn = open_read_only(db_path)?;
+    let schema_version = schema_version(&conn)?;
+    let query_err = |e: rusqlite::Error| LibraryDbError::Query {
+        library: LIBRARY,
+        schema_version: schema_version.to_string(),
+        message: e.to_string(),
+    };
+
+    let tracks = read_tracks(&conn).map_err(query_err)?;
+    let playlists = read_playlists(&conn).map_err(query_err)?;
+    Ok(MixxxLibrary { schema_version, tracks, playlists })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_library_fixture() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let db_path = dir.join("mixxxdb.sqlite");
+
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE settings (name TEXT UNIQUE NOT NULL, value TEXT, locked INTEGER DEFAULT 0, hidden INTEGER DEFAULT 0);
+             INSERT INTO settings (name, value) VALUES ('mixxx.schema.version', '39');
+             CREATE TABLE track_locations (id INTEGER PRIMARY KEY, location VARCHAR(512) UNIQUE);
+             CREATE TABLE library (id INTEGER PRIMARY KEY, artist TEXT, title TEXT, album TEXT, year TEXT,
+                 genre TEXT, tracknumber TEXT, location INTEGER, duration REAL, bpm REAL, key TEXT,
+                 key_id INTEGER, rating INTEGER, timesplayed INTEGER, datetime_added TEXT,
+                 samplerate INTEGER, mixxx_deleted INTEGER DEFAULT 0);
+             CREATE TABLE cues (id INTEGER PRIMARY KEY, track_id INTEGER, type INTEGER, position INTEGER,
+                 length INTEGER, hotcue INTEGER, label TEXT, color INTEGER);
+             CREATE TABLE Playlists (id INTEGER PRIMARY KEY, name TEXT, position INTEGER, hidden INTEGER);
+             CREATE TABLE PlaylistTracks (id INTEGER PRIMARY KEY, playlist_id INTEGER, track_id INTEGER, position INTEGER);
+             CREATE TABLE crates (id INTEGER PRIMARY KEY, name TEXT);
+             CREATE TABLE crate_tracks (crate_id INTEGER, track_id INTEGER);
+
+             INSERT INTO track_locations VALUES (1, '/music/Slow Burn.mp3'), (2, '/music/b.mp3'), (3, '/music/gone.mp3');
+             INSERT INTO library (id, artist, title, location, duration, bpm, key, key_id, rating, timesplayed,
+                 datetime_added, samplerate, mixxx_deleted)
+                 VALUES (1, 'Kacey', 'Slow Burn', 1, 214.8, 96.02, '8A', 22, 4, 3, '2023-05-01 12:00:00', 44100, 0),
+                        (2, 'B', 'Other', 2, NULL, NULL, NULL, 0, 0, 0, NULL, 48000, 0),
+                        (3, 'C', 'Deleted', 3, NULL, NULL, NULL, 0, 0, 0, NULL, 44100, 1);
+             INSERT INTO cues VALUES (1, 1, 1, 2866500, 0, 0, 'Drop', 16711680),
+                                     (2, 1, 4, 5292000, 705600, -1, '', NULL);
+             INSERT INTO Playlists VALUES (1, 'Saturday', 1, 0), (2, 'Auto DJ', 0, 1);
+             INSERT INTO PlaylistTracks VALUES (1, 1, 2, 1), (2, 1, 1, 2);
+             INSERT INTO crates VALUES (1, 'Blues');
+             INSERT INTO crate_tracks VALUES (1, 1), (1, 3);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let library = load_library(&resolve_database_path(dir)).unwrap();
+        assert_eq!(library.schema_version, 39);
+        assert_eq!(library.tracks.len(), 2);
+        let track = &library.tracks[0];
+        assert_eq!(track.file_path, "/music/Slow Burn.mp3");
+        assert_eq!(track.cues.len(), 2);
+        assert_eq!(track.cues[0].position, 32.5);
+        assert_eq!(track.cues[0].hotcue, 0);
+        assert_eq!(track.cues[1].cue_type, 4);
+        assert_eq!(track.cues[1].position, 60.0);
+        assert_eq!(track.cues[1].length, Some(8.0));
+
+        let [playlists, crates] = &library.playlists[..] else { panic!("expected two folders") };
+        assert_eq!(playlists.children.len(), 1);
+        assert_eq!(playlists.children[0].track_paths, ["/music/b.mp3", "/music/Slow Burn.mp3"]);
+        assert_eq!(crates.children[0].track_paths, ["/music/Slow Burn.mp3"]);
+
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute("UPDATE settings SET value = '24'", []).unwrap();
+        drop(conn);
+        assert!(matches!(
+            load_library(&db_path),
+            Err(LibraryDbError::UnsupportedSchema { ref found, .. }) if found == "24"
+        ));
+    }
+}
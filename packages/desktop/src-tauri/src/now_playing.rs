@@ -0,0 +1,548 @@
+// Now-playing sources
+//
+// The history watcher used to know only VDJ's history .m3u. Each DJ app keeps
+// its play history somewhere else, so the watcher now drives any
+// `NowPlayingSource`: it wakes on filesystem events under `watch_target()` (or
+// the rescan interval) and emits whatever `poll()` returns. Every source
+// produces `HistoryTrack`s, so the frontend sees the same track-changed event
+// whichever app is playing.
+
+use notify::RecursiveMode;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::error::PikaError;
+use crate::history_watcher::HistorySource;
+use crate::{HistoryCursor, HistoryTrack};
+
+/// Where played tracks are read from
+pub trait NowPlayingSource: Send {
+    /// Name used in log lines
+    fn name(&self) -> &'static str;
+
+    /// File or folder whose changes should trigger a poll
+    fn watch_target(&self) -> (PathBuf, RecursiveMode);
+
+    /// The most recently played track, without consuming anything
+    fn current(&mut self) -> Result<Option<HistoryTrack>, String>;
+
+    /// Tracks played since the previous poll. A new source starts at the end
+    /// of its history, so the first poll only returns tracks played after it was created.
+    fn poll(&mut self) -> Result<Vec<HistoryTrack>, String>;
+}
+
+/// Which source to follow, as chosen in Settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NowPlayingConfig {
+    /// VDJ's History folder (or the history file passed to `start_history_watcher`)
+    #[default]
+    VirtualDj,
+    /// The set log in Mixxx's database (default location when `db_path` is None)
+    Mixxx { db_path: Option<String> },
+    /// history_*.nml archives in Traktor's History folder
+    Traktor { history_dir: Option<String> },
+    /// Any M3U or "Artist - Title" text file, appended to or rewritten in place
+    TextFile { path: String },
+}
+
+impl NowPlayingConfig {
+    /// Create the configured source. `vdj_history_path` is the optional history
+    /// file override, only used by the VirtualDJ source.
+    pub fn build(&self, vdj_history_path: Option<String>) -> Result<Box<dyn NowPlayingSource>, PikaError> {
+        Ok(match self {
+            NowPlayingConfig::VirtualDj => {
+                let source = match vdj_history_path {
+                    Some(ref path) if path != "auto" && !path.is_empty() && Path::new(path).exists() => {
+                        HistorySource::Pinned(PathBuf::from(path))
+                    }
+                    _ => HistorySource::Latest,
+                };
+                Box::new(VdjHistorySource::new(source)?)
+            }
+            NowPlayingConfig::Mixxx { db_path } => {
+                let path = match db_path {
+                    Some(path) => crate::mixxx::resolve_database_path(Path::new(path)),
+                    None => crate::mixxx::default_database_path()
+                        .ok_or_else(|| PikaError::not_found("Mixxx database not found", None))?,
+                };
+                Box::new(MixxxHistorySource::new(path)?)
+            }
+            NowPlayingConfig::Traktor { history_dir } => {
+                let dir = match history_dir {
+                    Some(dir) => PathBuf::from(dir),
+                    None => crate::traktor::default_history_dir()
+                        .ok_or_else(|| PikaError::not_found("Traktor History folder not found", None))?,
+                };
+                Box::new(TraktorHistorySource::new(dir)?)
+            }
+            NowPlayingConfig::TextFile { path } => Box::new(TextFileSource::new(PathBuf::from(path))?),
+        })
+    }
+}
+
+fn now_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn simple_track(artist: String, title: String, file_path: String, timestamp: u64, song_length: Option<f64>) -> HistoryTrack {
+    HistoryTrack {
+        artist,
+        title,
+        file_path,
+        timestamp,
+        remix: None,
+        song_length,
+        file_size: None,
+        time: None,
+        extras: HashMap::new(),
+    }
+}
+
+/// VDJ's daily history .m3u, following day rollovers
+pub struct VdjHistorySource {
+    source: HistorySource,
+    cursor: HistoryCursor,
+}
+
+impl VdjHistorySource {
+    pub fn new(source: HistorySource) -> Result<Self, PikaError> {
+        let path = source.resolve()?;
+        Ok(Self { source, cursor: HistoryCursor::at_end(path) })
+    }
+}
+
+impl NowPlayingSource for VdjHistorySource {
+    fn name(&self) -> &'static str {
+        "VDJ"
+    }
+
+    fn watch_target(&self) -> (PathBuf, RecursiveMode) {
+        let dir = self.cursor.path().parent().map(Path::to_path_buf).unwrap_or_default();
+        (dir, RecursiveMode::Recursive)
+    }
+
+    fn current(&mut self) -> Result<Option<HistoryTrack>, String> {
+        Ok(crate::read_last_history_entry(self.cursor.path())?)
+    }
+
+    fn poll(&mut self) -> Result<Vec<HistoryTrack>, String> {
+        let mut tracks = Vec::new();
+
+        // Day rollover: VDJ starts a fresh file, follow it from the top
+        if let Ok(latest) = self.source.resolve() {
+            if latest != self.cursor.path() {
+                // Drain whatever was appended to the old file before switching
+                if let Ok(remaining) = self.cursor.read_new() {
+                    tracks.extend(remaining);
+                }
+                println!("[VDJ Watcher] Switching to history file: {:?}", latest);
+                self.cursor = HistoryCursor::new(latest);
+            }
+        }
+
+        tracks.extend(self.cursor.read_new()?);
+        Ok(tracks)
+    }
+}
+
+/// Mixxx's set log: the hidden history playlist it appends to as tracks are played
+pub struct MixxxHistorySource {
+    db_path: PathBuf,
+    last_id: i64,
+}
+
+const MIXXX_SET_LOG_SQL: &str =
+    "SELECT pt.id, l.artist, l.title, tl.location, l.duration,
+            CAST(strftime('%s', pt.pl_datetime_added) AS INTEGER)
+     FROM PlaylistTracks pt
+     JOIN Playlists p ON p.id = pt.playlist_id AND p.hidden = 2
+     JOIN library l ON l.id = pt.track_id
+     JOIN track_locations tl ON tl.id = l.location";
+
+impl MixxxHistorySource {
+    pub fn new(db_path: PathBuf) -> Result<Self, PikaError> {
+        let mut source = Self { db_path, last_id: 0 };
+        source.last_id = source.query("ORDER BY pt.id DESC LIMIT 1", 0)?.first().map_or(0, |(id, _)| *id);
+        Ok(source)
+    }
+
+    /// Run the set-log query with `tail` appended, returning (PlaylistTracks.id, track) pairs
+    fn query(&self, tail: &str, after_id: i64) -> Result<Vec<(i64, HistoryTrack)>, PikaError> {
+        let conn = crate::library_db::open_read_only(&self.db_path)?;
+        let query_err = |e: rusqlite::Error| {
+            PikaError::invalid_format(format!("Failed to read Mixxx history: {}", e), Some(&self.db_path))
+        };
+        let sql = format!("{} WHERE pt.id > ?1 {}", MIXXX_SET_LOG_SQL, tail);
+        let mut stmt = conn.prepare(&sql).map_err(query_err)?;
+        let rows = stmt
+            .query_map([after_id], |row| {
+                let track = simple_track(
+                    row.get::<_, Option<String>>(1)?.unwrap_or_else(|| "Unknown".to_string()),
+                    row.get::<_, Option<String>>(2)?.unwrap_or_else(|| "Unknown".to_string()),
+                    row.get(3)?,
+                    row.get::<_, Option<i64>>(5)?.and_then(|t| u64::try_from(t).ok()).unwrap_or_else(now_timestamp),
+                    row.get(4)?,
+                );
+                Ok((row.get(0)?, track))
+            })
+            .map_err(query_err)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(query_err)
+    }
+}
+
+impl NowPlayingSource for MixxxHistorySource {
+    fn name(&self) -> &'static str {
+        "Mixxx"
+    }
+
+    fn watch_target(&self) -> (PathBuf, RecursiveMode) {
+        // Writes land in mixxxdb.sqlite-wal next to the database
+        let dir = self.db_path.parent().map(Path::to_path_buf).unwrap_or_default();
+        (dir, RecursiveMode::NonRecursive)
+    }
+
+    fn current(&mut self) -> Result<Option<HistoryTrack>, String> {
+        Ok(self.query("ORDER BY pt.id DESC LIMIT 1", 0)?.pop().map(|(_, track)| track))
+    }
+
+    fn poll(&mut self) -> Result<Vec<HistoryTrack>, String> {
+        let rows = self.query("ORDER BY pt.id", self.last_id)?;
+        if let Some((id, _)) = rows.last() {
+            self.last_id = *id;
+        }
+        Ok(rows.into_iter().map(|(_, track)| track).collect())
+    }
+}
+
+/// Traktor's history archives: one history_*.nml per session, rewritten as tracks are played
+pub struct TraktorHistorySource {
+    history_dir: PathBuf,
+    file: Option<PathBuf>,
+    /// Entries of `file` already reported
+    seen: usize,
+}
+
+impl TraktorHistorySource {
+    pub fn new(history_dir: PathBuf) -> Result<Self, PikaError> {
+        if !history_dir.is_dir() {
+            return Err(PikaError::not_found("Traktor History folder not found", Some(&history_dir)));
+        }
+        let file = Self::latest_file(&history_dir);
+        let seen = file.as_deref().map_or(0, |f| Self::read_file(f).map_or(0, |t| t.len()));
+        Ok(Self { history_dir, file, seen })
+    }
+
+    fn latest_file(dir: &Path) -> Option<PathBuf> {
+        std::fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "nml"))
+            .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+            .map(|entry| entry.path())
+    }
+
+    fn read_file(path: &Path) -> Result<Vec<HistoryTrack>, PikaError> {
+        let collection = crate::traktor::load_collection(path)?;
+        Ok(collection
+            .entries
+            .into_iter()
+            .map(|entry| {
+                let length = entry.playtime_float.as_ref().or(entry.playtime.as_ref()).and_then(|p| p.trim().parse().ok());
+                simple_track(
+                    entry.artist.unwrap_or_else(|| "Unknown".to_string()),
+                    entry.title.unwrap_or_else(|| "Unknown".to_string()),
+                    entry.file_path,
+                    entry.played_at.and_then(|t| u64::try_from(t).ok()).unwrap_or_else(now_timestamp),
+                    length,
+                )
+            })
+            .collect())
+    }
+}
+
+impl NowPlayingSource for TraktorHistorySource {
+    fn name(&self) -> &'static str {
+        "Traktor"
+    }
+
+    fn watch_target(&self) -> (PathBuf, RecursiveMode) {
+        (self.history_dir.clone(), RecursiveMode::NonRecursive)
+    }
+
+    fn current(&mut self) -> Result<Option<HistoryTrack>, String> {
+        match self.file {
+            Some(ref file) => Ok(Self::read_file(file)?.pop()),
+            None => Ok(None),
+        }
+    }
+
+    fn poll(&mut self) -> Result<Vec<HistoryTrack>, String> {
+        let mut tracks = Vec::new();
+
+        // A new session starts a new archive; finish the old one, then read the new one from the top
+        let latest = Self::latest_file(&self.history_dir);
+        if latest != self.file {
+            if let Some(ref old) = self.file {
+                if let Ok(remaining) = Self::read_file(old) {
+                    tracks.extend(remaining.into_iter().skip(self.seen));
+                }
+            }
+            self.file = latest;
+            self.seen = 0;
+        }
+
+        if let Some(ref file) = self.file {
+            let entries = Self::read_file(file)?;
+            tracks.extend(entries.iter().skip(self.seen).cloned());
+            self.seen = self.seen.max(entries.len());
+        }
+        Ok(tracks)
+    }
+}
+
+/// Any M3U / plain text file: either a log that grows, or a one-line
+/// "now playing" file that is rewritten in place
+pub struct TextFileSource {
+    path: PathBuf,
+    seen: usize,
+    last: Option<(String, String, String)>,
+}
+
+impl TextFileSource {
+    pub fn new(path: PathBuf) -> Result<Self, PikaError> {
+        if !path.is_file() {
+            return Err(PikaError::not_found("Now playing file not found", Some(&path)));
+        }
+        let entries = Self::read_entries(&path)?;
+        let last = entries.last().map(Self::identity);
+        Ok(Self { path, seen: entries.len(), last })
+    }
+
+    fn identity(track: &HistoryTrack) -> (String, String, String) {
+        (track.artist.clone(), track.title.clone(), track.file_path.clone())
+    }
+
+    fn read_entries(path: &Path) -> Result<Vec<HistoryTrack>, PikaError> {
+        let bytes = std::fs::read(path).map_err(|e| PikaError::io("Failed to read now playing file", path, e))?;
+        Ok(parse_text_entries(&String::from_utf8_lossy(&bytes)))
+    }
+}
+
+/// "Artist - Title", or just a title
+fn split_artist_title(text: &str) -> (String, String) {
+    match text.split_once(" - ") {
+        Some((artist, title)) => (artist.trim().to_string(), title.trim().to_string()),
+        None => ("Unknown".to_string(), text.trim().to_string()),
+    }
+}
+
+/// Entries of an M3U (`#EXTINF:len,Artist - Title` + path), a list of paths,
+/// or plain "Artist - Title" lines. VDJ's #EXTVDJ lines are understood too.
+pub(crate) fn parse_text_entries(content: &str) -> Vec<HistoryTrack> {
+    let mut entries = Vec::new();
+    let mut pending: Option<(Option<f64>, String)> = None;
+    let mut pending_vdj: Option<&str> = None;
+    let timestamp = now_timestamp();
+
+    for line in content.lines().map(|l| l.trim_start_matches('\u{feff}').trim()) {
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            let (length, text) = info.split_once(',').unwrap_or(("", info));
+            pending = Some((length.trim().parse::<f64>().ok().filter(|l| *l > 0.0), text.to_string()));
+            continue;
+        }
+        if line.starts_with("#EXTVDJ:") {
+            pending_vdj = Some(line);
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(ext) = pending_vdj.take() {
+            if let Some(track) = crate::parse_history_entry(ext, line) {
+                entries.push(track);
+                continue;
+            }
+        }
+
+        let looks_like_path = line.contains('/') || line.contains('\\');
+        let (length, text) = match pending.take() {
+            Some((length, text)) => (length, text),
+            // A bare path: use the file name
+            None if looks_like_path => {
+                let stem = Path::new(line).file_stem().map(|s| s.to_string_lossy().into_owned());
+                (None, stem.unwrap_or_else(|| line.to_string()))
+            }
+            None => (None, line.to_string()),
+        };
+        let (artist, title) = split_artist_title(&text);
+        let file_path = if looks_like_path { line.to_string() } else { String::new() };
+        entries.push(simple_track(artist, title, file_path, timestamp, length));
+    }
+    entries
+}
+
+impl NowPlayingSource for TextFileSource {
+    fn name(&self) -> &'static str {
+        "Text file"
+    }
+
+    fn watch_target(&self) -> (PathBuf, RecursiveMode) {
+        // Watch the folder: many writers replace the file rather than modify it
+        let dir = self.path.parent().map(Path::to_path_buf).unwrap_or_default();
+        (dir, RecursiveMode::NonRecursive)
+    }
+
+    fn current(&mut self) -> Result<Option<HistoryTrack>, String> {
+        Ok(Self::read_entries(&self.path)?.pop())
+    }
+
+    fn poll(&mut self) -> Result<Vec<HistoryTrack>, String> {
+        let entries = Self::read_entries(&self.path)?;
+        let new: Vec<HistoryTrack> = if entries.len() > self.seen {
+            entries[self.seen..].to_vec()
+        } else {
+            // Rewritten in place (or truncated): report the last line if it changed
+            entries
+                .last()
+                .filter(|track| self.last.as_ref() != Some(&Self::identity(track)))
+                .cloned()
+                .into_iter()
+                .collect()
+        };
+
+        self.seen = entries.len();
+        if let Some(track) = new.last() {
+            self.last = Some(Self::identity(track));
+        }
+        Ok(new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_file_source_log_and_rewrite() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+
+        // An M3U log that grows
+        let m3u = dir.join("history.m3u");
+        std::fs::write(&m3u, "#EXTM3U\n#EXTINF:215,Kacey - Slow Burn\n/music/a.mp3\n").unwrap();
+        let mut source = TextFileSource::new(m3u.clone()).unwrap();
+        assert!(source.poll().unwrap().is_empty());
+        assert_eq!(source.current().unwrap().unwrap().title, "Slow Burn");
+
+        std::fs::write(&m3u, "#EXTM3U\n#EXTINF:215,Kacey - Slow Burn\n/music/a.mp3\n/music/B - Other.mp3\n").unwrap();
+        let new = source.poll().unwrap();
+        assert_eq!(new.len(), 1);
+        assert_eq!((new[0].artist.as_str(), new[0].title.as_str()), ("B", "Other"));
+        assert_eq!(new[0].file_path, "/music/B - Other.mp3");
+        assert!(source.poll().unwrap().is_empty());
+
+        // A one-line now-playing file that is overwritten
+        let text = dir.join("nowplaying.txt");
+        std::fs::write(&text, "Kacey - Slow Burn").unwrap();
+        let mut source = TextFileSource::new(text.clone()).unwrap();
+        std::fs::write(&text, "Kacey - Slow Burn").unwrap();
+        assert!(source.poll().unwrap().is_empty());
+        std::fs::write(&text, "Jessie Ware - Say You Love Me\n").unwrap();
+        let new = source.poll().unwrap();
+        assert_eq!(new.len(), 1);
+        assert_eq!(new[0].artist, "Jessie Ware");
+        assert_eq!(new[0].song_length, None);
+    }
+
+    #[test]
+    fn test_mixxx_set_log_source() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let db_path = dir.join("mixxxdb.sqlite");
+
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE track_locations (id INTEGER PRIMARY KEY, location TEXT);
+             CREATE TABLE library (id INTEGER PRIMARY KEY, artist TEXT, title TEXT, location INTEGER, duration REAL);
+             CREATE TABLE Playlists (id INTEGER PRIMARY KEY, name TEXT, hidden INTEGER);
+             CREATE TABLE PlaylistTracks (id INTEGER PRIMARY KEY, playlist_id INTEGER, track_id INTEGER,
+                 position INTEGER, pl_datetime_added TEXT);
+             INSERT INTO track_locations VALUES (1, '/music/a.mp3'), (2, '/music/b.mp3');
+             INSERT INTO library VALUES (1, 'Kacey', 'Slow Burn', 1, 214.8), (2, 'B', 'Other', 2, NULL);
+             INSERT INTO Playlists VALUES (1, 'Saturday', 0), (2, '2023-05-01', 2);
+             INSERT INTO PlaylistTracks VALUES (1, 2, 1, 1, '2023-05-01T20:15:00.000Z');",
+        )
+        .unwrap();
+
+        let mut source = MixxxHistorySource::new(db_path.clone()).unwrap();
+        assert_eq!(source.current().unwrap().unwrap().timestamp, 1682972100);
+        assert!(source.poll().unwrap().is_empty());
+
+        // Added to a normal playlist: not a play
+        conn.execute("INSERT INTO PlaylistTracks VALUES (2, 1, 2, 1, '2023-05-01T20:16:00Z')", []).unwrap();
+        assert!(source.poll().unwrap().is_empty());
+        conn.execute("INSERT INTO PlaylistTracks VALUES (3, 2, 2, 2, '2023-05-01T20:18:00Z')", []).unwrap();
+        let new = source.poll().unwrap();
+        assert_eq!(new.len(), 1);
+        assert_eq!(new[0].file_path, "/music/b.mp3");
+        assert!(source.poll().unwrap().is_empty());
+
+        drop(conn);
+    }
+
+    #[test]
+    fn test_traktor_history_source_follows_latest_archive() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let nml = |titles: &[&str]| {
+            let entries: String = titles
+                .iter()
+                .map(|title| format!(
+                    "<ENTRY TITLE=\"{}\" ARTIST=\"DJ\"><LOCATION DIR=\"/:Music/:\" FILE=\"{}.mp3\" VOLUME=\"C:\"></LOCATION>\
+                     <EXTENDEDDATA STARTDATE=\"132580609\" STARTTIME=\"72000\"></EXTENDEDDATA></ENTRY>",
+                    title, title
+                ))
+                .collect();
+            format!("<NML VERSION=\"19\"><COLLECTION ENTRIES=\"{}\">{}</COLLECTION></NML>", titles.len(), entries)
+        };
+        // Archives are told apart by mtime, so pin them instead of relying on write order
+        let write = |name: &str, titles: &[&str], age_secs: u64| {
+            let path = dir.join(name);
+            std::fs::write(&path, nml(titles)).unwrap();
+            let modified = std::time::SystemTime::now() - std::time::Duration::from_secs(age_secs);
+            std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        };
+
+        write("history_old.nml", &["Last Week"], 600);
+        write("history_today.nml", &["One", "Two"], 60);
+        let mut source = TraktorHistorySource::new(dir.to_path_buf()).unwrap();
+        // Tracks already in the latest archive at startup are not replayed
+        assert_eq!(source.seen, 2);
+        assert!(source.poll().unwrap().is_empty());
+        assert_eq!(source.current().unwrap().unwrap().title, "Two");
+
+        write("history_today.nml", &["One", "Two", "Three"], 50);
+        let new = source.poll().unwrap();
+        assert_eq!(new.iter().map(|t| t.title.as_str()).collect::<Vec<_>>(), ["Three"]);
+        assert_eq!(new[0].file_path, "C:\\Music\\Three.mp3");
+        assert_eq!(source.seen, 3);
+
+        // A new session: the rest of the old archive, then the new one from the top
+        write("history_today.nml", &["One", "Two", "Three", "Four"], 40);
+        write("history_next.nml", &["Five"], 0);
+        let new = source.poll().unwrap();
+        assert_eq!(new.iter().map(|t| t.title.as_str()).collect::<Vec<_>>(), ["Four", "Five"]);
+        assert_eq!(source.seen, 1);
+        assert!(source.poll().unwrap().is_empty());
+    }
+}
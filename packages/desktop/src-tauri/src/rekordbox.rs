@@ -0,0 +1,367 @@
+// Rekordbox XML collection reader
+//
+// Reads the `DJ_PLAYLISTS` file written by Rekordbox's "Export Collection in
+// xml format". Tracks live under COLLECTION; the PLAYLISTS tree only refers to
+// them by TrackID (or Location), so playlist entries are resolved to file paths
+// once the whole file has been read.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::Path;
+
+use crate::error::PikaError;
+use crate::vdj_database::for_each_attribute;
+use crate::LibraryPlaylist;
+
+/// One `<TRACK>` from the COLLECTION
+#[derive(Debug, Default, Clone)]
+pub(crate) struct RekordboxTrack {
+    pub(crate) track_id: String,
+    /// Decoded local file path (from the `file://localhost/...` Location URL)
+    pub(crate) file_path: String,
+    pub(crate) name: Option<String>,
+    pub(crate) artist: Option<String>,
+    pub(crate) album: Option<String>,
+    pub(crate) genre: Option<String>,
+    pub(crate) year: Option<String>,
+    pub(crate) track_number: Option<String>,
+    /// Seconds
+    pub(crate) total_time: Option<String>,
+    pub(crate) average_bpm: Option<String>,
+    pub(crate) tonality: Option<String>,
+    /// 0-255 (0, 51, 102, 153, 204, 255 for 0-5 stars)
+    pub(crate) rating: Option<String>,
+    pub(crate) play_count: Option<String>,
+    /// yyyy-mm-dd
+    pub(crate) date_added: Option<String>,
+    pub(crate) tempos: Vec<RekordboxTempo>,
+    pub(crate) position_marks: Vec<RekordboxPositionMark>,
+}
+
+/// `<TEMPO>`: a beatgrid anchor
+#[derive(Debug, Default, Clone)]
+pub(crate) struct RekordboxTempo {
+    /// Seconds
+    pub(crate) inizio: Option<String>,
+    pub(crate) bpm: Option<String>,
+}
+
+/// `<POSITION_MARK>`: memory cue, hot cue or loop
+#[derive(Debug, Default, Clone)]
+pub(crate) struct RekordboxPositionMark {
+    pub(crate) name: Option<String>,
+    /// 0 = cue, 1 = fade-in, 2 = fade-out, 3 = load, 4 = loop
+    pub(crate) mark_type: Option<String>,
+    /// Seconds
+    pub(crate) start: Option<String>,
+    pub(crate) end: Option<String>,
+    /// Hot cue slot, 0-based (-1 = memory cue)
+    pub(crate) num: Option<String>,
+    pub(crate) red: Option<String>,
+    pub(crate) green: Option<String>,
+    pub(crate) blue: Option<String>,
+}
+
+/// A parsed Rekordbox export
+#[derive(Debug, Default)]
+pub(crate) struct RekordboxCollection {
+    pub(crate) tracks: Vec<RekordboxTrack>,
+    /// Children of the ROOT node
+    pub(crate) playlists: Vec<LibraryPlaylist>,
+}
+
+/// A playlist node while parsing, before its entries are resolved to paths
+struct PendingNode {
+    name: String,
+    is_folder: bool,
+    /// KeyType="1": entries are Locations instead of TrackIDs
+    keyed_by_location: bool,
+    children: Vec<PendingNode>,
+    keys: Vec<String>,
+}
+
+impl PendingNode {
+    fn from_element(element: &BytesStart) -> Result<Self, String> {
+        let mut node = PendingNode {
+            name: String::new(),
+            is_folder: true,
+            keyed_by_location: false,
+            children: Vec::new(),
+            keys: Vec::new(),
+        };
+        for_each_attribute(element, |key, value| match key {
+            b"Name" => node.name = value,
+            b"Type" => node.is_folder = value.trim() == "0",
+            b"KeyType" => node.keyed_by_location = value.trim() == "1",
+            _ => {}
+        })?;
+        Ok(node)
+    }
+
+    fn resolve(self, paths_by_id: &HashMap<String, String>) -> LibraryPlaylist {
+        let track_paths = self
+            .keys
+            .into_iter()
+            .filter_map(|key| {
+                if self.keyed_by_location {
+                    Some(decode_location(&key))
+                } else {
+                    paths_by_id.get(&key).cloned()
+                }
+            })
+            .collect();
+        LibraryPlaylist {
+            name: self.name,
+            is_folder: self.is_folder,
+            children: self.children.into_iter().map(|c| c.resolve(paths_by_id)).collect(),
+            track_paths,
+        }
+    }
+}
+
+/// Turn a Rekordbox Location URL into a local path.
+/// `file://localhost/C:/Music/a%20b.mp3` -> `C:\Music\a b.mp3`,
+/// `file://localhost/Users/dj/a.mp3` -> `/Users/dj/a.mp3`
+pub(crate) fn decode_location(location: &str) -> String {
+    let path = location
+        .strip_prefix("file://localhost")
+        .or_else(|| location.strip_prefix("file://"))
+        .unwrap_or(location);
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    let path = String::from_utf8_lossy(&decoded).into_owned();
+
+    // Windows drive paths come through as "/C:/..."; VDJ and the OS use backslashes
+    let is_drive_path = path.len() >= 3 && path.as_bytes()[0] == b'/' && path.as_bytes()[2] == b':';
+    if is_drive_path {
+        path[1..].replace('/', "\\")
+    } else {
+        path
+    }
+}
+
+fn parse_track_start(element: &BytesStart) -> Result<RekordboxTrack, String> {
+    let mut track = RekordboxTrack::default();
+    for_each_attribute(element, |key, value| match key {
+        b"TrackID" => track.track_id = value,
+        b"Location" => track.file_path = decode_location(&value),
+        b"Name" => track.name = Some(value),
+        b"Artist" => track.artist = Some(value),
+        b"Album" => track.album = Some(value),
+        b"Genre" => track.genre = Some(value),
+        b"Year" => track.year = Some(value),
+        b"TrackNumber" => track.track_number = Some(value),
+        b"TotalTime" => track.total_time = Some(value),
+        b"AverageBpm" => track.average_bpm = Some(value),
+        b"Tonality" => track.tonality = Some(value),
+        b"Rating" => track.rating = Some(value),
+        b"PlayCount" => track.play_count = Some(value),
+        b"DateAdded" => track.date_added = Some(value),
+        _ => {}
+    })?;
+    Ok(track)
+}
+
+fn parse_tempo(element: &BytesStart) -> Result<RekordboxTempo, String> {
+    let mut tempo = RekordboxTempo::default();
+    for_each_attribute(element, |key, value| match key {
+        b"Inizio" => tempo.inizio = Some(value),
+        b"Bpm" => tempo.bpm = Some(value),
+        _ => {}
+    })?;
+    Ok(tempo)
+}
+
+fn parse_position_mark(element: &BytesStart) -> Result<RekordboxPositionMark, String> {
+    let mut mark = RekordboxPositionMark::default();
+    for_each_attribute(element, |key, value| match key {
+        b"Name" => mark.name = Some(value),
+        b"Type" => mark.mark_type = Some(value),
+        b"Start" => mark.start = Some(value),
+        b"End" => mark.end = Some(value),
+        b"Num" => mark.num = Some(value),
+        b"Red" => mark.red = Some(value),
+        b"Green" => mark.green = Some(value),
+        b"Blue" => mark.blue = Some(value),
+        _ => {}
+    })?;
+    Ok(mark)
+}
+
+/// Stream-parse a Rekordbox `DJ_PLAYLISTS` export
+pub(crate) fn parse_collection<R: BufRead>(source: R) -> Result<RekordboxCollection, PikaError> {
+    let mut reader = Reader::from_reader(source);
+    let mut buf = Vec::with_capacity(4096);
+    let mut tracks = Vec::new();
+    let mut current: Option<RekordboxTrack> = None;
+    let mut in_collection = false;
+    // Open NODE elements; the bottom one is ROOT
+    let mut node_stack: Vec<PendingNode> = Vec::new();
+    let mut root: Option<PendingNode> = None;
+    let mut seen_root = false;
+
+    let not_collection = || PikaError::invalid_format("Not a Rekordbox collection (missing <DJ_PLAYLISTS>)", None);
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| PikaError::xml_at(reader.error_position(), format!("XML parsing error: {}", e)))?;
+        // Attribute errors are reported at the element they belong to
+        let position = reader.buffer_position();
+        let attr_err = |message: String| PikaError::xml_at(position, message);
+
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) if !seen_root => {
+                if e.name().as_ref() != b"DJ_PLAYLISTS" {
+                    return Err(not_collection());
+                }
+                seen_root = true;
+            }
+            Event::Start(ref e) if e.name().as_ref() == b"COLLECTION" => in_collection = true,
+            Event::End(ref e) if e.name().as_ref() == b"COLLECTION" => in_collection = false,
+            Event::Start(ref e) if in_collection && e.name().as_ref() == b"TRACK" => {
+                current = Some(parse_track_start(e).map_err(attr_err)?);
+            }
+            Event::Empty(ref e) if in_collection && e.name().as_ref() == b"TRACK" => {
+                tracks.push(parse_track_start(e).map_err(attr_err)?);
+            }
+            Event::End(ref e) if in_collection && e.name().as_ref() == b"TRACK" => {
+                tracks.extend(current.take());
+            }
+            Event::Start(ref e) | Event::Empty(ref e) if in_collection => {
+                if let Some(ref mut track) = current {
+                    match e.name().as_ref() {
+                        b"TEMPO" => track.tempos.push(parse_tempo(e).map_err(attr_err)?),
+                        b"POSITION_MARK" => track.position_marks.push(parse_position_mark(e).map_err(attr_err)?),
+                        _ => {}
+                    }
+                }
+            }
+            Event::Start(ref e) if e.name().as_ref() == b"NODE" => {
+                node_stack.push(PendingNode::from_element(e).map_err(attr_err)?);
+            }
+            Event::Empty(ref e) if e.name().as_ref() == b"NODE" => {
+                let node = PendingNode::from_element(e).map_err(attr_err)?;
+                if let Some(parent) = node_stack.last_mut() {
+                    parent.children.push(node);
+                }
+            }
+            Event::End(ref e) if e.name().as_ref() == b"NODE" => {
+                if let Some(node) = node_stack.pop() {
+                    match node_stack.last_mut() {
+                        Some(parent) => parent.children.push(node),
+                        None => root = Some(node),
+                    }
+                }
+            }
+            Event::Empty(ref e) if e.name().as_ref() == b"TRACK" => {
+                if let Some(node) = node_stack.last_mut() {
+                    for_each_attribute(e, |key, value| {
+                        if key == b"Key" {
+                            node.keys.push(value);
+                        }
+                    }).map_err(attr_err)?;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if !seen_root {
+        return Err(not_collection());
+    }
+
+    let paths_by_id: HashMap<String, String> = tracks
+        .iter()
+        .map(|t| (t.track_id.clone(), t.file_path.clone()))
+        .collect();
+    let playlists = root
+        .map(|root| root.resolve(&paths_by_id).children)
+        .unwrap_or_default();
+
+    tracks.retain(|t| !t.file_path.is_empty());
+    Ok(RekordboxCollection { tracks, playlists })
+}
+
+/// Open and parse a Rekordbox XML export
+pub(crate) fn load_collection(path: &Path) -> Result<RekordboxCollection, PikaError> {
+    let file = std::fs::File::open(path).map_err(|e| PikaError::io("Failed to read Rekordbox XML", path, e))?;
+    parse_collection(std::io::BufReader::with_capacity(256 * 1024, file)).map_err(|e| e.in_file(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_collection_and_playlists() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<DJ_PLAYLISTS Version="1.0.0">
+  <PRODUCT Name="rekordbox" Version="6.8.2" Company="AlphaTheta"/>
+  <COLLECTION Entries="2">
+    <TRACK TrackID="11" Name="Slow Burn" Artist="Kacey &amp; Co" Genre="WCS" TotalTime="215" Year="2018"
+           AverageBpm="96.00" Tonality="Am" Rating="204" PlayCount="3" DateAdded="2023-05-01"
+           Location="file://localhost/C:/Music/Slow%20Burn.mp3">
+      <TEMPO Inizio="0.025" Bpm="96.00" Metro="4/4" Battito="1"/>
+      <POSITION_MARK Name="Intro" Type="0" Start="12.5" Num="0" Red="40" Green="226" Blue="20"/>
+      <POSITION_MARK Name="" Type="4" Start="30.0" End="40.0" Num="-1"/>
+    </TRACK>
+    <TRACK TrackID="12" Name="Other" Artist="B" Location="file://localhost/Users/dj/Music/b.mp3"/>
+  </COLLECTION>
+  <PLAYLISTS>
+    <NODE Type="0" Name="ROOT" Count="2">
+      <NODE Type="0" Name="Sets" Count="1">
+        <NODE Name="Saturday" Type="1" KeyType="0" Entries="2">
+          <TRACK Key="12"/>
+          <TRACK Key="11"/>
+        </NODE>
+      </NODE>
+      <NODE Name="Empty" Type="1" KeyType="0" Entries="0"/>
+    </NODE>
+  </PLAYLISTS>
+</DJ_PLAYLISTS>"#;
+
+        let collection = parse_collection(xml.as_bytes()).unwrap();
+        assert_eq!(collection.tracks.len(), 2);
+
+        let track = &collection.tracks[0];
+        assert_eq!(track.file_path, "C:\\Music\\Slow Burn.mp3");
+        assert_eq!(track.artist.as_deref(), Some("Kacey & Co"));
+        assert_eq!(track.tonality.as_deref(), Some("Am"));
+        assert_eq!(track.tempos.len(), 1);
+        assert_eq!(track.position_marks.len(), 2);
+        assert_eq!(collection.tracks[1].file_path, "/Users/dj/Music/b.mp3");
+
+        assert_eq!(collection.playlists.len(), 2);
+        let sets = &collection.playlists[0];
+        assert!(sets.is_folder);
+        assert_eq!(sets.children[0].name, "Saturday");
+        assert!(!sets.children[0].is_folder);
+        assert_eq!(
+            sets.children[0].track_paths,
+            vec!["/Users/dj/Music/b.mp3".to_string(), "C:\\Music\\Slow Burn.mp3".to_string()]
+        );
+        assert!(collection.playlists[1].track_paths.is_empty());
+
+        assert!(parse_collection(r#"<VirtualDJ_Database />"#.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+// Serato library reader (_Serato_/database V2 and Subcrates/*.crate)
+//
+// Both files are flat tag-length-value streams: a 4-byte ASCII tag, a
+// big-endian u32 length, then the payload. "otrk" records nest another TLV
+// stream holding the track fields. The first letter of a tag gives the
+// payload type: t/p = UTF-16BE text, u = u32, s = u16, b = bool, o = nested.
+// Track paths are stored relative to the root of the drive the _Serato_
+// folder is on.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::PikaError;
+use crate::LibraryPlaylist;
+
+/// Separator Serato uses in crate file names for sub-crates ("Sets%%Saturday.crate")
+const SUBCRATE_SEPARATOR: &str = "%%";
+
+/// One "otrk" record from database V2
+#[derive(Debug, Default, Clone)]
+pub(crate) struct SeratoTrack {
+    /// Absolute path (pfil resolved against the drive root)
+    pub(crate) file_path: String,
+    pub(crate) title: Option<String>,
+    pub(crate) artist: Option<String>,
+    pub(crate) album: Option<String>,
+    pub(crate) genre: Option<String>,
+    pub(crate) bpm: Option<String>,
+    pub(crate) key: Option<String>,
+    /// "mm:ss.xx" as displayed by Serato
+    pub(crate) length: Option<String>,
+    pub(crate) year: Option<String>,
+    /// Unix timestamp
+    pub(crate) date_added: Option<u32>,
+}
+
+/// A parsed Serato library
+#[derive(Debug, Default)]
+pub(crate) struct SeratoLibrary {
+    pub(crate) tracks: Vec<SeratoTrack>,
+    /// Crates, with sub-crates nested under their parent
+    pub(crate) crates: Vec<LibraryPlaylist>,
+}
+
+/// One tag-length-value record: the 4-byte tag and its payload
+type Record<'a> = ([u8; 4], &'a [u8]);
+
+/// Split a TLV stream into records
+fn parse_records(data: &[u8]) -> Result<Vec<Record<'_>>, String> {
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let header = data
+            .get(offset..offset + 8)
+            .ok_or_else(|| format!("Serato parsing error: truncated record header at byte {}", offset))?;
+        let tag = [header[0], header[1], header[2], header[3]];
+        let len = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let payload = data.get(offset + 8..offset + 8 + len).ok_or_else(|| {
+            format!(
+                "Serato parsing error: record '{}' at byte {} is longer than the file",
+                String::from_utf8_lossy(&tag),
+                offset
+            )
+        })?;
+        records.push((tag, payload));
+        offset += 8 + len;
+    }
+    Ok(records)
+}
+
+fn decode_text(payload: &[u8]) -> String {
+    let units: Vec<u16> = payload
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units).trim_end_matches('\0').to_string()
+}
+
+/// Resolve a stored path against the drive root. Serato writes them without a
+/// leading separator and with forward slashes ("Users/dj/Music/a.mp3", "Music/a.mp3").
+fn resolve_path(root: &Path, stored: &str) -> String {
+    let relative = stored
+        .trim_start_matches(['/', '\\'])
+        .replace('/', std::path::MAIN_SEPARATOR_STR);
+    root.join(relative).to_string_lossy().into_owned()
+}
+
+/// The root Serato paths are relative to: the folder holding `_Serato_` when that
+/// is a mounted drive, otherwise the root of the filesystem / drive letter
+fn drive_root(serato_dir: &Path) -> PathBuf {
+    let parent = serato_dir.parent().unwrap_or(serato_dir);
+    if crate::vdj_discovery::volume_roots().iter().any(|root| root == parent) {
+        return parent.to_path_buf();
+    }
+    parent.ancestors().last().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("/"))
+}
+
+fn parse_track(payload: &[u8], root: &Path) -> Result<SeratoTrack, String> {
+    let mut track = SeratoTrack::default();
+    for (tag, value) in parse_records(payload)? {
+        match &tag {
+            b"pfil" => track.file_path = resolve_path(root, &decode_text(value)),
+            b"tsng" => track.title = Some(decode_text(value)),
+            b"tart" => track.artist = Some(decode_text(value)),
+            b"talb" => track.album = Some(decode_text(value)),
+            b"tgen" => track.genre = Some(decode_text(value)),
+            b"tbpm" => track.bpm = Some(decode_text(value)),
+            b"tkey" => track.key = Some(decode_text(value)),
+            b"tlen" => track.length = Some(decode_text(value)),
+            b"ttyr" => track.year = Some(decode_text(value)),
+            b"uadd" if value.len() == 4 => {
+                track.date_added = Some(u32::from_be_bytes([value[0], value[1], value[2], value[3]]));
+            }
+            _ => {}
+        }
+    }
+    Ok(track)
+}
+
+/// Parse the contents of a `database V2` file
+pub(crate) fn parse_database(data: &[u8], root: &Path) -> Result<Vec<SeratoTrack>, String> {
+    let records = parse_records(data)?;
+    if records.first().is_none_or(|(tag, _)| tag != b"vrsn") {
+        return Err("Serato parsing error: not a Serato database (missing version header)".to_string());
+    }
+
+    let mut tracks = Vec::new();
+    for (tag, payload) in records {
+        if &tag == b"otrk" {
+            let track = parse_track(payload, root)?;
+            if !track.file_path.is_empty() {
+                tracks.push(track);
+            }
+        }
+    }
+    Ok(tracks)
+}
+
+/// Parse the contents of a `.crate` file into its track paths, in order
+pub(crate) fn parse_crate(data: &[u8], root: &Path) -> Result<Vec<String>, String> {
+    let mut paths = Vec::new();
+    for (tag, payload) in parse_records(data)? {
+        if &tag == b"otrk" {
+            for (inner, value) in parse_records(payload)? {
+                if &inner == b"ptrk" {
+                    paths.push(resolve_path(root, &decode_text(value)));
+                }
+            }
+        }
+    }
+    Ok(paths)
+}
+
+/// Insert a crate under its parents, creating any parent that has no crate file of its own
+fn insert_crate(crates: &mut Vec<LibraryPlaylist>, name_parts: &[&str], track_paths: Vec<String>) {
+    let Some((first, rest)) = name_parts.split_first() else {
+        return;
+    };
+    let index = match crates.iter().position(|c| c.name == *first) {
+        Some(index) => index,
+        None => {
+            crates.push(LibraryPlaylist {
+                name: first.to_string(),
+                is_folder: false,
+                children: Vec::new(),
+                track_paths: Vec::new(),
+            });
+            crates.len() - 1
+        }
+    };
+
+    let node = &mut crates[index];
+    if rest.is_empty() {
+        node.track_paths = track_paths;
+    } else {
+        node.is_folder = true;
+        insert_crate(&mut node.children, rest, track_paths);
+    }
+}
+
+/// Read a whole `_Serato_` folder: database V2 plus every crate in Subcrates
+pub(crate) fn load_library(serato_dir: &Path) -> Result<SeratoLibrary, PikaError> {
+    let root = drive_root(serato_dir);
+    let database_path = serato_dir.join("database V2");
+    let data = std::fs::read(&database_path)
+        .map_err(|e| PikaError::io("Failed to read Serato database V2", &database_path, e))?;
+    let tracks = parse_database(&data, &root).map_err(|message| PikaError::invalid_format(message, Some(&database_path)))?;
+
+    // (crate path split at the sub-crate separator, file)
+    let mut crate_files: Vec<(Vec<String>, PathBuf)> = match std::fs::read_dir(serato_dir.join("Subcrates")) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "crate"))
+            .filter_map(|p| {
+                let name = p.file_stem()?.to_string_lossy().into_owned();
+                Some((name.split(SUBCRATE_SEPARATOR).map(str::to_string).collect(), p))
+            })
+            .collect(),
+        // A library without crates is fine
+        Err(_) => Vec::new(),
+    };
+    // Sorting the split names puts parents before their sub-crates; sorting
+    // file names would not, since '%' sorts before '.'
+    crate_files.sort();
+
+    let mut crates = Vec::new();
+    for (parts, path) in crate_files {
+        let track_paths = match std::fs::read(&path).map_err(|e| e.to_string()).and_then(|d| parse_crate(&d, &root)) {
+            Ok(paths) => paths,
+            Err(e) => {
+                eprintln!("[Serato] Skipping unreadable crate {:?}: {}", path, e);
+                continue;
+            }
+        };
+        let parts: Vec<&str> = parts.iter().map(String::as_str).collect();
+        insert_crate(&mut crates, &parts, track_paths);
+    }
+
+    Ok(SeratoLibrary { tracks, crates })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(tag: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = tag.to_vec();
+        out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn text(tag: &[u8; 4], value: &str) -> Vec<u8> {
+        let payload: Vec<u8> = value.encode_utf16().flat_map(|u| u.to_be_bytes()).collect();
+        record(tag, &payload)
+    }
+
+    #[test]
+    fn test_load_library_with_crates() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let serato_dir = dir.join("_Serato_");
+        std::fs::create_dir_all(serato_dir.join("Subcrates")).unwrap();
+
+        let mut track = text(b"pfil", "Users/dj/Music/Slow Burn.mp3");
+        track.extend(text(b"tsng", "Slow Burn"));
+        track.extend(text(b"tart", "Kacey"));
+        track.extend(text(b"tbpm", "96.00"));
+        track.extend(text(b"tkey", "Am"));
+        track.extend(text(b"ttyr", "2019"));
+        track.extend(text(b"tlen", "03:34.80"));
+        track.extend(record(b"uadd", &1_682_899_200u32.to_be_bytes()));
+        track.extend(record(b"bmis", &[0]));
+        let mut database = text(b"vrsn", "2.0/Serato Scratch LIVE Database");
+        database.extend(record(b"otrk", &track));
+        std::fs::write(serato_dir.join("database V2"), &database).unwrap();
+
+        let mut saturday = text(b"vrsn", "1.0/Serato ScratchLive Crate");
+        saturday.extend(record(b"otrk", &text(b"ptrk", "Users/dj/Music/Slow Burn.mp3")));
+        std::fs::write(serato_dir.join("Subcrates").join("Sets%%Saturday.crate"), &saturday).unwrap();
+        std::fs::write(serato_dir.join("Subcrates").join("Sets.crate"), text(b"vrsn", "1.0")).unwrap();
+        std::fs::write(serato_dir.join("Subcrates").join("Sets%%Friday.crate"), text(b"vrsn", "1.0")).unwrap();
+        std::fs::write(serato_dir.join("Subcrates").join("Sets 2019.crate"), text(b"vrsn", "1.0")).unwrap();
+
+        let library = load_library(&serato_dir).unwrap();
+        assert_eq!(library.tracks.len(), 1);
+        let track = &library.tracks[0];
+        let expected_path = resolve_path(&drive_root(&serato_dir), "Users/dj/Music/Slow Burn.mp3");
+        assert_eq!(track.file_path, expected_path);
+        assert_eq!(track.artist.as_deref(), Some("Kacey"));
+        assert_eq!(track.key.as_deref(), Some("Am"));
+        assert_eq!(track.year.as_deref(), Some("2019"));
+        assert_eq!(track.date_added, Some(1_682_899_200));
+
+        // By crate path, not file name ('%' < ' ' < '.' would put "Sets 2019" first)
+        let names: Vec<&str> = library.crates.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["Sets", "Sets 2019"]);
+        let sets = &library.crates[0];
+        assert!(sets.is_folder);
+        let children: Vec<&str> = sets.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(children, ["Friday", "Saturday"]);
+        assert_eq!(sets.children[1].track_paths, vec![expected_path]);
+    }
+
+    #[test]
+    fn test_truncated_record_is_an_error() {
+        let mut data = text(b"vrsn", "2.0");
+        data.extend(b"otrk\x00\x00\x01\x00short");
+        assert!(parse_database(&data, Path::new("/")).is_err());
+        assert!(parse_database(&text(b"otrk", ""), Path::new("/")).is_err());
+    }
+}
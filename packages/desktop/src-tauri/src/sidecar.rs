@@ -0,0 +1,377 @@
+// Analysis sidecar supervisor
+//
+// The Python sidecar (binaries/api) used to be spawned from the webview, which
+// picked a random port without checking it and lost track of the child
+// whenever the window reloaded. The Rust side owns it now: a supervisor task
+// picks a port that is actually free, waits for SIDECAR_READY, polls /health,
+// restarts the process with exponential backoff when it dies or stops
+// answering, and kills it when the app exits. Every state change is pushed to
+// the webview as a `sidecar://status` event.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+use tauri_plugin_shell::ShellExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Event emitted to the webview on every status change
+pub const SIDECAR_STATUS_EVENT: &str = "sidecar://status";
+
+/// PyInstaller binaries unpack themselves before starting; give them time
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+const HEALTH_INTERVAL: Duration = Duration::from_secs(5);
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(3);
+/// Consecutive failed health checks before the sidecar is restarted
+const MAX_HEALTH_FAILURES: u32 = 3;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// A sidecar that stayed up this long resets the backoff
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum SidecarState {
+    /// Not started, or stopped on exit
+    Idle,
+    /// Spawned, waiting for SIDECAR_READY
+    Starting,
+    /// Listening and answering /health
+    Ready,
+    /// Exited or unhealthy; a restart is scheduled in `retry_in_ms`
+    Error,
+}
+
+/// Body of the sidecar's /health endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct HealthData {
+    status: String,
+    version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct SidecarStatus {
+    state: SidecarState,
+    /// http://127.0.0.1:<port> once ready
+    base_url: Option<String>,
+    pid: Option<u32>,
+    health: Option<HealthData>,
+    /// Restarts since the app started (or since the last manual restart)
+    restarts: u32,
+    last_error: Option<String>,
+    retry_in_ms: Option<u64>,
+}
+
+static STATUS: Lazy<Mutex<SidecarStatus>> = Lazy::new(|| {
+    Mutex::new(SidecarStatus {
+        state: SidecarState::Idle,
+        base_url: None,
+        pid: None,
+        health: None,
+        restarts: 0,
+        last_error: None,
+        retry_in_ms: None,
+    })
+});
+
+/// The running child process, if any
+static CHILD: Lazy<Mutex<Option<CommandChild>>> = Lazy::new(|| Mutex::new(None));
+
+/// The supervisor task, if running
+static TASK: Lazy<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Current status, for the webview to seed its state
+pub fn status() -> SidecarStatus {
+    STATUS.lock().map(|s| s.clone()).unwrap_or_else(|p| p.into_inner().clone())
+}
+
+fn update(app: &AppHandle, change: impl FnOnce(&mut SidecarStatus)) {
+    let snapshot = {
+        let mut status = STATUS.lock().unwrap_or_else(|p| p.into_inner());
+        change(&mut status);
+        status.clone()
+    };
+    if let Err(e) = app.emit(SIDECAR_STATUS_EVENT, &snapshot) {
+        eprintln!("[Sidecar] Failed to emit status: {}", e);
+    }
+}
+
+fn kill_child() {
+    let child = CHILD.lock().unwrap_or_else(|p| p.into_inner()).take();
+    if let Some(child) = child {
+        let pid = child.pid();
+        match child.kill() {
+            Ok(()) => println!("[Sidecar] Killed process {}", pid),
+            Err(e) => eprintln!("[Sidecar] Failed to kill process {} (already gone?): {}", pid, e),
+        }
+    }
+}
+
+/// Start supervising the sidecar. Does nothing if the supervisor is already running.
+pub fn start(app: AppHandle) {
+    let mut task = TASK.lock().unwrap_or_else(|p| p.into_inner());
+    if task.is_some() {
+        return;
+    }
+    *task = Some(tauri::async_runtime::spawn(supervise(app)));
+}
+
+/// Kill the sidecar and start over with a fresh backoff
+pub fn restart(app: AppHandle) {
+    stop();
+    if let Ok(mut status) = STATUS.lock() {
+        status.restarts = 0;
+    }
+    start(app);
+}
+
+/// Stop supervising and kill the sidecar (app exit)
+pub fn stop() {
+    if let Some(task) = TASK.lock().unwrap_or_else(|p| p.into_inner()).take() {
+        task.abort();
+    }
+    kill_child();
+    if let Ok(mut status) = STATUS.lock() {
+        status.state = SidecarState::Idle;
+        status.base_url = None;
+        status.pid = None;
+        status.health = None;
+        status.retry_in_ms = None;
+    }
+}
+
+async fn supervise(app: AppHandle) {
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        let started = Instant::now();
+        let error = run_once(&app).await;
+        kill_child();
+
+        if started.elapsed() >= STABLE_AFTER {
+            backoff = INITIAL_BACKOFF;
+        }
+        eprintln!("[Sidecar] {}. Restarting in {:?}", error, backoff);
+        update(&app, |s| {
+            s.state = SidecarState::Error;
+            s.base_url = None;
+            s.pid = None;
+            s.health = None;
+            s.restarts += 1;
+            s.last_error = Some(error);
+            s.retry_in_ms = Some(backoff.as_millis() as u64);
+        });
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Ask the OS for a port nobody is listening on
+fn free_port() -> Result<u16, String> {
+    std::net::TcpListener::bind(("127.0.0.1", 0))
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .map_err(|e| format!("No free port available: {}", e))
+}
+
+/// Port from a "SIDECAR_READY port=NNNNN" line
+fn parse_ready(line: &str) -> Option<u16> {
+    let rest = &line[line.find("SIDECAR_READY")?..];
+    let digits: String = rest[rest.find("port=")? + 5..].chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+fn log_output(stream: &str, bytes: &[u8]) {
+    let line = String::from_utf8_lossy(bytes);
+    println!("[Sidecar {}] {}", stream, line.trim_end());
+}
+
+/// Spawn the sidecar and watch it until it exits or stops answering; returns why
+async fn run_once(app: &AppHandle) -> String {
+    let port = match free_port() {
+        Ok(port) => port,
+        Err(e) => return e,
+    };
+    update(app, |s| {
+        s.state = SidecarState::Starting;
+        s.retry_in_ms = None;
+    });
+
+    let command = match app.shell().sidecar("api") {
+        Ok(command) => command.args(["--port", &port.to_string()]),
+        Err(e) => return format!("Sidecar binary not found: {}", e),
+    };
+    let (mut events, child) = match command.spawn() {
+        Ok(spawned) => spawned,
+        Err(e) => return format!("Failed to spawn sidecar: {}", e),
+    };
+    let pid = child.pid();
+    *CHILD.lock().unwrap_or_else(|p| p.into_inner()) = Some(child);
+    update(app, |s| s.pid = Some(pid));
+
+    // Wait for the ready line
+    let deadline = tokio::time::sleep(READY_TIMEOUT);
+    tokio::pin!(deadline);
+    let ready_port = loop {
+        tokio::select! {
+            _ = &mut deadline => return format!("Sidecar did not report ready within {:?}", READY_TIMEOUT),
+            event = events.recv() => match event {
+                Some(CommandEvent::Stdout(line)) => {
+                    log_output("stdout", &line);
+                    if let Some(port) = parse_ready(&String::from_utf8_lossy(&line)) {
+                        break port;
+                    }
+                }
+                Some(CommandEvent::Stderr(line)) => log_output("stderr", &line),
+                Some(CommandEvent::Error(e)) => eprintln!("[Sidecar] {}", e),
+                Some(CommandEvent::Terminated(payload)) => {
+                    return format!("Sidecar exited during startup (code {:?})", payload.code)
+                }
+                None => return "Sidecar exited during startup".to_string(),
+                Some(_) => {}
+            },
+        }
+    };
+
+    let health = check_health(ready_port).await.ok();
+    update(app, |s| {
+        s.state = SidecarState::Ready;
+        s.base_url = Some(format!("http://127.0.0.1:{}", ready_port));
+        s.health = health;
+        s.last_error = None;
+    });
+
+    // Watch it: exit ends the run, so do repeated failed health checks
+    let mut interval = tokio::time::interval(HEALTH_INTERVAL);
+    interval.tick().await;
+    let mut failures = 0;
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Some(CommandEvent::Stdout(line)) => log_output("stdout", &line),
+                Some(CommandEvent::Stderr(line)) => log_output("stderr", &line),
+                Some(CommandEvent::Error(e)) => eprintln!("[Sidecar] {}", e),
+                Some(CommandEvent::Terminated(payload)) => {
+                    return format!("Sidecar exited (code {:?}, signal {:?})", payload.code, payload.signal)
+                }
+                None => return "Sidecar exited".to_string(),
+                Some(_) => {}
+            },
+            _ = interval.tick() => match check_health(ready_port).await {
+                Ok(health) => {
+                    failures = 0;
+                    if status().health.as_ref() != Some(&health) {
+                        update(app, |s| s.health = Some(health));
+                    }
+                }
+                Err(e) => {
+                    failures += 1;
+                    eprintln!("[Sidecar] Health check failed ({}/{}): {}", failures, MAX_HEALTH_FAILURES, e);
+                    if failures >= MAX_HEALTH_FAILURES {
+                        return format!("Sidecar stopped answering health checks: {}", e);
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Body of a plain HTTP/1.1 200 response
+fn parse_response(response: &[u8]) -> Result<String, String> {
+    let text = String::from_utf8_lossy(response);
+    let (head, body) = text.split_once("\r\n\r\n").ok_or_else(|| "Malformed HTTP response".to_string())?;
+    let status_line = head.lines().next().unwrap_or_default();
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(format!("Unexpected response: {}", status_line));
+    }
+    Ok(body.trim().to_string())
+}
+
+/// GET `path` on the sidecar's port and return the response body
+async fn http_get(port: u16, path: &str, timeout: Duration) -> Result<String, String> {
+    let request = async {
+        let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .map_err(|e| format!("Connection failed: {}", e))?;
+        let request = format!("GET {} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nConnection: close\r\n\r\n", path, port);
+        stream.write_all(request.as_bytes()).await.map_err(|e| format!("Request failed: {}", e))?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.map_err(|e| format!("Read failed: {}", e))?;
+        parse_response(&response)
+    };
+    tokio::time::timeout(timeout, request)
+        .await
+        .map_err(|_| format!("No answer within {:?}", timeout))?
+}
+
+/// GET /health on the sidecar's port
+async fn check_health(port: u16) -> Result<HealthData, String> {
+    let body = http_get(port, "/health", HEALTH_TIMEOUT).await?;
+    serde_json::from_str(&body).map_err(|e| format!("Invalid health response: {}", e))
+}
+
+/// Port of the sidecar while it is ready
+fn ready_port() -> Option<u16> {
+    let status = status();
+    if status.state != SidecarState::Ready {
+        return None;
+    }
+    status.base_url?.rsplit(':').next()?.parse().ok()
+}
+
+/// Whether the sidecar is currently accepting requests
+pub(crate) fn is_ready() -> bool {
+    ready_port().is_some()
+}
+
+/// GET `path` (including the query string) on the running sidecar
+pub(crate) async fn get(path: &str, timeout: Duration) -> Result<String, String> {
+    let port = ready_port().ok_or_else(|| "Sidecar is not ready".to_string())?;
+    http_get(port, path, timeout).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ready_line_and_free_port() {
+        assert_eq!(parse_ready("SIDECAR_READY port=52341"), Some(52341));
+        assert_eq!(parse_ready("[2026-01-21] SIDECAR_READY port=49999 v0.2.1"), Some(49999));
+        assert_eq!(parse_ready("INFO: Uvicorn running on http://127.0.0.1:8000 port=8000"), None);
+        assert_eq!(parse_ready("SIDECAR_READY port=99999"), None);
+
+        let port = free_port().unwrap();
+        assert!(std::net::TcpListener::bind(("127.0.0.1", port)).is_ok());
+    }
+
+    #[test]
+    fn test_health_check() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            for body in ["{\"status\":\"running\",\"version\":\"0.2.0\"}", "oops"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0u8; 512];
+                let _ = stream.read(&mut request).unwrap();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let health = tauri::async_runtime::block_on(check_health(port)).unwrap();
+        assert_eq!(health, HealthData { status: "running".to_string(), version: "0.2.0".to_string() });
+        assert!(tauri::async_runtime::block_on(check_health(port)).is_err());
+        server.join().unwrap();
+
+        assert!(parse_response(b"HTTP/1.1 500 Internal Server Error\r\n\r\n{}").is_err());
+    }
+}
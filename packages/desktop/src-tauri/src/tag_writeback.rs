@@ -0,0 +1,1299 @@
+// Write-back of BPM, key, energy and Pika tags into the audio files themselves
+//
+// Pika's SQLite is invisible to other DJ software; the file's own tags travel
+// with it. ID3v2 (MP3, and the ID3 chunk of WAV/AIFF) goes through `id3`.
+// FLAC and Ogg (Vorbis / Opus) comments and MP4 `ilst` atoms are edited
+// directly: only the comment block / header pages / `moov` atom are rebuilt,
+// the audio bytes are copied as-is. Every format is written to a temp file
+// that then replaces the original.
+//
+// Every real write first records the previous values in a JSON journal, so a
+// batch can be undone later - but only for fields nobody has changed since.
+
+use id3::TagLike;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::path::{Path, PathBuf};
+
+use crate::error::PikaError;
+use crate::keys::KeyNotation;
+
+/// Pika data that can be written into a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum TagField {
+    Bpm,
+    Key,
+    Energy,
+    Tags,
+}
+
+/// Pika data for a single file. `None` leaves the field untouched.
+#[derive(Debug, Clone, Deserialize, Type)]
+pub struct AudioTagUpdate {
+    pub file_path: String,
+    pub bpm: Option<f64>,
+    /// Any notation `keys::parse` understands; written in `key_notation`
+    pub key: Option<String>,
+    pub energy: Option<f64>,
+    /// An empty list removes the field
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Type)]
+#[serde(default)]
+pub struct AudioTagWriteOptions {
+    pub key_notation: KeyNotation,
+    /// Report what would change without touching any file
+    pub dry_run: bool,
+}
+
+/// One field of one file, before and after
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct TagChange {
+    field: TagField,
+    /// Frame, comment or atom name in this file's format (TBPM, INITIALKEY, tmpo, ...)
+    native: String,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct FileTagChanges {
+    file_path: String,
+    changes: Vec<TagChange>,
+}
+
+#[derive(Debug, Serialize, Type)]
+pub struct TagWriteFailure {
+    file_path: String,
+    error: String,
+}
+
+#[derive(Debug, Serialize, Type)]
+pub struct AudioTagWriteReport {
+    dry_run: bool,
+    /// Files with at least one changed field (for an undo: the fields restored)
+    files: Vec<FileTagChanges>,
+    /// Files (or, for an undo, fields) that were left alone, and why
+    failed: Vec<TagWriteFailure>,
+    /// Pass to `undo_audio_tag_write` to revert this write
+    journal_path: Option<String>,
+}
+
+/// What a write changed, recorded before the files are touched
+#[derive(Debug, Serialize, Deserialize)]
+struct TagJournal {
+    created_at: String,
+    files: Vec<FileTagChanges>,
+}
+
+/// A file's tags, loaded in memory and saved back in its own format
+trait TagStore {
+    fn native_name(&self, field: TagField) -> String;
+    fn get(&self, field: TagField) -> Option<String>;
+    fn set(&mut self, field: TagField, value: Option<&str>);
+    fn save(&self, path: &Path) -> Result<(), String>;
+
+    /// TBPM and MP4 tmpo only hold whole numbers
+    fn bpm_text(&self, bpm: f64) -> String {
+        format_number(bpm)
+    }
+}
+
+/// 128.0 -> "128", 127.504 -> "127.5"
+fn format_number(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!("{}.pika-tmp", file_name))
+}
+
+/// Replace a file atomically via a temp file + rename
+fn replace_file(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let tmp_path = temp_path(path);
+    std::fs::write(&tmp_path, bytes).map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+    std::fs::rename(&tmp_path, path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp_path);
+        format!("Failed to replace {}: {}", path.display(), e)
+    })
+}
+
+/// Edit a copy of the file, then swap it in, so a failed write never leaves a half-written original
+fn edit_copy(path: &Path, edit: impl FnOnce(&Path) -> Result<(), String>) -> Result<(), String> {
+    let tmp_path = temp_path(path);
+    let result = std::fs::copy(path, &tmp_path)
+        .map_err(|e| format!("Failed to copy {}: {}", path.display(), e))
+        .and_then(|_| edit(&tmp_path))
+        .and_then(|_| std::fs::rename(&tmp_path, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e)));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+fn open_store(path: &Path) -> Result<Box<dyn TagStore>, String> {
+    if !path.is_file() {
+        return Err(format!("Audio file not found: {}", path.display()));
+    }
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "mp3" | "wav" | "wave" | "aif" | "aiff" | "aifc" => Ok(Box::new(Id3Store::open(path)?)),
+        "flac" => Ok(Box::new(FlacStore::open(path)?)),
+        "ogg" | "oga" | "opus" => Ok(Box::new(OggStore::open(path)?)),
+        "m4a" | "mp4" | "alac" => Ok(Box::new(Mp4Store::open(path)?)),
+        _ => Err(format!("Writing tags to .{} files is not supported", extension)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ID3v2
+
+struct Id3Store {
+    tag: id3::Tag,
+}
+
+impl Id3Store {
+    fn open(path: &Path) -> Result<Self, String> {
+        let tag = match id3::Tag::read_from_path(path) {
+            Ok(tag) => tag,
+            Err(e) if matches!(e.kind, id3::ErrorKind::NoTag) => id3::Tag::new(),
+            Err(e) => return Err(format!("Failed to read ID3 tag from {}: {}", path.display(), e)),
+        };
+        Ok(Self { tag })
+    }
+
+    /// Text frame id, or the TXXX description for fields without a standard frame
+    fn frame(field: TagField) -> (&'static str, Option<&'static str>) {
+        match field {
+            TagField::Bpm => ("TBPM", None),
+            TagField::Key => ("TKEY", None),
+            TagField::Energy => ("TXXX", Some("EnergyLevel")),
+            TagField::Tags => ("TXXX", Some("PIKA_TAGS")),
+        }
+    }
+}
+
+impl TagStore for Id3Store {
+    fn native_name(&self, field: TagField) -> String {
+        match Self::frame(field) {
+            (id, Some(description)) => format!("{}:{}", id, description),
+            (id, None) => id.to_string(),
+        }
+    }
+
+    fn get(&self, field: TagField) -> Option<String> {
+        match Self::frame(field) {
+            (_, Some(description)) => self
+                .tag
+                .extended_texts()
+                .find(|t| t.description.eq_ignore_ascii_case(description))
+                .map(|t| t.value.clone()),
+            (id, None) => self.tag.get(id).and_then(|f| f.content().text()).map(str::to_string),
+        }
+    }
+
+    fn set(&mut self, field: TagField, value: Option<&str>) {
+        match Self::frame(field) {
+            (_, Some(description)) => {
+                let existing: Vec<String> = self
+                    .tag
+                    .extended_texts()
+                    .filter(|t| t.description.eq_ignore_ascii_case(description))
+                    .map(|t| t.description.clone())
+                    .collect();
+                for old in existing {
+                    self.tag.remove_extended_text(Some(&old), None);
+                }
+                if let Some(value) = value {
+                    self.tag.add_frame(id3::frame::ExtendedText {
+                        description: description.to_string(),
+                        value: value.to_string(),
+                    });
+                }
+            }
+            (id, None) => match value {
+                Some(value) => self.tag.set_text(id, value),
+                None => {
+                    self.tag.remove(id);
+                }
+            },
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let version = match self.tag.version() {
+            id3::Version::Id3v22 => id3::Version::Id3v23,
+            version => version,
+        };
+        edit_copy(path, |tmp_path| {
+            self.tag
+                .write_to_path(tmp_path, version)
+                .map_err(|e| format!("Failed to write ID3 tag to {}: {}", path.display(), e))
+        })
+    }
+
+    fn bpm_text(&self, bpm: f64) -> String {
+        format!("{}", bpm.round() as i64)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Vorbis comments (FLAC and Ogg)
+
+/// A Vorbis comment list. Comments are kept as raw bytes, so the ones Pika
+/// doesn't edit are written back exactly as read even when they aren't UTF-8.
+struct VorbisComments {
+    vendor: Vec<u8>,
+    comments: Vec<Vec<u8>>,
+}
+
+fn read_u32_le(bytes: &[u8], pos: usize) -> Option<u32> {
+    bytes.get(pos..pos + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+impl VorbisComments {
+    fn new() -> Self {
+        Self { vendor: b"Pika".to_vec(), comments: Vec::new() }
+    }
+
+    /// Parse the list at the start of `body`; also returns how many bytes it took
+    fn parse(body: &[u8]) -> Option<(Self, usize)> {
+        let vendor_len = read_u32_le(body, 0)? as usize;
+        let vendor = body.get(4..4 + vendor_len)?.to_vec();
+        let mut pos = 4 + vendor_len;
+        let count = read_u32_le(body, pos)?;
+        pos += 4;
+        let mut comments = Vec::new();
+        for _ in 0..count {
+            let len = read_u32_le(body, pos)? as usize;
+            comments.push(body.get(pos + 4..pos + 4 + len)?.to_vec());
+            pos += 4 + len;
+        }
+        Some((Self { vendor, comments }, pos))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&(self.vendor.len() as u32).to_le_bytes());
+        body.extend_from_slice(&self.vendor);
+        body.extend_from_slice(&(self.comments.len() as u32).to_le_bytes());
+        for comment in &self.comments {
+            body.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+            body.extend_from_slice(comment);
+        }
+        body
+    }
+
+    fn name(field: TagField) -> &'static str {
+        match field {
+            TagField::Bpm => "BPM",
+            TagField::Key => "INITIALKEY",
+            TagField::Energy => "ENERGYLEVEL",
+            TagField::Tags => "PIKA_TAGS",
+        }
+    }
+
+    /// The value of `comment` if its name is `name` (names are case-insensitive)
+    fn value<'a>(comment: &'a [u8], name: &str) -> Option<&'a [u8]> {
+        let eq = comment.iter().position(|&b| b == b'=')?;
+        comment[..eq].eq_ignore_ascii_case(name.as_bytes()).then(|| &comment[eq + 1..])
+    }
+
+    fn get(&self, field: TagField) -> Option<String> {
+        let name = Self::name(field);
+        self.comments
+            .iter()
+            .find_map(|c| Self::value(c, name))
+            .map(|value| String::from_utf8_lossy(value).into_owned())
+    }
+
+    fn set(&mut self, field: TagField, value: Option<&str>) {
+        let name = Self::name(field);
+        self.comments.retain(|c| Self::value(c, name).is_none());
+        if let Some(value) = value {
+            self.comments.push(format!("{}={}", name, value).into_bytes());
+        }
+    }
+}
+
+const FLAC_VORBIS_COMMENT: u8 = 4;
+
+struct FlacStore {
+    data: Vec<u8>,
+    /// (block type, body) in file order; the comment block's body is rebuilt on save
+    blocks: Vec<(u8, Vec<u8>)>,
+    audio_offset: usize,
+    comments: VorbisComments,
+}
+
+impl FlacStore {
+    fn open(path: &Path) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let invalid = || format!("Not a valid FLAC file: {}", path.display());
+        if !data.starts_with(b"fLaC") {
+            return Err(invalid());
+        }
+
+        let mut store = FlacStore {
+            data: Vec::new(),
+            blocks: Vec::new(),
+            audio_offset: 0,
+            comments: VorbisComments::new(),
+        };
+        let mut pos = 4;
+        loop {
+            let header = data.get(pos..pos + 4).ok_or_else(invalid)?;
+            let (last, kind) = (header[0] & 0x80 != 0, header[0] & 0x7f);
+            let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+            let body = data.get(pos + 4..pos + 4 + len).ok_or_else(invalid)?;
+            if kind == FLAC_VORBIS_COMMENT {
+                store.comments = VorbisComments::parse(body).ok_or_else(invalid)?.0;
+            }
+            store.blocks.push((kind, body.to_vec()));
+            pos += 4 + len;
+            if last {
+                break;
+            }
+        }
+        store.audio_offset = pos;
+        store.data = data;
+        Ok(store)
+    }
+}
+
+impl TagStore for FlacStore {
+    fn native_name(&self, field: TagField) -> String {
+        VorbisComments::name(field).to_string()
+    }
+
+    fn get(&self, field: TagField) -> Option<String> {
+        self.comments.get(field)
+    }
+
+    fn set(&mut self, field: TagField, value: Option<&str>) {
+        self.comments.set(field, value)
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let comments = self.comments.to_bytes();
+        if comments.len() >= 1 << 24 {
+            return Err(format!("Vorbis comments too large for {}", path.display()));
+        }
+
+        let mut blocks: Vec<(u8, &[u8])> = self
+            .blocks
+            .iter()
+            .map(|(kind, body)| match *kind {
+                FLAC_VORBIS_COMMENT => (*kind, comments.as_slice()),
+                _ => (*kind, body.as_slice()),
+            })
+            .collect();
+        if !blocks.iter().any(|(kind, _)| *kind == FLAC_VORBIS_COMMENT) {
+            // Right after STREAMINFO, which must stay first
+            blocks.insert(1.min(blocks.len()), (FLAC_VORBIS_COMMENT, comments.as_slice()));
+        }
+
+        let mut out = Vec::with_capacity(self.data.len() + comments.len());
+        out.extend_from_slice(b"fLaC");
+        let count = blocks.len();
+        for (i, (kind, body)) in blocks.into_iter().enumerate() {
+            let last = if i + 1 == count { 0x80 } else { 0 };
+            out.push(last | kind);
+            out.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+            out.extend_from_slice(body);
+        }
+        out.extend_from_slice(&self.data[self.audio_offset..]);
+        replace_file(path, &out)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Ogg Vorbis / Opus comment headers
+//
+// The comments are the second header packet. The header packets are repaged
+// (their page count can change), and later pages of the stream are copied with
+// their sequence numbers shifted to match.
+
+const OGG_CONTINUED: u8 = 0x01;
+const OGG_FIRST_PAGE: u8 = 0x02;
+/// Granule position of a page on which no packet ends
+const OGG_NO_GRANULE: u64 = u64::MAX;
+
+/// Where a page sits in the file
+struct OggPage {
+    header_type: u8,
+    serial: u32,
+    sequence: u32,
+    lacing: Vec<u8>,
+    body: std::ops::Range<usize>,
+    end: usize,
+}
+
+/// CRC-32 as Ogg defines it (polynomial 0x04c11db7, no reflection, no final xor)
+fn ogg_crc(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0u32, |crc, &byte| {
+        (0..8).fold(crc ^ (u32::from(byte) << 24), |crc, _| {
+            if crc & 0x8000_0000 != 0 { (crc << 1) ^ 0x04c1_1db7 } else { crc << 1 }
+        })
+    })
+}
+
+/// Parse the page at `pos`, checking its CRC
+fn read_ogg_page(data: &[u8], pos: usize) -> Option<OggPage> {
+    let header = data.get(pos..pos + 27)?;
+    if &header[..4] != b"OggS" || header[4] != 0 {
+        return None;
+    }
+    let lacing = data.get(pos + 27..pos + 27 + header[26] as usize)?.to_vec();
+    let body_start = pos + 27 + lacing.len();
+    let end = body_start + lacing.iter().map(|&l| l as usize).sum::<usize>();
+    let mut page = data.get(pos..end)?.to_vec();
+    let crc = read_u32_le(&page, 22)?;
+    page[22..26].fill(0);
+    if ogg_crc(&page) != crc {
+        return None;
+    }
+    Some(OggPage {
+        header_type: header[5],
+        serial: read_u32_le(header, 14)?,
+        sequence: read_u32_le(header, 18)?,
+        lacing,
+        body: body_start..end,
+        end,
+    })
+}
+
+fn write_ogg_page(out: &mut Vec<u8>, header_type: u8, granule: u64, serial: u32, sequence: u32, lacing: &[u8], body: &[u8]) {
+    let start = out.len();
+    out.extend_from_slice(b"OggS");
+    out.push(0);
+    out.push(header_type);
+    out.extend_from_slice(&granule.to_le_bytes());
+    out.extend_from_slice(&serial.to_le_bytes());
+    out.extend_from_slice(&sequence.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.push(lacing.len() as u8);
+    out.extend_from_slice(lacing);
+    out.extend_from_slice(body);
+    let crc = ogg_crc(&out[start..]);
+    out[start + 22..start + 26].copy_from_slice(&crc.to_le_bytes());
+}
+
+/// Lay `packets` out on fresh pages, the last one ending with the last packet.
+/// Returns the number of pages written.
+fn write_ogg_packets(out: &mut Vec<u8>, packets: &[&[u8]], first_header_type: u8, serial: u32, sequence: u32) -> u32 {
+    // (lacing value, ends a packet)
+    let mut segments = Vec::new();
+    for packet in packets {
+        let mut rest = packet.len();
+        loop {
+            let len = rest.min(255);
+            segments.push((len as u8, len < 255));
+            rest -= len;
+            if len < 255 {
+                break;
+            }
+        }
+    }
+    let body = packets.concat();
+
+    let (mut pos, mut pages, mut header_type) = (0, 0, first_header_type);
+    for chunk in segments.chunks(255) {
+        let lacing: Vec<u8> = chunk.iter().map(|&(len, _)| len).collect();
+        let len: usize = lacing.iter().map(|&l| l as usize).sum();
+        // Header packets all sit at granule position 0
+        let granule = if chunk.iter().any(|&(_, ends)| ends) { 0 } else { OGG_NO_GRANULE };
+        write_ogg_page(out, header_type, granule, serial, sequence + pages, &lacing, &body[pos..pos + len]);
+        header_type = if chunk.last().is_some_and(|&(_, ends)| !ends) { OGG_CONTINUED } else { 0 };
+        pos += len;
+        pages += 1;
+    }
+    pages
+}
+
+struct OggStore {
+    data: Vec<u8>,
+    serial: u32,
+    first_sequence: u32,
+    /// Header packets (identification, comments, and Vorbis' setup)
+    packets: Vec<Vec<u8>>,
+    /// Pages the header packets took up, and where the audio pages start
+    header_pages: u32,
+    audio_offset: usize,
+    /// "\x03vorbis" or "OpusTags"
+    comment_magic: &'static [u8],
+    comments: VorbisComments,
+    /// Whatever follows the comment list (Vorbis' framing bit, Opus padding)
+    comment_tail: Vec<u8>,
+}
+
+impl OggStore {
+    fn open(path: &Path) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let invalid = |reason: &str| format!("Not a valid Ogg file ({}): {}", reason, path.display());
+
+        let first = read_ogg_page(&data, 0)
+            .filter(|page| page.header_type & OGG_FIRST_PAGE != 0)
+            .ok_or_else(|| invalid("bad first page"))?;
+        let (serial, first_sequence) = (first.serial, first.sequence);
+        let (mut packets, mut pending): (Vec<Vec<u8>>, Vec<u8>) = (Vec::new(), Vec::new());
+        let mut header_count = None;
+        let (mut pos, mut header_pages) = (0, 0);
+        while header_count.is_none_or(|count| packets.len() < count) {
+            let page = read_ogg_page(&data, pos).ok_or_else(|| invalid("truncated or corrupt header pages"))?;
+            if page.serial != serial {
+                return Err(format!("Writing tags to multiplexed Ogg files is not supported: {}", path.display()));
+            }
+            let mut offset = page.body.start;
+            for &len in &page.lacing {
+                pending.extend_from_slice(&data[offset..offset + len as usize]);
+                offset += len as usize;
+                if len < 255 {
+                    packets.push(std::mem::take(&mut pending));
+                }
+            }
+            pos = page.end;
+            header_pages += 1;
+
+            if header_count.is_none() {
+                header_count = match packets.first() {
+                    Some(id) if id.starts_with(b"\x01vorbis") => Some(3),
+                    Some(id) if id.starts_with(b"OpusHead") => Some(2),
+                    Some(_) => return Err(format!("Writing tags to this Ogg codec is not supported: {}", path.display())),
+                    None => None,
+                };
+            }
+        }
+        // The last header packet must end its page, as the Vorbis and Opus specs require
+        if packets.len() != header_count.unwrap_or(0) || !pending.is_empty() {
+            return Err(invalid("audio shares a page with the headers"));
+        }
+
+        let comment_magic: &'static [u8] = if packets[0].starts_with(b"OpusHead") { b"OpusTags" } else { b"\x03vorbis" };
+        let body = packets[1].strip_prefix(comment_magic).ok_or_else(|| invalid("missing comment header"))?;
+        let (comments, used) = VorbisComments::parse(body).ok_or_else(|| invalid("bad comment header"))?;
+        let comment_tail = body[used..].to_vec();
+
+        Ok(OggStore {
+            serial,
+            first_sequence,
+            packets,
+            header_pages,
+            audio_offset: pos,
+            comment_magic,
+            comments,
+            comment_tail,
+            data,
+        })
+    }
+}
+
+impl TagStore for OggStore {
+    fn native_name(&self, field: TagField) -> String {
+        VorbisComments::name(field).to_string()
+    }
+
+    fn get(&self, field: TagField) -> Option<String> {
+        self.comments.get(field)
+    }
+
+    fn set(&mut self, field: TagField, value: Option<&str>) {
+        self.comments.set(field, value)
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let comment_packet = [self.comment_magic, &self.comments.to_bytes(), &self.comment_tail].concat();
+        let mut out = Vec::with_capacity(self.data.len() + comment_packet.len());
+
+        // The identification header gets a page of its own
+        let mut pages = write_ogg_packets(&mut out, &[&self.packets[0]], OGG_FIRST_PAGE, self.serial, self.first_sequence);
+        let rest: Vec<&[u8]> = std::iter::once(comment_packet.as_slice())
+            .chain(self.packets[2..].iter().map(Vec::as_slice))
+            .collect();
+        pages += write_ogg_packets(&mut out, &rest, 0, self.serial, self.first_sequence + pages);
+
+        // Audio pages are copied, renumbered if the header page count changed
+        let shift = pages.wrapping_sub(self.header_pages);
+        let mut pos = self.audio_offset;
+        while pos < self.data.len() {
+            let Some(page) = read_ogg_page(&self.data, pos) else {
+                // Trailing bytes that aren't a page are kept as they are
+                out.extend_from_slice(&self.data[pos..]);
+                break;
+            };
+            let start = out.len();
+            out.extend_from_slice(&self.data[pos..page.end]);
+            if shift != 0 && page.serial == self.serial {
+                out[start + 18..start + 22].copy_from_slice(&page.sequence.wrapping_add(shift).to_le_bytes());
+                out[start + 22..start + 26].fill(0);
+                let crc = ogg_crc(&out[start..]);
+                out[start + 22..start + 26].copy_from_slice(&crc.to_le_bytes());
+            }
+            pos = page.end;
+        }
+        replace_file(path, &out)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// MP4 / M4A metadata atoms (moov > udta > meta > ilst)
+
+/// An atom inside `moov`. Containers keep their children parsed; leaves keep their payload.
+#[derive(Debug, Clone)]
+struct Atom {
+    kind: [u8; 4],
+    /// Leaf payload, or for containers the bytes before the children (`meta`'s version/flags)
+    data: Vec<u8>,
+    children: Option<Vec<Atom>>,
+}
+
+const MP4_CONTAINERS: [&[u8; 4]; 9] = [b"moov", b"udta", b"meta", b"ilst", b"trak", b"mdia", b"minf", b"stbl", b"edts"];
+const ITUNES_MEAN: &str = "com.apple.iTunes";
+/// `data` atom value types
+const MP4_UTF8: u32 = 1;
+const MP4_INTEGER: u32 = 21;
+
+/// Where an atom sits in its parent's bytes
+struct AtomSpan {
+    kind: [u8; 4],
+    start: usize,
+    header: usize,
+    end: usize,
+}
+
+fn atom_spans(bytes: &[u8]) -> Result<Vec<AtomSpan>, String> {
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    while pos + 8 <= bytes.len() {
+        let size = u32::from_be_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]]) as u64;
+        let kind = [bytes[pos + 4], bytes[pos + 5], bytes[pos + 6], bytes[pos + 7]];
+        let (header, size) = match size {
+            0 => (8, (bytes.len() - pos) as u64),
+            1 => {
+                let large = bytes.get(pos + 8..pos + 16).ok_or("Truncated MP4 atom")?;
+                (16, u64::from_be_bytes(large.try_into().unwrap_or_default()))
+            }
+            size => (8, size),
+        };
+        let end = pos as u64 + size;
+        if size < header as u64 || end > bytes.len() as u64 {
+            return Err(format!("Invalid MP4 atom '{}'", String::from_utf8_lossy(&kind)));
+        }
+        spans.push(AtomSpan { kind, start: pos, header, end: end as usize });
+        pos = end as usize;
+    }
+    Ok(spans)
+}
+
+fn parse_atoms(bytes: &[u8], parent: &[u8; 4]) -> Result<Vec<Atom>, String> {
+    atom_spans(bytes)?
+        .into_iter()
+        .map(|AtomSpan { kind, start, header, end }| {
+            let payload = &bytes[start + header..end];
+            // Items in ilst are containers of mean/name/data
+            if MP4_CONTAINERS.contains(&&kind) || parent == b"ilst" {
+                // ISO meta is a full box (4 bytes version/flags); QuickTime's is not
+                let prefix = if &kind == b"meta" && payload.get(8..12) == Some(b"hdlr") { 4 } else { 0 };
+                let prefix = prefix.min(payload.len());
+                Ok(Atom {
+                    kind,
+                    data: payload[..prefix].to_vec(),
+                    children: Some(parse_atoms(&payload[prefix..], &kind)?),
+                })
+            } else {
+                Ok(Atom { kind, data: payload.to_vec(), children: None })
+            }
+        })
+        .collect()
+}
+
+impl Atom {
+    fn leaf(kind: &[u8; 4], data: Vec<u8>) -> Self {
+        Atom { kind: *kind, data, children: None }
+    }
+
+    fn container(kind: &[u8; 4], data: Vec<u8>, children: Vec<Atom>) -> Self {
+        Atom { kind: *kind, data, children: Some(children) }
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        let start = out.len();
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&self.kind);
+        out.extend_from_slice(&self.data);
+        for child in self.children.iter().flatten() {
+            child.write(out);
+        }
+        let size = (out.len() - start) as u32;
+        out[start..start + 4].copy_from_slice(&size.to_be_bytes());
+    }
+
+    fn child(&self, kind: &[u8; 4]) -> Option<&Atom> {
+        self.children.as_ref()?.iter().find(|a| &a.kind == kind)
+    }
+
+    /// The child of this kind, created by `make` when missing
+    fn child_or_insert(&mut self, kind: &[u8; 4], make: impl FnOnce() -> Atom) -> &mut Atom {
+        let children = self.children.get_or_insert_with(Vec::new);
+        let index = match children.iter().position(|a| &a.kind == kind) {
+            Some(index) => index,
+            None => {
+                children.push(make());
+                children.len() - 1
+            }
+        };
+        &mut children[index]
+    }
+
+    /// Payload of a `data` child, without its type and locale
+    fn data_value(&self) -> Option<(u32, &[u8])> {
+        let data = &self.child(b"data")?.data;
+        let kind = u32::from_be_bytes(data.get(..4)?.try_into().ok()?);
+        Some((kind & 0x00ff_ffff, data.get(8..)?))
+    }
+
+    /// Name of a `----` freeform item (the part after the "mean" namespace)
+    fn freeform_name(&self) -> Option<String> {
+        self.child(b"name").and_then(|n| n.data.get(4..)).map(|n| String::from_utf8_lossy(n).into_owned())
+    }
+
+    /// Add `delta` to every chunk offset at or after `from` (stco / co64 in every track)
+    fn shift_chunk_offsets(&mut self, from: u64, delta: i64) {
+        for child in self.children.iter_mut().flatten() {
+            child.shift_chunk_offsets(from, delta);
+        }
+        let width = match &self.kind {
+            b"stco" => 4,
+            b"co64" => 8,
+            _ => return,
+        };
+        let count = self.data.get(4..8).map_or(0, |c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]) as usize);
+        for i in 0..count {
+            let pos = 8 + i * width;
+            let Some(entry) = self.data.get_mut(pos..pos + width) else { break };
+            let mut raw = [0u8; 8];
+            raw[8 - width..].copy_from_slice(entry);
+            let offset = u64::from_be_bytes(raw);
+            if offset >= from {
+                let shifted = (offset as i64 + delta) as u64;
+                entry.copy_from_slice(&shifted.to_be_bytes()[8 - width..]);
+            }
+        }
+    }
+}
+
+struct Mp4Store {
+    data: Vec<u8>,
+    /// Byte range of the top-level moov atom
+    moov_range: (usize, usize),
+    moov: Atom,
+}
+
+impl Mp4Store {
+    fn open(path: &Path) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let AtomSpan { start, header, end, .. } = atom_spans(&data)?
+            .into_iter()
+            .find(|span| &span.kind == b"moov")
+            .ok_or_else(|| format!("No moov atom in {}", path.display()))?;
+        let moov = Atom::container(b"moov", Vec::new(), parse_atoms(&data[start + header..end], b"moov")?);
+        Ok(Self { data, moov_range: (start, end), moov })
+    }
+
+    fn ilst(&self) -> Option<&Atom> {
+        self.moov.child(b"udta")?.child(b"meta")?.child(b"ilst")
+    }
+
+    fn ilst_mut(&mut self) -> &mut Atom {
+        self.moov
+            .child_or_insert(b"udta", || Atom::container(b"udta", Vec::new(), Vec::new()))
+            .child_or_insert(b"meta", || {
+                // iTunes metadata handler: version/flags, pre_defined, "mdir", "appl", reserved, empty name
+                let mut hdlr = vec![0; 8];
+                hdlr.extend_from_slice(b"mdirappl");
+                hdlr.extend_from_slice(&[0; 9]);
+                Atom::container(b"meta", vec![0; 4], vec![Atom::leaf(b"hdlr", hdlr)])
+            })
+            .child_or_insert(b"ilst", || Atom::container(b"ilst", Vec::new(), Vec::new()))
+    }
+
+    /// Freeform name for fields without a dedicated atom
+    fn freeform(field: TagField) -> Option<&'static str> {
+        match field {
+            TagField::Bpm => None,
+            TagField::Key => Some("initialkey"),
+            TagField::Energy => Some("EnergyLevel"),
+            TagField::Tags => Some("PIKA_TAGS"),
+        }
+    }
+
+    fn is_item(item: &Atom, field: TagField) -> bool {
+        match Self::freeform(field) {
+            None => &item.kind == b"tmpo",
+            Some(name) => &item.kind == b"----" && item.freeform_name().is_some_and(|n| n.eq_ignore_ascii_case(name)),
+        }
+    }
+
+    fn data_atom(kind: u32, value: &[u8]) -> Atom {
+        let mut data = kind.to_be_bytes().to_vec();
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(value);
+        Atom::leaf(b"data", data)
+    }
+}
+
+impl TagStore for Mp4Store {
+    fn native_name(&self, field: TagField) -> String {
+        match Self::freeform(field) {
+            None => "tmpo".to_string(),
+            Some(name) => format!("----:{}:{}", ITUNES_MEAN, name),
+        }
+    }
+
+    fn get(&self, field: TagField) -> Option<String> {
+        let item = self.ilst()?.children.iter().flatten().find(|a| Self::is_item(a, field))?;
+        match item.data_value()? {
+            (MP4_INTEGER, value) if !value.is_empty() && value.len() <= 8 => {
+                let mut raw = [0u8; 8];
+                raw[8 - value.len()..].copy_from_slice(value);
+                Some(u64::from_be_bytes(raw).to_string())
+            }
+            (_, value) => Some(String::from_utf8_lossy(value).into_owned()),
+        }
+    }
+
+    fn set(&mut self, field: TagField, value: Option<&str>) {
+        let item = value.map(|value| match Self::freeform(field) {
+            None => {
+                let bpm = value.parse::<u16>().unwrap_or(0);
+                Atom::container(b"tmpo", Vec::new(), vec![Self::data_atom(MP4_INTEGER, &bpm.to_be_bytes())])
+            }
+            Some(name) => {
+                let mut mean = vec![0; 4];
+                mean.extend_from_slice(ITUNES_MEAN.as_bytes());
+                let mut name_data = vec![0; 4];
+                name_data.extend_from_slice(name.as_bytes());
+                Atom::container(
+                    b"----",
+                    Vec::new(),
+                    vec![
+                        Atom::leaf(b"mean", mean),
+                        Atom::leaf(b"name", name_data),
+                        Self::data_atom(MP4_UTF8, value.as_bytes()),
+                    ],
+                )
+            }
+        });
+
+        if item.is_none() && self.ilst().is_none() {
+            return;
+        }
+        let items = self.ilst_mut().children.get_or_insert_with(Vec::new);
+        match items.iter().position(|a| Self::is_item(a, field)) {
+            Some(index) => match item {
+                Some(item) => items[index] = item,
+                None => {
+                    items.remove(index);
+                }
+            },
+            None => items.extend(item),
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let (start, end) = self.moov_range;
+        let mut moov = self.moov.clone();
+        let mut bytes = Vec::new();
+        moov.write(&mut bytes);
+
+        // Audio stored after moov moves with it: its chunk offsets have to follow
+        let delta = bytes.len() as i64 - (end - start) as i64;
+        if delta != 0 {
+            moov.shift_chunk_offsets(end as u64, delta);
+            bytes.clear();
+            moov.write(&mut bytes);
+        }
+        if bytes.len() > u32::MAX as usize {
+            return Err(format!("moov atom too large in {}", path.display()));
+        }
+
+        let mut out = Vec::with_capacity(self.data.len() + bytes.len());
+        out.extend_from_slice(&self.data[..start]);
+        out.extend_from_slice(&bytes);
+        out.extend_from_slice(&self.data[end..]);
+        replace_file(path, &out)
+    }
+
+    fn bpm_text(&self, bpm: f64) -> String {
+        format!("{}", bpm.round().clamp(0.0, u16::MAX as f64) as u16)
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+/// Values this update asks for, in the store's format. Fields left as `None` are skipped.
+fn requested(update: &AudioTagUpdate, options: &AudioTagWriteOptions, store: &dyn TagStore) -> Result<Vec<(TagField, Option<String>)>, String> {
+    let mut fields = Vec::new();
+    if let Some(bpm) = update.bpm {
+        fields.push((TagField::Bpm, Some(store.bpm_text(bpm))));
+    }
+    if let Some(ref key) = update.key {
+        let key = crate::keys::format(key, options.key_notation).ok_or_else(|| format!("Unrecognized key: {}", key))?;
+        fields.push((TagField::Key, Some(key)));
+    }
+    if let Some(energy) = update.energy {
+        fields.push((TagField::Energy, Some(format_number(energy))));
+    }
+    if let Some(ref tags) = update.tags {
+        fields.push((TagField::Tags, Some(tags.join(", ")).filter(|t| !t.is_empty())));
+    }
+    Ok(fields)
+}
+
+/// Compare a file's tags with an update; returns the store and the fields that differ
+fn plan(update: &AudioTagUpdate, options: &AudioTagWriteOptions) -> Result<(Box<dyn TagStore>, Vec<TagChange>), String> {
+    let store = open_store(Path::new(&update.file_path))?;
+    let changes = requested(update, options, store.as_ref())?
+        .into_iter()
+        .filter_map(|(field, after)| {
+            let before = store.get(field);
+            (before != after).then(|| TagChange { field, native: store.native_name(field), before, after })
+        })
+        .collect();
+    Ok((store, changes))
+}
+
+/// A journal file name not used by an earlier write (several can land in the same millisecond)
+fn new_journal_path(journal_dir: &Path, now: &chrono::DateTime<chrono::Local>) -> Result<PathBuf, PikaError> {
+    std::fs::create_dir_all(journal_dir).map_err(|e| PikaError::io("Failed to create tag journal folder", journal_dir, e))?;
+    let stem = format!("tags-{}", now.format("%Y%m%d-%H%M%S%.3f"));
+    let mut path = journal_dir.join(format!("{}.json", stem));
+    let mut n = 1;
+    while path.exists() || path.with_extension("json.undone").exists() {
+        path = journal_dir.join(format!("{}-{}.json", stem, n));
+        n += 1;
+    }
+    Ok(path)
+}
+
+fn write_journal(path: &Path, journal: &TagJournal) -> Result<(), PikaError> {
+    let json = serde_json::to_vec_pretty(journal)
+        .map_err(|e| PikaError::internal(format!("Failed to encode tag journal: {}", e)))?;
+    replace_file(path, &json).map_err(|message| PikaError::Io { message, path: path.to_string_lossy().into_owned() })
+}
+
+/// Write Pika data into the files' tags (or just report the diff on a dry run).
+/// Each file's previous values are journaled in `journal_dir` before it is written.
+pub fn write_tags(updates: &[AudioTagUpdate], options: &AudioTagWriteOptions, journal_dir: &Path) -> Result<AudioTagWriteReport, PikaError> {
+    let mut report = AudioTagWriteReport {
+        dry_run: options.dry_run,
+        files: Vec::new(),
+        failed: Vec::new(),
+        journal_path: None,
+    };
+    let now = chrono::Local::now();
+    let mut journal = TagJournal { created_at: now.to_rfc3339(), files: Vec::new() };
+    let mut journal_path: Option<PathBuf> = None;
+
+    for update in updates {
+        let (mut store, changes) = match plan(update, options) {
+            Ok(planned) => planned,
+            Err(error) => {
+                report.failed.push(TagWriteFailure { file_path: update.file_path.clone(), error });
+                continue;
+            }
+        };
+        if changes.is_empty() {
+            continue;
+        }
+        let file = FileTagChanges { file_path: update.file_path.clone(), changes };
+        if options.dry_run {
+            report.files.push(file);
+            continue;
+        }
+
+        // Journal first: a crash mid-write must still be undoable
+        let journal_path = match journal_path {
+            Some(ref path) => path,
+            None => journal_path.insert(new_journal_path(journal_dir, &now)?),
+        };
+        journal.files.push(file.clone());
+        write_journal(journal_path, &journal)?;
+
+        for change in &file.changes {
+            store.set(change.field, change.after.as_deref());
+        }
+        if let Err(error) = store.save(Path::new(&file.file_path)) {
+            journal.files.pop();
+            write_journal(journal_path, &journal)?;
+            report.failed.push(TagWriteFailure { file_path: file.file_path, error });
+            continue;
+        }
+        report.files.push(file);
+    }
+
+    if let Some(path) = journal_path {
+        if journal.files.is_empty() {
+            // Every file failed: nothing to undo
+            let _ = std::fs::remove_file(&path);
+        } else {
+            report.journal_path = Some(path.to_string_lossy().into_owned());
+        }
+    }
+    Ok(report)
+}
+
+/// Revert a write recorded in `journal_path`. Fields edited since (by Pika or
+/// another app) are left alone and reported. The journal is then renamed to *.undone.
+pub fn undo(journal_path: &Path) -> Result<AudioTagWriteReport, PikaError> {
+    let bytes = std::fs::read(journal_path).map_err(|e| PikaError::io("Failed to read tag journal", journal_path, e))?;
+    let journal: TagJournal = serde_json::from_slice(&bytes)
+        .map_err(|e| PikaError::invalid_format(format!("Invalid tag journal: {}", e), Some(journal_path)))?;
+
+    let mut report = AudioTagWriteReport {
+        dry_run: false,
+        files: Vec::new(),
+        failed: Vec::new(),
+        journal_path: None,
+    };
+    for file in journal.files {
+        let fail = |error: String| TagWriteFailure { file_path: file.file_path.clone(), error };
+        let mut store = match open_store(Path::new(&file.file_path)) {
+            Ok(store) => store,
+            Err(error) => {
+                report.failed.push(fail(error));
+                continue;
+            }
+        };
+
+        let mut restored = Vec::new();
+        for change in &file.changes {
+            if store.get(change.field) != change.after {
+                report.failed.push(fail(format!("{} was changed since it was written", change.native)));
+                continue;
+            }
+            store.set(change.field, change.before.as_deref());
+            restored.push(TagChange {
+                field: change.field,
+                native: change.native.clone(),
+                before: change.after.clone(),
+                after: change.before.clone(),
+            });
+        }
+        if restored.is_empty() {
+            continue;
+        }
+        match store.save(Path::new(&file.file_path)) {
+            Ok(()) => report.files.push(FileTagChanges { file_path: file.file_path.clone(), changes: restored }),
+            Err(error) => report.failed.push(fail(error)),
+        }
+    }
+
+    let mut undone = journal_path.as_os_str().to_owned();
+    undone.push(".undone");
+    std::fs::rename(journal_path, PathBuf::from(undone))
+        .map_err(|e| PikaError::io("Failed to retire tag journal", journal_path, e))?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(path: &Path) -> AudioTagUpdate {
+        AudioTagUpdate {
+            file_path: path.to_string_lossy().into_owned(),
+            bpm: Some(101.6),
+            key: Some("Am".to_string()),
+            energy: Some(7.0),
+            tags: Some(vec!["blues".to_string(), "opener".to_string()]),
+        }
+    }
+
+    #[test]
+    fn test_id3_dry_run_write_and_undo() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("track.mp3");
+        // An MPEG frame header followed by padding is enough for ID3 to prepend a tag
+        let mut audio = vec![0xff, 0xfb, 0x90, 0x64];
+        audio.resize(417, 0);
+        std::fs::write(&path, &audio).unwrap();
+        let mut tag = id3::Tag::new();
+        tag.set_text("TBPM", "100");
+        tag.write_to_path(&path, id3::Version::Id3v24).unwrap();
+        let journal_dir = dir.join("journal");
+
+        let options = AudioTagWriteOptions { key_notation: KeyNotation::Camelot, dry_run: true };
+        let report = write_tags(&[update(&path)], &options, &journal_dir).unwrap();
+        assert!(report.journal_path.is_none());
+        assert_eq!(report.files[0].changes.len(), 4);
+        assert_eq!(
+            report.files[0].changes[0],
+            TagChange {
+                field: TagField::Bpm,
+                native: "TBPM".to_string(),
+                before: Some("100".to_string()),
+                after: Some("102".to_string()),
+            }
+        );
+        assert_eq!(report.files[0].changes[1].after.as_deref(), Some("8A"));
+        assert_eq!(Id3Store::open(&path).unwrap().get(TagField::Bpm).as_deref(), Some("100"));
+
+        let options = AudioTagWriteOptions { dry_run: false, ..options };
+        let report = write_tags(&[update(&path)], &options, &journal_dir).unwrap();
+        let journal = PathBuf::from(report.journal_path.unwrap());
+        let store = Id3Store::open(&path).unwrap();
+        assert_eq!(store.get(TagField::Key).as_deref(), Some("8A"));
+        assert_eq!(store.get(TagField::Energy).as_deref(), Some("7"));
+        assert_eq!(store.get(TagField::Tags).as_deref(), Some("blues, opener"));
+
+        // Writing the same values again changes nothing and journals nothing
+        let report = write_tags(&[update(&path)], &options, &journal_dir).unwrap();
+        assert!(report.files.is_empty() && report.journal_path.is_none());
+
+        // A field edited since the write is kept; the rest is reverted
+        let mut store = Id3Store::open(&path).unwrap();
+        store.set(TagField::Energy, Some("9"));
+        store.save(&path).unwrap();
+        let report = undo(&journal).unwrap();
+        assert_eq!(report.files[0].changes.len(), 3);
+        assert_eq!(report.failed.len(), 1);
+        let store = Id3Store::open(&path).unwrap();
+        assert_eq!(store.get(TagField::Bpm).as_deref(), Some("100"));
+        assert_eq!(store.get(TagField::Key), None);
+        assert_eq!(store.get(TagField::Energy).as_deref(), Some("9"));
+        assert!(!journal.exists());
+    }
+
+    #[test]
+    fn test_flac_and_mp4_keep_audio_intact() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let options = AudioTagWriteOptions { key_notation: KeyNotation::OpenKey, dry_run: false };
+
+        // FLAC: STREAMINFO, an existing comment block, then "audio"
+        let mut flac = b"fLaC".to_vec();
+        flac.extend_from_slice(&[0x00, 0, 0, 34]);
+        flac.extend_from_slice(&[0; 34]);
+        // Latin-1, as some taggers write it
+        let legacy = b"COMMENT=caf\xe9".to_vec();
+        let mut comments = VorbisComments { vendor: b"ref".to_vec(), comments: vec![b"TITLE=Slow Burn".to_vec(), legacy.clone()] }
+            .to_bytes();
+        flac.push(0x80 | FLAC_VORBIS_COMMENT);
+        flac.extend_from_slice(&(comments.len() as u32).to_be_bytes()[1..]);
+        flac.append(&mut comments);
+        flac.extend_from_slice(b"FRAMES");
+        let flac_path = dir.join("track.flac");
+        std::fs::write(&flac_path, &flac).unwrap();
+
+        write_tags(&[update(&flac_path)], &options, dir).unwrap();
+        let store = FlacStore::open(&flac_path).unwrap();
+        assert_eq!(store.get(TagField::Bpm).as_deref(), Some("101.6"));
+        assert_eq!(store.get(TagField::Key).as_deref(), Some("1m"));
+        assert!(store.comments.comments.contains(&b"TITLE=Slow Burn".to_vec()));
+        assert!(store.comments.comments.contains(&legacy));
+        assert_eq!(store.comments.vendor, b"ref");
+        assert!(std::fs::read(&flac_path).unwrap().ends_with(b"FRAMES"));
+
+        // MP4: moov (one track whose stco points into mdat) before mdat, no metadata yet
+        let mut stco = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stco.extend_from_slice(&0u32.to_be_bytes());
+        let stbl = Atom::container(b"stbl", Vec::new(), vec![Atom::leaf(b"stco", stco)]);
+        let trak = Atom::container(
+            b"trak",
+            Vec::new(),
+            vec![Atom::container(b"mdia", Vec::new(), vec![Atom::container(b"minf", Vec::new(), vec![stbl])])],
+        );
+        let mut mp4 = Vec::new();
+        Atom::leaf(b"ftyp", b"M4A \0\0\0\0".to_vec()).write(&mut mp4);
+        let mut moov = Atom::container(b"moov", Vec::new(), vec![trak]);
+        let mut moov_bytes = Vec::new();
+        moov.write(&mut moov_bytes);
+        let mdat_data_offset = (mp4.len() + moov_bytes.len() + 8) as u64;
+        moov.shift_chunk_offsets(0, mdat_data_offset as i64);
+        moov.write(&mut mp4);
+        Atom::leaf(b"mdat", b"SAMPLES".to_vec()).write(&mut mp4);
+        let mp4_path = dir.join("track.m4a");
+        std::fs::write(&mp4_path, &mp4).unwrap();
+
+        write_tags(&[update(&mp4_path)], &options, dir).unwrap();
+        let store = Mp4Store::open(&mp4_path).unwrap();
+        assert_eq!(store.get(TagField::Bpm).as_deref(), Some("102"));
+        assert_eq!(store.get(TagField::Key).as_deref(), Some("1m"));
+        assert_eq!(store.get(TagField::Tags).as_deref(), Some("blues, opener"));
+
+        // The chunk offset still points at the samples
+        let written = std::fs::read(&mp4_path).unwrap();
+        let stco = [b"trak", b"mdia", b"minf", b"stbl", b"stco"]
+            .iter()
+            .fold(&store.moov, |atom, kind| atom.child(kind).unwrap());
+        let stco = &stco.data;
+        let offset = u32::from_be_bytes(stco[8..12].try_into().unwrap()) as usize;
+        assert_eq!(&written[offset..offset + 7], b"SAMPLES");
+    }
+
+    #[test]
+    fn test_ogg_repages_headers_and_keeps_audio() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let options = AudioTagWriteOptions::default();
+        // Known value for this CRC variant (CRC-32/POSIX before its final xor)
+        assert_eq!(ogg_crc(b"123456789"), !0x765e_7680);
+
+        let legacy = b"COMMENT=caf\xe9".to_vec();
+        let comments = VorbisComments { vendor: b"Xiph".to_vec(), comments: vec![b"TITLE=Slow Burn".to_vec(), legacy.clone()] };
+        let comment_packet = [b"\x03vorbis".as_slice(), &comments.to_bytes(), &[1]].concat();
+        let setup = [b"\x05vorbis".as_slice(), &[7; 600]].concat();
+        let mut ogg = Vec::new();
+        let mut pages = write_ogg_packets(&mut ogg, &[b"\x01vorbis-identification"], OGG_FIRST_PAGE, 42, 0);
+        pages += write_ogg_packets(&mut ogg, &[&comment_packet, &setup], 0, 42, pages);
+        for (i, granule) in [1024u64, 2048, 3072].into_iter().enumerate() {
+            let audio = vec![i as u8 + 1; 100];
+            write_ogg_page(&mut ogg, 0, granule, 42, pages + i as u32, &[100], &audio);
+        }
+        let ogg_path = dir.join("track.ogg");
+        std::fs::write(&ogg_path, &ogg).unwrap();
+
+        // A page holds at most 255 * 255 bytes: this pushes the headers onto another page
+        let long_tags = AudioTagUpdate { tags: Some(vec!["x".repeat(70_000)]), ..update(&ogg_path) };
+        let report = write_tags(&[long_tags], &options, dir).unwrap();
+        assert!(report.failed.is_empty(), "{:?}", report.failed);
+
+        let store = OggStore::open(&ogg_path).unwrap();
+        assert_eq!(store.get(TagField::Bpm).as_deref(), Some("101.6"));
+        assert_eq!(store.get(TagField::Tags).map(|t| t.len()), Some(70_000));
+        assert!(store.comments.comments.contains(&legacy));
+        assert_eq!(store.comments.vendor, b"Xiph");
+        assert_eq!(store.packets[2], setup);
+        assert_eq!(store.header_pages, pages + 1);
+
+        // Every page is intact and numbered in order, and the audio is unchanged
+        let written = std::fs::read(&ogg_path).unwrap();
+        let (mut pos, mut sequence, mut audio) = (0, 0, Vec::new());
+        while pos < written.len() {
+            let page = read_ogg_page(&written, pos).expect("valid page");
+            assert_eq!(page.sequence, sequence);
+            if page.end > store.audio_offset {
+                audio.extend_from_slice(&written[page.body.clone()]);
+            }
+            sequence += 1;
+            pos = page.end;
+        }
+        assert_eq!(audio, [vec![1; 100], vec![2; 100], vec![3; 100]].concat());
+
+        // Unsupported codecs are refused rather than mangled
+        let mut flac_in_ogg = Vec::new();
+        write_ogg_packets(&mut flac_in_ogg, &[b"\x7fFLAC"], OGG_FIRST_PAGE, 1, 0);
+        std::fs::write(&ogg_path, &flac_in_ogg).unwrap();
+        assert!(OggStore::open(&ogg_path).is_err());
+    }
+}
+
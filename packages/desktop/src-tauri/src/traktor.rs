@@ -0,0 +1,440 @@
+// Traktor collection.nml reader
+//
+// Traktor splits each track's location into VOLUME, DIR and FILE, with DIR
+// written in its own `/:`-separated form ("/:Users/:dj/:Music/:"). Playlists
+// refer to tracks by the concatenation of those three raw strings, so entries
+// are resolved to file paths once the whole collection has been read.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::Path;
+
+use crate::error::PikaError;
+use crate::vdj_database::for_each_attribute;
+use crate::LibraryPlaylist;
+
+/// One `<ENTRY>` from the COLLECTION
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TraktorEntry {
+    /// Reconstructed local file path
+    pub(crate) file_path: String,
+    /// VOLUME + DIR + FILE as written, the key playlists use
+    pub(crate) primary_key: String,
+    pub(crate) title: Option<String>,
+    pub(crate) artist: Option<String>,
+    pub(crate) album: Option<String>,
+    pub(crate) track_number: Option<String>,
+    pub(crate) genre: Option<String>,
+    /// INFO KEY as displayed in Traktor (fallback when MUSICAL_KEY is missing)
+    pub(crate) key_text: Option<String>,
+    /// MUSICAL_KEY VALUE, 0-23
+    pub(crate) musical_key: Option<String>,
+    /// Seconds
+    pub(crate) playtime: Option<String>,
+    pub(crate) playtime_float: Option<String>,
+    pub(crate) bpm: Option<String>,
+    /// 0-255 (51 per star)
+    pub(crate) ranking: Option<String>,
+    pub(crate) playcount: Option<String>,
+    /// yyyy/m/d
+    pub(crate) import_date: Option<String>,
+    pub(crate) release_date: Option<String>,
+    pub(crate) cues: Vec<TraktorCue>,
+    /// When the track was played, from EXTENDEDDATA in history archives
+    pub(crate) played_at: Option<i64>,
+}
+
+/// `<CUE_V2>`: cue, fade, load, grid marker or loop
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TraktorCue {
+    pub(crate) name: Option<String>,
+    /// 0 = cue, 1 = fade-in, 2 = fade-out, 3 = load, 4 = grid, 5 = loop
+    pub(crate) cue_type: Option<String>,
+    /// Milliseconds
+    pub(crate) start: Option<String>,
+    /// Milliseconds (loops only)
+    pub(crate) len: Option<String>,
+    /// Hot cue slot, 0-based (-1 = not assigned)
+    pub(crate) hotcue: Option<String>,
+}
+
+impl TraktorEntry {
+    /// Key name from MUSICAL_KEY, falling back to INFO KEY
+    pub(crate) fn key_name(&self) -> Option<String> {
+        self.musical_key
+            .as_ref()
+            .and_then(|v| v.trim().parse::<i64>().ok())
+            .and_then(crate::keys::from_chromatic)
+            .map(str::to_string)
+            .or_else(|| self.key_text.clone().filter(|k| !k.is_empty()))
+    }
+}
+
+/// EXTENDEDDATA in history archives: STARTDATE packs (year << 16 | month << 8 | day),
+/// STARTTIME is local seconds since midnight
+fn parse_played_at(element: &BytesStart) -> Result<Option<i64>, String> {
+    let (mut date, mut time) = (None, None);
+    for_each_attribute(element, |key, value| match key {
+        b"STARTDATE" => date = value.trim().parse::<u32>().ok(),
+        b"STARTTIME" => time = value.trim().parse::<u32>().ok(),
+        _ => {}
+    })?;
+    let (Some(date), Some(time)) = (date, time) else {
+        return Ok(None);
+    };
+
+    let day = chrono::NaiveDate::from_ymd_opt((date >> 16) as i32, (date >> 8) & 0xFF, date & 0xFF);
+    let played = day
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight + chrono::Duration::seconds(time as i64))
+        .and_then(|local| local.and_local_timezone(chrono::Local).earliest())
+        .map(|local| local.timestamp());
+    Ok(played)
+}
+
+/// A parsed collection.nml
+#[derive(Debug, Default)]
+pub(crate) struct TraktorCollection {
+    pub(crate) entries: Vec<TraktorEntry>,
+    /// Children of the $ROOT node
+    pub(crate) playlists: Vec<LibraryPlaylist>,
+}
+
+/// A playlist node while parsing, before its entries are resolved to paths
+struct PendingNode {
+    name: String,
+    is_folder: bool,
+    children: Vec<PendingNode>,
+    keys: Vec<String>,
+}
+
+impl PendingNode {
+    fn from_element(element: &BytesStart) -> Result<Self, String> {
+        let mut node = PendingNode { name: String::new(), is_folder: true, children: Vec::new(), keys: Vec::new() };
+        for_each_attribute(element, |key, value| match key {
+            b"NAME" => node.name = value,
+            b"TYPE" => node.is_folder = value != "PLAYLIST",
+            _ => {}
+        })?;
+        Ok(node)
+    }
+
+    fn resolve(self, paths_by_key: &HashMap<String, String>) -> LibraryPlaylist {
+        LibraryPlaylist {
+            name: self.name,
+            is_folder: self.is_folder,
+            children: self.children.into_iter().map(|c| c.resolve(paths_by_key)).collect(),
+            track_paths: self.keys.iter().filter_map(|key| paths_by_key.get(key).cloned()).collect(),
+        }
+    }
+}
+
+/// Rebuild a file path from LOCATION's VOLUME, DIR and FILE.
+/// `C:` + `/:Music/:WCS/:` + `a.mp3` -> `C:\Music\WCS\a.mp3`;
+/// `Macintosh HD` + `/:Users/:dj/:` + `a.mp3` -> `/Users/dj/a.mp3`
+pub(crate) fn location_path(volume: &str, dir: &str, file: &str) -> String {
+    let rest = format!("{}{}", dir.replace("/:", "/"), file);
+
+    // Windows: the volume is the drive letter; VDJ and the OS use backslashes
+    if volume.len() == 2 && volume.ends_with(':') {
+        return format!("{}{}", volume, rest.replace('/', "\\"));
+    }
+
+    // macOS: the boot volume is a symlink in /Volumes, external drives are real mounts
+    if cfg!(target_os = "macos") && !volume.is_empty() {
+        let mount = Path::new("/Volumes").join(volume);
+        if std::fs::symlink_metadata(&mount).is_ok_and(|m| m.is_dir()) {
+            return format!("{}{}", mount.display(), rest);
+        }
+    }
+    rest
+}
+
+fn parse_entry_start(element: &BytesStart) -> Result<TraktorEntry, String> {
+    let mut entry = TraktorEntry::default();
+    for_each_attribute(element, |key, value| match key {
+        b"TITLE" => entry.title = Some(value),
+        b"ARTIST" => entry.artist = Some(value),
+        _ => {}
+    })?;
+    Ok(entry)
+}
+
+fn parse_location(element: &BytesStart, entry: &mut TraktorEntry) -> Result<(), String> {
+    let (mut volume, mut dir, mut file) = (String::new(), String::new(), String::new());
+    for_each_attribute(element, |key, value| match key {
+        b"VOLUME" => volume = value,
+        b"DIR" => dir = value,
+        b"FILE" => file = value,
+        _ => {}
+    })?;
+    entry.file_path = location_path(&volume, &dir, &file);
+    entry.primary_key = format!("{}{}{}", volume, dir, file);
+    Ok(())
+}
+
+fn parse_info(element: &BytesStart, entry: &mut TraktorEntry) -> Result<(), String> {
+    for_each_attribute(element, |key, value| match key {
+        b"GENRE" => entry.genre = Some(value),
+        b"KEY" => entry.key_text = Some(value),
+        b"PLAYTIME" => entry.playtime = Some(value),
+        b"PLAYTIME_FLOAT" => entry.playtime_float = Some(value),
+        b"RANKING" => entry.ranking = Some(value),
+        b"PLAYCOUNT" => entry.playcount = Some(value),
+        b"IMPORT_DATE" => entry.import_date = Some(value),
+        b"RELEASE_DATE" => entry.release_date = Some(value),
+        _ => {}
+    })
+}
+
+fn parse_cue(element: &BytesStart) -> Result<TraktorCue, String> {
+    let mut cue = TraktorCue::default();
+    for_each_attribute(element, |key, value| match key {
+        b"NAME" => cue.name = Some(value),
+        b"TYPE" => cue.cue_type = Some(value),
+        b"START" => cue.start = Some(value),
+        b"LEN" => cue.len = Some(value),
+        b"HOTCUE" => cue.hotcue = Some(value),
+        _ => {}
+    })?;
+    Ok(cue)
+}
+
+/// Stream-parse a Traktor collection.nml
+pub(crate) fn parse_collection<R: BufRead>(source: R) -> Result<TraktorCollection, PikaError> {
+    let mut reader = Reader::from_reader(source);
+    let mut buf = Vec::with_capacity(4096);
+    let mut entries = Vec::new();
+    let mut current: Option<TraktorEntry> = None;
+    let mut in_collection = false;
+    // Open NODE elements; the bottom one is $ROOT
+    let mut node_stack: Vec<PendingNode> = Vec::new();
+    let mut root: Option<PendingNode> = None;
+    let mut seen_root = false;
+
+    let not_collection = || PikaError::invalid_format("Not a Traktor collection (missing <NML>)", None);
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| PikaError::xml_at(reader.error_position(), format!("XML parsing error: {}", e)))?;
+        // Attribute errors are reported at the element they belong to
+        let position = reader.buffer_position();
+        let attr_err = |message: String| PikaError::xml_at(position, message);
+
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) if !seen_root => {
+                if e.name().as_ref() != b"NML" {
+                    return Err(not_collection());
+                }
+                seen_root = true;
+            }
+            Event::Start(ref e) if e.name().as_ref() == b"COLLECTION" => in_collection = true,
+            Event::End(ref e) if e.name().as_ref() == b"COLLECTION" => in_collection = false,
+            Event::Start(ref e) if in_collection && e.name().as_ref() == b"ENTRY" => {
+                current = Some(parse_entry_start(e).map_err(attr_err)?);
+            }
+            Event::End(ref e) if in_collection && e.name().as_ref() == b"ENTRY" => {
+                entries.extend(current.take());
+            }
+            Event::Start(ref e) | Event::Empty(ref e) if in_collection => {
+                if let Some(ref mut entry) = current {
+                    match e.name().as_ref() {
+                        b"LOCATION" => parse_location(e, entry).map_err(attr_err)?,
+                        b"ALBUM" => for_each_attribute(e, |key, value| match key {
+                            b"TITLE" => entry.album = Some(value),
+                            b"TRACK" => entry.track_number = Some(value),
+                            _ => {}
+                        }).map_err(attr_err)?,
+                        b"INFO" => parse_info(e, entry).map_err(attr_err)?,
+                        b"TEMPO" => for_each_attribute(e, |key, value| {
+                            if key == b"BPM" {
+                                entry.bpm = Some(value);
+                            }
+                        }).map_err(attr_err)?,
+                        b"MUSICAL_KEY" => for_each_attribute(e, |key, value| {
+                            if key == b"VALUE" {
+                                entry.musical_key = Some(value);
+                            }
+                        }).map_err(attr_err)?,
+                        b"CUE_V2" => entry.cues.push(parse_cue(e).map_err(attr_err)?),
+                        b"EXTENDEDDATA" => entry.played_at = parse_played_at(e).map_err(attr_err)?,
+                        _ => {}
+                    }
+                }
+            }
+            Event::Start(ref e) if e.name().as_ref() == b"NODE" => {
+                node_stack.push(PendingNode::from_element(e).map_err(attr_err)?);
+            }
+            Event::Empty(ref e) if e.name().as_ref() == b"NODE" => {
+                let node = PendingNode::from_element(e).map_err(attr_err)?;
+                if let Some(parent) = node_stack.last_mut() {
+                    parent.children.push(node);
+                }
+            }
+            Event::End(ref e) if e.name().as_ref() == b"NODE" => {
+                if let Some(node) = node_stack.pop() {
+                    match node_stack.last_mut() {
+                        Some(parent) => parent.children.push(node),
+                        None => root = Some(node),
+                    }
+                }
+            }
+            Event::Start(ref e) | Event::Empty(ref e) if e.name().as_ref() == b"PRIMARYKEY" => {
+                if let Some(node) = node_stack.last_mut() {
+                    for_each_attribute(e, |key, value| {
+                        if key == b"KEY" {
+                            node.keys.push(value);
+                        }
+                    }).map_err(attr_err)?;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if !seen_root {
+        return Err(not_collection());
+    }
+
+    let paths_by_key: HashMap<String, String> = entries
+        .iter()
+        .map(|e| (e.primary_key.clone(), e.file_path.clone()))
+        .collect();
+    let playlists = root
+        .map(|root| root.resolve(&paths_by_key).children)
+        .unwrap_or_default();
+
+    entries.retain(|e| !e.file_path.is_empty());
+    Ok(TraktorCollection { entries, playlists })
+}
+
+/// Traktor's History folder (history_*.nml archives) in the newest "Traktor x.y" settings folder
+pub(crate) fn default_history_dir() -> Option<std::path::PathBuf> {
+    let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")).ok()?;
+    newest_history_dir(&Path::new(&home).join("Documents").join("Native Instruments"))
+}
+
+/// History folder of the highest "Traktor x.y.z" under `root`
+fn newest_history_dir(root: &Path) -> Option<std::path::PathBuf> {
+    std::fs::read_dir(root)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            let version = folder_version(name.strip_prefix("Traktor")?);
+            Some((version, e.path().join("History")))
+        })
+        .filter(|(_, history)| history.is_dir())
+        .max()
+        .map(|(_, history)| history)
+}
+
+/// " 3.10.1" -> [3, 10, 1], so 3.10 ranks above 3.9 (a plain string sort gets that wrong)
+fn folder_version(version: &str) -> Vec<u32> {
+    version
+        .trim()
+        .split('.')
+        .map_while(|part| part.trim().parse().ok())
+        .collect()
+}
+
+/// Open and parse a collection.nml file
+pub(crate) fn load_collection(path: &Path) -> Result<TraktorCollection, PikaError> {
+    let file = std::fs::File::open(path).map_err(|e| PikaError::io("Failed to read collection.nml", path, e))?;
+    parse_collection(std::io::BufReader::with_capacity(256 * 1024, file)).map_err(|e| e.in_file(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_newest_history_dir_compares_versions() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for version in ["Traktor 3.9.2", "Traktor 3.10.1", "Traktor 2.11"] {
+            std::fs::create_dir_all(root.join(version).join("History")).unwrap();
+        }
+        // No History folder yet
+        std::fs::create_dir_all(root.join("Traktor 4.0")).unwrap();
+
+        assert_eq!(newest_history_dir(root), Some(root.join("Traktor 3.10.1").join("History")));
+        assert_eq!(folder_version(" 3.10.1"), vec![3, 10, 1]);
+    }
+
+    const FIXTURE: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no" ?>
+<NML VERSION="19"><HEAD COMPANY="www.native-instruments.com" PROGRAM="Traktor"></HEAD>
+<COLLECTION ENTRIES="3">
+<ENTRY MODIFIED_DATE="2023/5/1" TITLE="Slow Burn" ARTIST="Kacey &amp; Co">
+<LOCATION DIR="/:Music/:WCS/:" FILE="Slow Burn.mp3" VOLUME="C:" VOLUMEID="c0ffee"></LOCATION>
+<ALBUM TRACK="3" TITLE="Golden Hour"></ALBUM>
+<INFO BITRATE="320000" GENRE="WCS" KEY="8m" PLAYCOUNT="3" PLAYTIME="215" PLAYTIME_FLOAT="214.8" RANKING="204" IMPORT_DATE="2023/5/1" RELEASE_DATE="2018/3/30"></INFO>
+<TEMPO BPM="96.000" BPM_QUALITY="100.000"></TEMPO>
+<MUSICAL_KEY VALUE="21"></MUSICAL_KEY>
+<CUE_V2 NAME="AutoGrid" DISPL_ORDER="0" TYPE="4" START="25.3" LEN="0" REPEATS="-1" HOTCUE="0"></CUE_V2>
+<CUE_V2 NAME="Drop" DISPL_ORDER="0" TYPE="0" START="32500" LEN="0" REPEATS="-1" HOTCUE="1"></CUE_V2>
+<CUE_V2 NAME="n.n." DISPL_ORDER="0" TYPE="5" START="60000" LEN="8000" REPEATS="-1" HOTCUE="-1"></CUE_V2>
+</ENTRY>
+<ENTRY TITLE="Mac Track" ARTIST="B">
+<LOCATION DIR="/:Users/:dj/:Music/:" FILE="b.m4a" VOLUME="Macintosh HD"></LOCATION>
+<INFO KEY="11d"></INFO>
+</ENTRY>
+<ENTRY TITLE="Stream" ARTIST="C"></ENTRY>
+</COLLECTION>
+<PLAYLISTS>
+<NODE TYPE="FOLDER" NAME="$ROOT"><SUBNODES COUNT="2">
+<NODE TYPE="FOLDER" NAME="Sets"><SUBNODES COUNT="1">
+<NODE TYPE="PLAYLIST" NAME="Saturday"><PLAYLIST ENTRIES="2" TYPE="LIST" UUID="1">
+<ENTRY><PRIMARYKEY TYPE="TRACK" KEY="Macintosh HD/:Users/:dj/:Music/:b.m4a"></PRIMARYKEY></ENTRY>
+<ENTRY><PRIMARYKEY TYPE="TRACK" KEY="C:/:Music/:WCS/:Slow Burn.mp3"></PRIMARYKEY></ENTRY>
+</PLAYLIST></NODE>
+</SUBNODES></NODE>
+<NODE TYPE="PLAYLIST" NAME="Preparation"><PLAYLIST ENTRIES="0" TYPE="LIST" UUID="2"></PLAYLIST></NODE>
+</SUBNODES></NODE>
+</PLAYLISTS>
+</NML>"#;
+
+    #[test]
+    fn test_location_path_decoding() {
+        assert_eq!(location_path("C:", "/:Music/:WCS/:", "a.mp3"), "C:\\Music\\WCS\\a.mp3");
+        assert_eq!(location_path("D:", "/:", "b.mp3"), "D:\\b.mp3");
+        if !cfg!(target_os = "macos") {
+            assert_eq!(location_path("Macintosh HD", "/:Users/:dj/:", "a.mp3"), "/Users/dj/a.mp3");
+        }
+    }
+
+    #[test]
+    fn test_parse_collection_fixture() {
+        let collection = parse_collection(FIXTURE.as_bytes()).unwrap();
+        // The entry without a LOCATION (a streaming track) is skipped
+        assert_eq!(collection.entries.len(), 2);
+
+        let entry = &collection.entries[0];
+        assert_eq!(entry.file_path, "C:\\Music\\WCS\\Slow Burn.mp3");
+        assert_eq!(entry.primary_key, "C:/:Music/:WCS/:Slow Burn.mp3");
+        assert_eq!(entry.artist.as_deref(), Some("Kacey & Co"));
+        assert_eq!(entry.album.as_deref(), Some("Golden Hour"));
+        assert_eq!(entry.bpm.as_deref(), Some("96.000"));
+        assert_eq!(entry.key_name().as_deref(), Some("Am"));
+        assert_eq!(entry.cues.len(), 3);
+        // No MUSICAL_KEY: fall back to the displayed key
+        assert_eq!(collection.entries[1].key_name().as_deref(), Some("11d"));
+
+        assert_eq!(collection.playlists.len(), 2);
+        let sets = &collection.playlists[0];
+        assert!(sets.is_folder);
+        assert_eq!(sets.children[0].name, "Saturday");
+        assert_eq!(
+            sets.children[0].track_paths,
+            vec![collection.entries[1].file_path.clone(), "C:\\Music\\WCS\\Slow Burn.mp3".to_string()]
+        );
+        assert!(!collection.playlists[1].is_folder);
+
+        assert!(parse_collection(r#"<DJ_PLAYLISTS />"#.as_bytes()).is_err());
+    }
+}
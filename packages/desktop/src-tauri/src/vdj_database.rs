@@ -0,0 +1,582 @@
+// VirtualDJ database.xml model and streaming parser
+//
+// Libraries with 100k+ songs made the old read_to_string + serde approach take
+// seconds and hundreds of MB (whole file in memory, then an intermediate Vec,
+// then a HashMap copy). This walks the file with quick_xml::Reader in a single
+// pass, reusing one event buffer and only allocating for the fields Pika uses.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use crate::error::PikaError;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Emit a progress update every N songs
+const PROGRESS_EVERY_SONGS: usize = 5_000;
+/// Check the cancel flag every N songs
+const CANCEL_CHECK_EVERY_SONGS: usize = 1_000;
+/// Only these file systems treat `Song.mp3` and `song.mp3` as the same file
+pub(crate) const CASE_INSENSITIVE_PATHS: bool = cfg!(any(target_os = "windows", target_os = "macos"));
+
+/// `<Song>` element
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct VirtualDJSong {
+    /// `@FilePath`
+    pub(crate) file_path: String,
+    /// `<Tags>`
+    pub(crate) tags: Option<VirtualDJTags>,
+    /// `<Scan>`
+    pub(crate) scan: Option<VirtualDJScan>,
+    /// `<Infos>`
+    pub(crate) infos: Option<VirtualDJInfos>,
+    /// `<Poi>` (zero or more)
+    pub(crate) pois: Vec<VirtualDJPoi>,
+}
+
+/// `<Poi Name Pos Type Num Size Point Color Bpm />`
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct VirtualDJPoi {
+    pub(crate) name: Option<String>,
+    pub(crate) pos: Option<String>,
+    pub(crate) _type: Option<String>,
+    pub(crate) num: Option<String>,
+    pub(crate) size: Option<String>,
+    pub(crate) point: Option<String>,
+    pub(crate) color: Option<String>,
+    pub(crate) bpm: Option<String>,
+}
+
+/// `<Infos SongLength FirstSeen PlayCount />`
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct VirtualDJInfos {
+    pub(crate) song_length: Option<String>,
+    pub(crate) first_seen: Option<String>,
+    pub(crate) play_count: Option<String>,
+}
+
+/// `<Tags Author Title Genre Album TrackNumber Year Flag />`
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct VirtualDJTags {
+    pub(crate) author: Option<String>,
+    pub(crate) title: Option<String>,
+    pub(crate) genre: Option<String>,
+    pub(crate) album: Option<String>,
+    pub(crate) track_number: Option<String>,
+    pub(crate) year: Option<String>,
+    pub(crate) flag: Option<String>,
+}
+
+/// `<Scan Bpm Volume Key />`
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct VirtualDJScan {
+    pub(crate) bpm: Option<String>,
+    pub(crate) volume: Option<String>,
+    pub(crate) key: Option<String>,
+}
+
+/// Parsed database with O(1) lookups by exact path, normalized path,
+/// case-folded path (Windows and macOS only) and artist+title. Shared via `Arc` so lookups never copy it.
+#[derive(Debug, Default)]
+pub(crate) struct VdjIndex {
+    songs: Vec<VirtualDJSong>,
+    by_path: HashMap<String, usize>,
+    by_normalized_path: HashMap<String, usize>,
+    by_folded_path: HashMap<String, usize>,
+    by_track_key: HashMap<String, usize>,
+}
+
+/// Unify separators and drop the Windows extended-length prefix so
+/// `C:\Music\a.mp3`, `C:/Music/a.mp3` and `\\?\C:\Music\a.mp3` match
+pub(crate) fn normalize_path(path: &str) -> String {
+    let path = path.trim();
+    let path = path.strip_prefix(r"\\?\").unwrap_or(path);
+    let mut normalized = String::with_capacity(path.len());
+    for c in path.chars() {
+        let c = if c == '\\' { '/' } else { c };
+        if c == '/' && normalized.ends_with('/') && normalized.len() > 1 {
+            continue;
+        }
+        normalized.push(c);
+    }
+    normalized
+}
+
+/// Lowercased with whitespace collapsed, for comparing artist/title tags
+pub(crate) fn fold_tag(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Case-insensitive artist+title key (whitespace collapsed)
+pub(crate) fn track_key(artist: &str, title: &str) -> String {
+    format!("{}\u{1f}{}", fold_tag(artist), fold_tag(title))
+}
+
+fn song_track_key(song: &VirtualDJSong) -> Option<String> {
+    let tags = song.tags.as_ref()?;
+    Some(track_key(tags.author.as_deref()?, tags.title.as_deref()?))
+}
+
+impl VdjIndex {
+    /// Add a song, replacing any earlier entry with the same file path
+    pub(crate) fn insert(&mut self, song: VirtualDJSong) {
+        let normalized = normalize_path(&song.file_path);
+        let key = song_track_key(&song);
+
+        let idx = match self.by_path.get(&song.file_path) {
+            Some(&existing) => {
+                let old = std::mem::replace(&mut self.songs[existing], song);
+                // A retagged song must stop answering to its old artist+title
+                if let Some(old_key) = song_track_key(&old).filter(|old_key| Some(old_key) != key.as_ref()) {
+                    self.evict_track_key(old_key, existing);
+                }
+                existing
+            }
+            None => {
+                self.songs.push(song);
+                let idx = self.songs.len() - 1;
+                self.by_path.insert(self.songs[idx].file_path.clone(), idx);
+                idx
+            }
+        };
+
+        if CASE_INSENSITIVE_PATHS {
+            self.by_folded_path.insert(normalized.to_lowercase(), idx);
+        }
+        self.by_normalized_path.insert(normalized, idx);
+        if let Some(key) = key {
+            // First song wins for duplicate artist+title so results stay stable
+            self.by_track_key.entry(key).or_insert(idx);
+        }
+    }
+
+    /// Drop `key` if it points at `idx`, handing it to the next song with the same artist+title
+    fn evict_track_key(&mut self, key: String, idx: usize) {
+        if self.by_track_key.get(&key) != Some(&idx) {
+            return;
+        }
+        match self.songs.iter().position(|song| song_track_key(song).as_ref() == Some(&key)) {
+            Some(next) => self.by_track_key.insert(key, next),
+            None => self.by_track_key.remove(&key),
+        };
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.songs.len()
+    }
+
+    pub(crate) fn songs(&self) -> impl Iterator<Item = &VirtualDJSong> {
+        self.songs.iter()
+    }
+
+    /// Look a song up by file path: exact, then normalized separators, then
+    /// case-insensitive on Windows and macOS
+    pub(crate) fn get(&self, file_path: &str) -> Option<&VirtualDJSong> {
+        if let Some(&idx) = self.by_path.get(file_path) {
+            return Some(&self.songs[idx]);
+        }
+        let normalized = normalize_path(file_path);
+        self.by_normalized_path
+            .get(&normalized)
+            .or_else(|| {
+                CASE_INSENSITIVE_PATHS.then(|| self.by_folded_path.get(&normalized.to_lowercase())).flatten()
+            })
+            .map(|&idx| &self.songs[idx])
+    }
+
+    /// Look a song up by artist and title (case-insensitive)
+    pub(crate) fn find_by_track(&self, artist: &str, title: &str) -> Option<&VirtualDJSong> {
+        self.by_track_key
+            .get(&track_key(artist, title))
+            .map(|&idx| &self.songs[idx])
+    }
+}
+
+/// Several databases (home folder + external drives) searched in priority order.
+/// Each hit reports which database.xml it came from.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct MergedVdjIndex {
+    sources: Vec<(PathBuf, Arc<VdjIndex>)>,
+}
+
+impl MergedVdjIndex {
+    pub(crate) fn push(&mut self, database: PathBuf, index: Arc<VdjIndex>) {
+        self.sources.push((database, index));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Look a song up by file path in every database, first match wins
+    pub(crate) fn get(&self, file_path: &str) -> Option<(&VirtualDJSong, &Path)> {
+        self.sources
+            .iter()
+            .find_map(|(database, index)| Some((index.get(file_path)?, database.as_path())))
+    }
+
+    /// Look a song up by artist and title in every database, first match wins
+    pub(crate) fn find_by_track(&self, artist: &str, title: &str) -> Option<(&VirtualDJSong, &Path)> {
+        self.sources
+            .iter()
+            .find_map(|(database, index)| Some((index.find_by_track(artist, title)?, database.as_path())))
+    }
+}
+
+/// Progress of a database parse, reported to the UI during import
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ImportProgress {
+    bytes_read: u64,
+    total_bytes: u64,
+    songs: usize,
+}
+
+fn xml_err(e: impl std::fmt::Display) -> String {
+    format!("XML parsing error: {}", e)
+}
+
+/// Call `set(name, value)` for every attribute, unescaping values
+pub(crate) fn for_each_attribute(element: &BytesStart, mut set: impl FnMut(&[u8], String)) -> Result<(), String> {
+    for attr in element.attributes().with_checks(false) {
+        let attr = attr.map_err(xml_err)?;
+        let value = attr.unescape_value().map_err(xml_err)?;
+        set(attr.key.as_ref(), value.into_owned());
+    }
+    Ok(())
+}
+
+fn parse_tags(element: &BytesStart) -> Result<VirtualDJTags, String> {
+    let mut tags = VirtualDJTags::default();
+    for_each_attribute(element, |key, value| match key {
+        b"Author" => tags.author = Some(value),
+        b"Title" => tags.title = Some(value),
+        b"Genre" => tags.genre = Some(value),
+        b"Album" => tags.album = Some(value),
+        b"TrackNumber" => tags.track_number = Some(value),
+        b"Year" => tags.year = Some(value),
+        b"Flag" => tags.flag = Some(value),
+        _ => {}
+    })?;
+    Ok(tags)
+}
+
+fn parse_scan(element: &BytesStart) -> Result<VirtualDJScan, String> {
+    let mut scan = VirtualDJScan::default();
+    for_each_attribute(element, |key, value| match key {
+        b"Bpm" => scan.bpm = Some(value),
+        b"Volume" => scan.volume = Some(value),
+        b"Key" => scan.key = Some(value),
+        _ => {}
+    })?;
+    Ok(scan)
+}
+
+fn parse_infos(element: &BytesStart) -> Result<VirtualDJInfos, String> {
+    let mut infos = VirtualDJInfos::default();
+    for_each_attribute(element, |key, value| match key {
+        b"SongLength" => infos.song_length = Some(value),
+        b"FirstSeen" => infos.first_seen = Some(value),
+        b"PlayCount" => infos.play_count = Some(value),
+        _ => {}
+    })?;
+    Ok(infos)
+}
+
+fn parse_poi(element: &BytesStart) -> Result<VirtualDJPoi, String> {
+    let mut poi = VirtualDJPoi::default();
+    for_each_attribute(element, |key, value| match key {
+        b"Name" => poi.name = Some(value),
+        b"Pos" => poi.pos = Some(value),
+        b"Type" => poi._type = Some(value),
+        b"Num" => poi.num = Some(value),
+        b"Size" => poi.size = Some(value),
+        b"Point" => poi.point = Some(value),
+        b"Color" => poi.color = Some(value),
+        b"Bpm" => poi.bpm = Some(value),
+        _ => {}
+    })?;
+    Ok(poi)
+}
+
+fn parse_song_start(element: &BytesStart) -> Result<VirtualDJSong, String> {
+    let mut song = VirtualDJSong::default();
+    for_each_attribute(element, |key, value| {
+        if key == b"FilePath" {
+            song.file_path = value;
+        }
+    })?;
+    Ok(song)
+}
+
+/// Parse database.xml in a single pass straight into a `VdjIndex`.
+/// `on_progress` is called periodically; setting `cancel` aborts the parse.
+pub(crate) fn parse_database<R: BufRead>(
+    source: R,
+    total_bytes: u64,
+    cancel: Option<&AtomicBool>,
+    mut on_progress: impl FnMut(ImportProgress),
+) -> Result<VdjIndex, PikaError> {
+    let mut reader = Reader::from_reader(source);
+    let mut buf = Vec::with_capacity(4096);
+    let mut songs = VdjIndex::default();
+    let mut current: Option<VirtualDJSong> = None;
+    let mut seen_root = false;
+
+    let not_virtualdj = || PikaError::InvalidFormat {
+        message: "Not a VirtualDJ database (missing <VirtualDJ_Database>)".to_string(),
+        path: None,
+    };
+
+    // Counts every <Song> element, so path-less or duplicate entries (which
+    // don't grow the index) still advance cancel checks and progress
+    let mut processed: usize = 0;
+    let mut finish_song = |song: VirtualDJSong, songs: &mut VdjIndex, position: u64| -> Result<(), PikaError> {
+        if !song.file_path.is_empty() {
+            songs.insert(song);
+        }
+        processed += 1;
+        if processed.is_multiple_of(CANCEL_CHECK_EVERY_SONGS) && cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+            return Err(PikaError::Cancelled { message: "Import cancelled".to_string() });
+        }
+        if processed.is_multiple_of(PROGRESS_EVERY_SONGS) {
+            on_progress(ImportProgress { bytes_read: position, total_bytes, songs: songs.len() });
+        }
+        Ok(())
+    };
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| PikaError::xml_at(reader.error_position(), format!("XML parsing error: {}", e)))?;
+        // Attribute errors are reported at the element they belong to
+        let position = reader.buffer_position();
+        let attr_err = |message: String| PikaError::xml_at(position, message);
+
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) if !seen_root => {
+                if e.name().as_ref() != b"VirtualDJ_Database" {
+                    return Err(not_virtualdj());
+                }
+                seen_root = true;
+            }
+            Event::Start(ref e) if e.name().as_ref() == b"Song" => {
+                current = Some(parse_song_start(e).map_err(attr_err)?);
+            }
+            Event::Empty(ref e) if e.name().as_ref() == b"Song" => {
+                finish_song(parse_song_start(e).map_err(attr_err)?, &mut songs, position)?;
+            }
+            Event::End(ref e) if e.name().as_ref() == b"Song" => {
+                if let Some(song) = current.take() {
+                    finish_song(song, &mut songs, position)?;
+                }
+            }
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                if let Some(ref mut song) = current {
+                    match e.name().as_ref() {
+                        b"Tags" => song.tags = Some(parse_tags(e).map_err(attr_err)?),
+                        b"Scan" => song.scan = Some(parse_scan(e).map_err(attr_err)?),
+                        b"Infos" => song.infos = Some(parse_infos(e).map_err(attr_err)?),
+                        b"Poi" => song.pois.push(parse_poi(e).map_err(attr_err)?),
+                        _ => {}
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if !seen_root {
+        return Err(not_virtualdj());
+    }
+
+    on_progress(ImportProgress { bytes_read: total_bytes, total_bytes, songs: songs.len() });
+    Ok(songs)
+}
+
+/// Open and parse a database.xml file
+pub(crate) fn load_database(
+    path: &Path,
+    cancel: Option<&AtomicBool>,
+    on_progress: impl FnMut(ImportProgress),
+) -> Result<VdjIndex, PikaError> {
+    let file = std::fs::File::open(path).map_err(|e| PikaError::io("Failed to read database.xml", path, e))?;
+    let total_bytes = file.metadata().map(|m| m.len()).unwrap_or(0);
+    parse_database(std::io::BufReader::with_capacity(256 * 1024, file), total_bytes, cancel, on_progress)
+        .map_err(|e| e.in_file(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::time::Instant;
+
+    fn write_fixture(path: &Path, song_count: usize) {
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path).unwrap());
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+        writeln!(out, r#"<VirtualDJ_Database Version="8.5">"#).unwrap();
+        for i in 0..song_count {
+            writeln!(
+                out,
+                r#" <Song FilePath="D:\Music\Artist {i}\Track {i} &amp; Co.mp3" FileSize="{size}">
+  <Tags Author="Artist {i}" Title="Track {i}" Genre="WCS" Album="Album {a}" Year="2019" Flag="1" />
+  <Infos SongLength="{len}.5" FirstSeen="1600000000" PlayCount="3" />
+  <Comment>generated</Comment>
+  <Scan Version="801" Bpm="0.{bpm}" AltBpm="0.25" Volume="1.1" Key="Am" Flag="32768" />
+  <Poi Pos="0.071" Type="beatgrid" />
+  <Poi Name="Cue 1" Pos="32.5" Num="1" Type="cue" />
+  <Poi Pos="{len}.0" Type="automix" Point="realEnd" />
+ </Song>"#,
+                i = i,
+                a = i % 500,
+                size = 5_000_000 + i,
+                len = 150 + i % 120,
+                bpm = 45 + i % 20,
+            )
+            .unwrap();
+        }
+        writeln!(out, "</VirtualDJ_Database>").unwrap();
+    }
+
+    #[test]
+    fn test_parse_database_streaming() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("database.xml");
+        write_fixture(&path, 12_000);
+
+        let mut updates = Vec::new();
+        let songs = load_database(&path, None, |p| updates.push(p)).unwrap();
+        assert_eq!(songs.len(), 12_000);
+        assert_eq!(updates.len(), 3, "two periodic updates plus the final one");
+        assert_eq!(updates.last().unwrap().songs, 12_000);
+
+        let song = songs.get("D:\\Music\\Artist 7\\Track 7 & Co.mp3").unwrap();
+        assert_eq!(song.tags.as_ref().unwrap().author.as_deref(), Some("Artist 7"));
+        assert_eq!(song.scan.as_ref().unwrap().key.as_deref(), Some("Am"));
+        assert_eq!(song.infos.as_ref().unwrap().song_length.as_deref(), Some("157.5"));
+        assert_eq!(song.pois.len(), 3);
+
+        let cancelled = AtomicBool::new(true);
+        assert_eq!(load_database(&path, Some(&cancelled), |_| {}).unwrap_err().code(), "cancelled");
+
+        // Malformed XML reports the file and where in it
+        std::fs::write(&path, "<VirtualDJ_Database>\n <Song FilePath=\"a.mp3\">\n </Tags>\n").unwrap();
+        match load_database(&path, None, |_| {}).unwrap_err() {
+            PikaError::XmlParse { path: Some(file), line, .. } => {
+                assert_eq!(file, path.to_string_lossy());
+                assert_eq!(line, Some(3));
+            }
+            other => panic!("expected an XML error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_cancel_counts_duplicate_songs() {
+        // Duplicates don't grow the index past one song, but they still have to be counted
+        let songs = "<Song FilePath=\"a.mp3\" />".repeat(CANCEL_CHECK_EVERY_SONGS);
+        let xml = format!("<VirtualDJ_Database>{}</VirtualDJ_Database>", songs);
+        let cancelled = AtomicBool::new(true);
+        let err = parse_database(xml.as_bytes(), xml.len() as u64, Some(&cancelled), |_| {}).unwrap_err();
+        assert_eq!(err.code(), "cancelled");
+    }
+
+    #[test]
+    fn test_index_secondary_lookups() {
+        let mut index = VdjIndex::default();
+        index.insert(VirtualDJSong {
+            file_path: "C:\\Music\\Blues\\Song.mp3".to_string(),
+            tags: Some(VirtualDJTags {
+                author: Some("Some  Artist".to_string()),
+                title: Some("The Song".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+
+        assert!(index.get("C:\\Music\\Blues\\Song.mp3").is_some());
+        assert!(index.get("C:/Music/Blues/Song.mp3").is_some());
+        assert!(index.get("\\\\?\\C:\\Music\\Blues\\Song.mp3").is_some());
+        assert_eq!(index.get("c:\\music\\blues\\SONG.MP3").is_some(), CASE_INSENSITIVE_PATHS);
+        assert!(index.get("C:\\Music\\Other.mp3").is_none());
+        assert!(index.find_by_track("some artist", "the song").is_some());
+        assert!(index.find_by_track("Some Artist", "Other").is_none());
+
+        // Re-inserting the same path replaces the entry instead of duplicating it
+        index.insert(VirtualDJSong {
+            file_path: "C:\\Music\\Blues\\Song.mp3".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(index.len(), 1);
+        // ...and the old tags no longer find it
+        assert!(index.find_by_track("some artist", "the song").is_none());
+    }
+
+    #[test]
+    fn test_retagged_song_hands_key_to_duplicate() {
+        let song = |path: &str, title: &str| VirtualDJSong {
+            file_path: path.to_string(),
+            tags: Some(VirtualDJTags {
+                author: Some("Artist".to_string()),
+                title: Some(title.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut index = VdjIndex::default();
+        index.insert(song("C:\\a.mp3", "Tune"));
+        index.insert(song("C:\\b.mp3", "Tune"));
+        assert_eq!(index.find_by_track("Artist", "Tune").unwrap().file_path, "C:\\a.mp3");
+
+        index.insert(song("C:\\a.mp3", "Renamed"));
+        assert_eq!(index.find_by_track("Artist", "Tune").unwrap().file_path, "C:\\b.mp3");
+        assert_eq!(index.find_by_track("Artist", "Renamed").unwrap().file_path, "C:\\a.mp3");
+    }
+
+    #[test]
+    fn test_merged_index_reports_source_database() {
+        let song = |path: &str| VirtualDJSong { file_path: path.to_string(), ..Default::default() };
+        let mut home = VdjIndex::default();
+        home.insert(song("C:\\Music\\a.mp3"));
+        let mut usb = VdjIndex::default();
+        usb.insert(song("E:\\Music\\b.mp3"));
+
+        let mut merged = MergedVdjIndex::default();
+        merged.push(PathBuf::from("home.xml"), Arc::new(home));
+        merged.push(PathBuf::from("E:\\database.xml"), Arc::new(usb));
+
+        let (found, database) = merged.get("E:\\Music\\b.mp3").unwrap();
+        assert_eq!(found.file_path, "E:\\Music\\b.mp3");
+        assert_eq!(database, Path::new("E:\\database.xml"));
+        assert_eq!(merged.get("C:\\Music\\a.mp3").unwrap().1, Path::new("home.xml"));
+        assert!(merged.get("F:\\nope.mp3").is_none());
+    }
+
+    /// Run with: cargo test --release bench_parse_200k_songs -- --ignored --nocapture
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_parse_200k_songs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("database.xml");
+        write_fixture(&path, 200_000);
+        let size_mb = std::fs::metadata(&path).unwrap().len() as f64 / 1_048_576.0;
+
+        let started = Instant::now();
+        let songs = load_database(&path, None, |_| {}).unwrap();
+        let elapsed = started.elapsed();
+
+        println!(
+            "[bench] parsed {} songs ({:.1} MB) in {:.2?} ({:.0} songs/s)",
+            songs.len(),
+            size_mb,
+            elapsed,
+            songs.len() as f64 / elapsed.as_secs_f64()
+        );
+        assert_eq!(songs.len(), 200_000);
+    }
+}
@@ -0,0 +1,141 @@
+// Discovery of every VirtualDJ database.xml on this machine
+//
+// Besides the one in the VDJ home folder, VirtualDJ keeps a separate
+// database.xml on every external drive it has seen tracks on - at the drive
+// root, or in a VirtualDJ folder there (depending on the VDJ version).
+// Tracks on USB sticks only have BPM/key in that drive's database.
+
+use serde::Serialize;
+use specta::Type;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Where a database.xml was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseSource {
+    /// The VDJ home folder
+    Home,
+    /// Root of a mounted drive / volume
+    Volume,
+    /// Configured by the user
+    Custom,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredDatabase {
+    pub path: PathBuf,
+    pub source: DatabaseSource,
+}
+
+/// Roots of currently mounted volumes that may carry their own database.xml
+pub(crate) fn volume_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+
+    if cfg!(target_os = "windows") {
+        roots.extend((b'C'..=b'Z').map(|letter| PathBuf::from(format!("{}:\\", letter as char))));
+    } else {
+        let mut mount_dirs = vec![PathBuf::from("/Volumes"), PathBuf::from("/media"), PathBuf::from("/mnt")];
+        if let Ok(user) = std::env::var("USER") {
+            mount_dirs.push(PathBuf::from("/media").join(&user));
+            mount_dirs.push(PathBuf::from("/run/media").join(&user));
+        }
+        for dir in mount_dirs {
+            if let Ok(entries) = std::fs::read_dir(&dir) {
+                roots.extend(entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_dir()));
+            }
+        }
+    }
+
+    roots
+}
+
+/// A configured path may point at the database.xml itself or at the folder holding it
+fn resolve_database_file(path: &Path) -> Option<PathBuf> {
+    if path.is_dir() {
+        let candidate = path.join("database.xml");
+        candidate.is_file().then_some(candidate)
+    } else {
+        path.is_file().then(|| path.to_path_buf())
+    }
+}
+
+/// database.xml files VDJ may have left on a drive: at its root and in `<drive>/VirtualDJ`
+fn volume_database_files(root: &Path) -> Vec<PathBuf> {
+    [root.to_path_buf(), root.join("VirtualDJ")]
+        .iter()
+        .filter_map(|dir| resolve_database_file(dir))
+        .collect()
+}
+
+/// Every database.xml worth reading, in lookup priority order: home, volumes, custom.
+/// Duplicates (same file reached through different paths) are dropped.
+pub fn discover_databases(home_database: Option<PathBuf>, extra_paths: &[PathBuf]) -> Vec<DiscoveredDatabase> {
+    let mut found = Vec::new();
+    let mut seen = HashSet::new();
+    let mut push = |path: PathBuf, source: DatabaseSource| {
+        let key = std::fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if seen.insert(key) {
+            found.push(DiscoveredDatabase { path, source });
+        }
+    };
+
+    if let Some(home) = home_database {
+        push(home, DatabaseSource::Home);
+    }
+    for root in volume_roots() {
+        for path in volume_database_files(&root) {
+            push(path, DatabaseSource::Volume);
+        }
+    }
+    for extra in extra_paths {
+        if let Some(path) = resolve_database_file(extra) {
+            push(path, DatabaseSource::Custom);
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_custom_databases() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let usb = dir.join("usb");
+        std::fs::create_dir_all(&usb).unwrap();
+        let home_db = dir.join("database.xml");
+        std::fs::write(&home_db, "<VirtualDJ_Database />").unwrap();
+        std::fs::write(usb.join("database.xml"), "<VirtualDJ_Database />").unwrap();
+
+        let found = discover_databases(
+            Some(home_db.clone()),
+            // Folder form, file form of the same database, and a path with no database
+            &[usb.clone(), usb.join("database.xml"), dir.join("missing")],
+        );
+        let ours: Vec<_> = found.iter().filter(|d| d.path.starts_with(dir)).collect();
+
+        assert_eq!(ours.len(), 2);
+        assert_eq!(ours[0].path, home_db);
+        assert_eq!(ours[0].source, DatabaseSource::Home);
+        assert_eq!(ours[1].path, usb.join("database.xml"));
+        assert_eq!(ours[1].source, DatabaseSource::Custom);
+    }
+
+    #[test]
+    fn test_volume_database_locations() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("VirtualDJ")).unwrap();
+        assert!(volume_database_files(root).is_empty());
+
+        std::fs::write(root.join("VirtualDJ").join("database.xml"), "<VirtualDJ_Database />").unwrap();
+        assert_eq!(volume_database_files(root), vec![root.join("VirtualDJ").join("database.xml")]);
+
+        std::fs::write(root.join("database.xml"), "<VirtualDJ_Database />").unwrap();
+        assert_eq!(volume_database_files(root).len(), 2);
+    }
+}
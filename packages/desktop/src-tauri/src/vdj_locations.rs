@@ -0,0 +1,296 @@
+// VirtualDJ home folder resolution
+//
+// VDJ's home folder holds database.xml, settings.xml and the History folder.
+// It is usually Documents/VirtualDJ, but users move it (VDJ's own home-folder
+// setting), run VDJ under Wine on Linux, or carry a portable install on a
+// drive. Every candidate checked is recorded with the reason it was rejected
+// so the Settings screen can explain why nothing was found.
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Serialize;
+use specta::Type;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that points Pika at a VDJ home folder
+pub const VDJ_HOME_ENV: &str = "PIKA_VDJ_HOME";
+
+/// Why a folder was considered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateOrigin {
+    /// Configured in Pika's Settings
+    Setting,
+    /// `PIKA_VDJ_HOME`
+    Environment,
+    /// Home-folder override found in a VDJ settings.xml
+    SettingsXml,
+    /// Default install location for this OS
+    Standard,
+    /// Inside a Wine prefix (Linux)
+    Wine,
+    /// `VirtualDJ` folder at the root of a drive
+    Portable,
+}
+
+/// One folder that was checked, and what was found there
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct VdjHomeCandidate {
+    pub path: PathBuf,
+    pub origin: CandidateOrigin,
+    pub has_database: bool,
+    pub has_history: bool,
+    /// Why the folder was not used (None = usable)
+    pub rejected: Option<String>,
+}
+
+/// Result of resolving VDJ's locations, with a full diagnostic trail
+#[derive(Debug, Clone, Default, Serialize, Type)]
+pub struct VdjLocations {
+    /// First usable home folder
+    pub home: Option<PathBuf>,
+    /// First database.xml found, in candidate order
+    pub database: Option<PathBuf>,
+    /// First History folder found, in candidate order
+    pub history_dir: Option<PathBuf>,
+    pub candidates: Vec<VdjHomeCandidate>,
+}
+
+impl VdjLocations {
+    /// Resolve using the real environment
+    pub fn resolve(explicit: Option<&Path>) -> Self {
+        Self::resolve_with(explicit, |name| std::env::var(name).ok())
+    }
+
+    /// Resolve with an injectable environment lookup (for tests)
+    pub fn resolve_with(explicit: Option<&Path>, env: impl Fn(&str) -> Option<String>) -> Self {
+        let mut locations = VdjLocations::default();
+        for (path, origin) in candidate_paths(explicit, &env) {
+            locations.check(path, origin);
+        }
+        locations
+    }
+
+    fn check(&mut self, path: PathBuf, origin: CandidateOrigin) {
+        if self.candidates.iter().any(|c| c.path == path) {
+            return;
+        }
+
+        let database = path.join("database.xml");
+        let history = path.join("History");
+        let has_database = database.is_file();
+        let has_history = history.is_dir();
+
+        let rejected = if !path.exists() {
+            Some("Folder does not exist".to_string())
+        } else if !path.is_dir() {
+            Some("Not a folder".to_string())
+        } else if !has_database && !has_history && !path.join("settings.xml").is_file() {
+            Some("No database.xml, History folder or settings.xml".to_string())
+        } else {
+            None
+        };
+
+        if rejected.is_none() {
+            if self.home.is_none() {
+                self.home = Some(path.clone());
+            }
+            if has_database && self.database.is_none() {
+                self.database = Some(database);
+            }
+            if has_history && self.history_dir.is_none() {
+                self.history_dir = Some(history);
+            }
+        }
+
+        self.candidates.push(VdjHomeCandidate {
+            path,
+            origin,
+            has_database,
+            has_history,
+            rejected,
+        });
+    }
+}
+
+/// Candidate folders in priority order
+fn candidate_paths(explicit: Option<&Path>, env: &impl Fn(&str) -> Option<String>) -> Vec<(PathBuf, CandidateOrigin)> {
+    let mut candidates = Vec::new();
+
+    if let Some(path) = explicit {
+        candidates.push((path.to_path_buf(), CandidateOrigin::Setting));
+    }
+    if let Some(path) = env(VDJ_HOME_ENV).filter(|p| !p.is_empty()) {
+        candidates.push((PathBuf::from(path), CandidateOrigin::Environment));
+    }
+
+    let standard = env("HOME")
+        .or_else(|| env("USERPROFILE"))
+        .map(|home| standard_homes(Path::new(&home)))
+        .unwrap_or_default();
+
+    // A relocated home folder is recorded in the settings.xml of the default one
+    for home in &standard {
+        if let Some(path) = settings_xml_home_folder(&home.join("settings.xml")) {
+            candidates.push((path, CandidateOrigin::SettingsXml));
+        }
+    }
+    candidates.extend(standard.into_iter().map(|p| (p, CandidateOrigin::Standard)));
+
+    if !cfg!(target_os = "windows") {
+        candidates.extend(wine_homes(env).into_iter().map(|p| (p, CandidateOrigin::Wine)));
+    }
+    candidates.extend(
+        crate::vdj_discovery::volume_roots()
+            .into_iter()
+            .map(|root| (root.join("VirtualDJ"), CandidateOrigin::Portable))
+            .filter(|(p, _)| p.is_dir()),
+    );
+
+    candidates
+}
+
+fn standard_homes(home: &Path) -> Vec<PathBuf> {
+    vec![
+        // 1. Standard Documents location (Windows & macOS Modern)
+        home.join("Documents").join("VirtualDJ"),
+        // 2. macOS Legacy location
+        home.join("Library").join("Application Support").join("VirtualDJ"),
+        // 3. Windows AppData
+        home.join("AppData").join("Local").join("VirtualDJ"),
+    ]
+}
+
+/// VirtualDJ folders inside Wine prefixes ($WINEPREFIX, ~/.wine, Bottles)
+fn wine_homes(env: &impl Fn(&str) -> Option<String>) -> Vec<PathBuf> {
+    let mut prefixes = Vec::new();
+    if let Some(prefix) = env("WINEPREFIX") {
+        prefixes.push(PathBuf::from(prefix));
+    }
+    if let Some(home) = env("HOME") {
+        let home = PathBuf::from(home);
+        prefixes.push(home.join(".wine"));
+        let bottles = home.join(".local").join("share").join("bottles").join("bottles");
+        if let Ok(entries) = std::fs::read_dir(bottles) {
+            prefixes.extend(entries.filter_map(|e| e.ok()).map(|e| e.path()));
+        }
+    }
+
+    let mut homes = Vec::new();
+    for prefix in prefixes {
+        let Ok(users) = std::fs::read_dir(prefix.join("drive_c").join("users")) else {
+            continue;
+        };
+        for user in users.filter_map(|e| e.ok()).map(|e| e.path()) {
+            homes.push(user.join("Documents").join("VirtualDJ"));
+            // Older Wine versions
+            homes.push(user.join("My Documents").join("VirtualDJ"));
+        }
+    }
+    homes
+}
+
+/// Read a home-folder override from VDJ's settings.xml.
+/// Accepts `<homeFolder>path</homeFolder>` and `<setting name="homeFolder" value="path"/>` forms.
+fn settings_xml_home_folder(settings_path: &Path) -> Option<PathBuf> {
+    let content = std::fs::read_to_string(settings_path).ok()?;
+    if !content.to_ascii_lowercase().contains("homefolder") {
+        return None;
+    }
+
+    let is_home_folder = |name: &[u8]| name.eq_ignore_ascii_case(b"homeFolder");
+    let mut reader = Reader::from_str(&content);
+    loop {
+        let event = reader.read_event().ok()?;
+        let element = match event {
+            Event::Start(ref e) | Event::Empty(ref e) => e,
+            Event::Eof => return None,
+            _ => continue,
+        };
+
+        let mut named = is_home_folder(element.name().as_ref());
+        let mut value = None;
+        for attr in element.attributes().with_checks(false).flatten() {
+            let key = attr.key.as_ref();
+            if key.eq_ignore_ascii_case(b"name") && is_home_folder(&attr.value) {
+                named = true;
+            } else if key.eq_ignore_ascii_case(b"value") {
+                value = attr.unescape_value().ok().map(|v| v.trim().to_string());
+            }
+        }
+        if !named {
+            continue;
+        }
+
+        if let Some(value) = value.filter(|v| !v.is_empty()) {
+            return Some(PathBuf::from(value));
+        }
+        if matches!(event, Event::Start(_)) {
+            if let Ok(Event::Text(text)) = reader.read_event() {
+                let text = text.unescape().ok()?;
+                if !text.trim().is_empty() {
+                    return Some(PathBuf::from(text.trim()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_order_and_diagnostics() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let home = root.join("home");
+        let default_home = home.join("Documents").join("VirtualDJ");
+        let moved_home = root.join("moved");
+        std::fs::create_dir_all(&default_home).unwrap();
+        std::fs::create_dir_all(moved_home.join("History")).unwrap();
+        std::fs::write(moved_home.join("database.xml"), "<VirtualDJ_Database />").unwrap();
+        std::fs::write(
+            default_home.join("settings.xml"),
+            format!("<settings><homeFolder>{}</homeFolder></settings>", moved_home.display()),
+        )
+        .unwrap();
+
+        let env_home = home.to_string_lossy().into_owned();
+        let env = |name: &str| match name {
+            "HOME" => Some(env_home.clone()),
+            VDJ_HOME_ENV => Some(root.join("missing").to_string_lossy().into_owned()),
+            _ => None,
+        };
+        let locations = VdjLocations::resolve_with(None, env);
+
+        // PIKA_VDJ_HOME is checked first but doesn't exist
+        assert_eq!(locations.candidates[0].origin, CandidateOrigin::Environment);
+        assert_eq!(locations.candidates[0].rejected.as_deref(), Some("Folder does not exist"));
+        // settings.xml override wins over the default location
+        assert_eq!(locations.candidates[1].origin, CandidateOrigin::SettingsXml);
+        assert_eq!(locations.home.as_deref(), Some(moved_home.as_path()));
+        assert_eq!(locations.database, Some(moved_home.join("database.xml")));
+        assert_eq!(locations.history_dir, Some(moved_home.join("History")));
+        // The default folder is still listed, and usable because it has settings.xml
+        let default = locations.candidates.iter().find(|c| c.path == default_home).unwrap();
+        assert_eq!(default.origin, CandidateOrigin::Standard);
+        assert!(default.rejected.is_none());
+
+        // An explicit setting takes priority over everything
+        let explicit = VdjLocations::resolve_with(Some(&default_home), |_| None);
+        assert_eq!(explicit.candidates[0].origin, CandidateOrigin::Setting);
+        assert_eq!(explicit.home.as_deref(), Some(default_home.as_path()));
+        assert!(explicit.database.is_none());
+    }
+
+    #[test]
+    fn test_settings_xml_attribute_form() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("settings.xml");
+        std::fs::write(&path, r#"<settings><setting name="HomeFolder" value="D:\VirtualDJ" /></settings>"#).unwrap();
+        assert_eq!(settings_xml_home_folder(&path), Some(PathBuf::from("D:\\VirtualDJ")));
+        std::fs::write(&path, r#"<settings><skin>default</skin></settings>"#).unwrap();
+        assert_eq!(settings_xml_home_folder(&path), None);
+    }
+}
@@ -0,0 +1,189 @@
+// On-disk snapshot of the parsed VirtualDJ index
+//
+// VDJ_CACHE only lives in memory, so every launch re-parsed database.xml on the
+// first lookup - right when the DJ is starting their set. The parsed songs are
+// written to the app data dir after each parse and loaded back at startup as
+// long as database.xml still has the same path, size and modification time.
+
+use serde::{Deserialize, Serialize};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::vdj_database::{VdjIndex, VirtualDJSong};
+
+/// Bump whenever `VirtualDJSong` (or anything it contains) changes shape,
+/// so snapshots written by older builds are rebuilt instead of misread
+const SNAPSHOT_VERSION: u32 = 1;
+const SNAPSHOT_MAGIC: [u8; 4] = *b"PVDJ";
+
+/// Written before the songs so staleness can be checked without reading the whole file
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SnapshotHeader {
+    magic: [u8; 4],
+    version: u32,
+    db_path: String,
+    db_size: u64,
+    /// database.xml mtime as nanoseconds since the Unix epoch
+    db_modified: u128,
+}
+
+impl SnapshotHeader {
+    fn for_database(db_path: &Path, metadata: &std::fs::Metadata) -> Option<Self> {
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            magic: SNAPSHOT_MAGIC,
+            version: SNAPSHOT_VERSION,
+            db_path: db_path.to_string_lossy().into_owned(),
+            db_size: metadata.len(),
+            db_modified: modified.as_nanos(),
+        })
+    }
+}
+
+/// Stable (FNV-1a) hash of the database path, used as the snapshot file name
+fn path_hash(path: &str) -> u64 {
+    path.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn snapshot_path(snapshot_dir: &Path, db_path: &Path) -> PathBuf {
+    snapshot_dir.join(format!("vdj-index-{:016x}.bin", path_hash(&db_path.to_string_lossy())))
+}
+
+/// Load the snapshot for `db_path` if it matches the file currently on disk
+pub(crate) fn load(snapshot_dir: &Path, db_path: &Path, metadata: &std::fs::Metadata) -> Option<VdjIndex> {
+    let expected = SnapshotHeader::for_database(db_path, metadata)?;
+    let file = std::fs::File::open(snapshot_path(snapshot_dir, db_path)).ok()?;
+    let mut reader = BufReader::with_capacity(256 * 1024, file);
+
+    let header: SnapshotHeader = bincode::deserialize_from(&mut reader).ok()?;
+    if header != expected {
+        return None;
+    }
+
+    let songs: Vec<VirtualDJSong> = bincode::deserialize_from(&mut reader)
+        .map_err(|e| eprintln!("[VDJ] Discarding unreadable index snapshot: {}", e))
+        .ok()?;
+    let mut index = VdjIndex::default();
+    songs.into_iter().for_each(|song| index.insert(song));
+    Some(index)
+}
+
+/// Write a snapshot of `index` for `db_path` (atomically, via a temp file)
+pub(crate) fn save(snapshot_dir: &Path, db_path: &Path, metadata: &std::fs::Metadata, index: &VdjIndex) -> Result<(), String> {
+    let header = SnapshotHeader::for_database(db_path, metadata)
+        .ok_or_else(|| "Failed to get modification time".to_string())?;
+    std::fs::create_dir_all(snapshot_dir)
+        .map_err(|e| format!("Failed to create snapshot directory: {}", e))?;
+
+    let path = snapshot_path(snapshot_dir, db_path);
+    let tmp_path = path.with_extension("bin.tmp");
+    let write = || -> Result<(), String> {
+        let file = std::fs::File::create(&tmp_path).map_err(|e| format!("Failed to create snapshot: {}", e))?;
+        let mut writer = BufWriter::with_capacity(256 * 1024, file);
+        bincode::serialize_into(&mut writer, &header).map_err(|e| format!("Failed to write snapshot: {}", e))?;
+        let songs: Vec<&VirtualDJSong> = index.songs().collect();
+        bincode::serialize_into(&mut writer, &songs).map_err(|e| format!("Failed to write snapshot: {}", e))?;
+        writer.flush().map_err(|e| format!("Failed to write snapshot: {}", e))?;
+        std::fs::rename(&tmp_path, &path).map_err(|e| format!("Failed to replace snapshot: {}", e))
+    };
+
+    write().inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp_path);
+    })?;
+    prune(snapshot_dir, &path);
+    Ok(())
+}
+
+/// Delete snapshots nothing will load again: written by another snapshot version,
+/// or unreadable. Snapshots whose database.xml is missing are kept - that's
+/// usually an unplugged drive, which should load instantly when it's back.
+fn prune(snapshot_dir: &Path, keep: &Path) {
+    let Ok(entries) = std::fs::read_dir(snapshot_dir) else { return };
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        let is_snapshot = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("vdj-index-") && name.ends_with(".bin"));
+        if !is_snapshot || path == keep {
+            continue;
+        }
+
+        let header = std::fs::File::open(&path)
+            .ok()
+            .and_then(|file| bincode::deserialize_from::<_, SnapshotHeader>(BufReader::new(file)).ok());
+        let live = header.is_some_and(|header| {
+            header.magic == SNAPSHOT_MAGIC
+                && header.version == SNAPSHOT_VERSION
+                && snapshot_path(snapshot_dir, Path::new(&header.db_path)) == path
+        });
+        if !live {
+            println!("[VDJ] Removing stale index snapshot {}", path.display());
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_round_trip_and_invalidation() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let db_path = dir.join("database.xml");
+        let xml = r#"<VirtualDJ_Database Version="8.5">
+ <Song FilePath="/music/a.mp3"><Tags Author="A" Title="Song" /><Scan Bpm="0.5" Key="Am" /></Song>
+</VirtualDJ_Database>"#;
+        std::fs::write(&db_path, xml).unwrap();
+        let index = crate::vdj_database::load_database(&db_path, None, |_| {}).unwrap();
+
+        let metadata = std::fs::metadata(&db_path).unwrap();
+        save(dir, &db_path, &metadata, &index).unwrap();
+
+        let loaded = load(dir, &db_path, &metadata).unwrap();
+        assert_eq!(loaded.len(), 1);
+        let song = loaded.get("/music/a.mp3").unwrap();
+        assert_eq!(song.scan.as_ref().unwrap().key.as_deref(), Some("Am"));
+        assert!(loaded.find_by_track("a", "song").is_some());
+
+        // VDJ rewrote the file - the snapshot no longer applies
+        std::fs::write(&db_path, format!("{}\n", xml)).unwrap();
+        let changed = std::fs::metadata(&db_path).unwrap();
+        assert!(load(dir, &db_path, &changed).is_none());
+    }
+
+    #[test]
+    fn test_save_prunes_stale_snapshots() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let xml = "<VirtualDJ_Database Version=\"8.5\"></VirtualDJ_Database>";
+        let home = dir.join("home.xml");
+        let usb = dir.join("usb.xml");
+        std::fs::write(&home, xml).unwrap();
+        std::fs::write(&usb, xml).unwrap();
+        let index = VdjIndex::default();
+
+        save(dir, &usb, &std::fs::metadata(&usb).unwrap(), &index).unwrap();
+        // Left behind by an older build
+        let outdated = dir.join("vdj-index-0000000000000001.bin");
+        std::fs::write(&outdated, b"garbage").unwrap();
+        let old_db = dir.join("old.xml");
+        let old_version = snapshot_path(dir, &old_db);
+        let mut header = SnapshotHeader::for_database(&old_db, &std::fs::metadata(&home).unwrap()).unwrap();
+        header.version = SNAPSHOT_VERSION + 1;
+        std::fs::write(&old_version, bincode::serialize(&header).unwrap()).unwrap();
+
+        // The drive was unplugged; its snapshot stays for when it's back
+        std::fs::remove_file(&usb).unwrap();
+        save(dir, &home, &std::fs::metadata(&home).unwrap(), &index).unwrap();
+
+        assert!(snapshot_path(dir, &home).exists());
+        assert!(snapshot_path(dir, &usb).exists());
+        assert!(!outdated.exists());
+        assert!(!old_version.exists());
+    }
+}
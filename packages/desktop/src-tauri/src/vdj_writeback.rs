@@ -0,0 +1,616 @@
+// Write-back of Pika tags, notes and ratings into VirtualDJ's database.xml
+//
+// The serde models in lib.rs are read-only and drop anything they don't know,
+// so writing through them would destroy data. Instead the original bytes are
+// scanned with quick_xml only to locate byte ranges, and edits are spliced in:
+// every element and attribute we don't touch stays byte-for-byte identical.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::error::PikaError;
+
+/// Refuse to write if database.xml changed this recently (VDJ is probably saving)
+const MIN_QUIET_PERIOD: Duration = Duration::from_secs(3);
+
+/// VDJ fields Pika data can be written into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+pub enum VdjField {
+    /// The `<Comment>` element of a song
+    Comment,
+    /// `Tags@User1`
+    User1,
+    /// `Tags@User2`
+    User2,
+}
+
+/// Pika data for a single track. `None` leaves the VDJ field untouched.
+#[derive(Debug, Clone, Deserialize, Type)]
+pub struct VdjTagUpdate {
+    pub file_path: String,
+    pub tags: Option<Vec<String>>,
+    pub notes: Option<String>,
+    /// 1-5 stars, written to `Tags@Stars`; 0 removes the rating
+    pub rating: Option<u8>,
+}
+
+/// Which VDJ field receives which piece of Pika data
+#[derive(Debug, Clone, Deserialize, Type)]
+#[serde(default)]
+pub struct VdjWriteBackOptions {
+    pub tags_field: VdjField,
+    pub notes_field: VdjField,
+}
+
+impl Default for VdjWriteBackOptions {
+    fn default() -> Self {
+        Self {
+            tags_field: VdjField::User1,
+            notes_field: VdjField::Comment,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Type)]
+pub struct VdjWriteBackReport {
+    /// Songs that were modified
+    updated: usize,
+    /// Requested file paths that are not in database.xml
+    not_found: Vec<String>,
+    backup_path: String,
+}
+
+/// Edits for a single `<Song>`
+#[derive(Debug, Default)]
+struct SongEdit {
+    /// Attributes to set on `<Tags>` (an empty value removes the attribute)
+    tag_attrs: Vec<(&'static str, String)>,
+    comment: Option<String>,
+}
+
+impl SongEdit {
+    fn from_update(update: &VdjTagUpdate, options: &VdjWriteBackOptions) -> Self {
+        let mut edit = SongEdit::default();
+        if let Some(ref tags) = update.tags {
+            edit.set(options.tags_field, tags.join(", "));
+        }
+        if let Some(ref notes) = update.notes {
+            edit.set(options.notes_field, notes.clone());
+        }
+        if let Some(rating) = update.rating {
+            // VDJ has no "0 stars"; unrated songs simply lack the attribute
+            let stars = if rating == 0 { String::new() } else { rating.min(5).to_string() };
+            edit.tag_attrs.push(("Stars", stars));
+        }
+        edit
+    }
+
+    fn set(&mut self, field: VdjField, value: String) {
+        match field {
+            VdjField::Comment => self.comment = Some(value),
+            VdjField::User1 => self.tag_attrs.push(("User1", value)),
+            VdjField::User2 => self.tag_attrs.push(("User2", value)),
+        }
+    }
+}
+
+/// Key used to match file paths, folded like the VDJ index folds them
+fn path_key(path: &str) -> String {
+    if crate::vdj_database::CASE_INSENSITIVE_PATHS {
+        path.to_lowercase()
+    } else {
+        path.to_string()
+    }
+}
+
+fn xml_err(e: impl std::fmt::Display) -> String {
+    format!("XML parsing error: {}", e)
+}
+
+fn escape(value: &str) -> String {
+    quick_xml::escape::escape(value).into_owned()
+}
+
+/// Byte ranges of one attribute within a start tag
+struct AttrSpan {
+    /// Start of the whitespace before the name
+    start: usize,
+    name: std::ops::Range<usize>,
+    /// Between the quotes
+    value: std::ops::Range<usize>,
+    /// Just past the closing quote
+    end: usize,
+}
+
+/// Locate every attribute of a raw start/empty tag. Returns where the element
+/// name ends (for tags without attributes) and the attribute spans.
+fn attribute_spans(tag: &[u8]) -> Result<(usize, Vec<AttrSpan>), String> {
+    let malformed = || xml_err(format!("malformed attributes in {}", String::from_utf8_lossy(tag)));
+    let is_space = |b: u8| b.is_ascii_whitespace();
+    let skip_space = |mut pos: usize| {
+        while pos < tag.len() && is_space(tag[pos]) {
+            pos += 1;
+        }
+        pos
+    };
+
+    let mut pos = 1;
+    while pos < tag.len() && !is_space(tag[pos]) && tag[pos] != b'/' && tag[pos] != b'>' {
+        pos += 1;
+    }
+    let name_end = pos;
+
+    let mut spans = Vec::new();
+    loop {
+        let start = pos;
+        pos = skip_space(pos);
+        if pos >= tag.len() || tag[pos] == b'/' || tag[pos] == b'>' {
+            break;
+        }
+        let name_start = pos;
+        while pos < tag.len() && !is_space(tag[pos]) && tag[pos] != b'=' {
+            pos += 1;
+        }
+        let name = name_start..pos;
+        pos = skip_space(pos);
+        if tag.get(pos) != Some(&b'=') {
+            return Err(malformed());
+        }
+        pos = skip_space(pos + 1);
+        let quote = match tag.get(pos) {
+            Some(&q) if q == b'"' || q == b'\'' => q,
+            _ => return Err(malformed()),
+        };
+        let value_start = pos + 1;
+        let value_len = tag[value_start..].iter().position(|&b| b == quote).ok_or_else(malformed)?;
+        pos = value_start + value_len + 1;
+        spans.push(AttrSpan { start, name, value: value_start..value_start + value_len, end: pos });
+    }
+    Ok((name_end, spans))
+}
+
+/// Rebuild a `<Tags>` start/empty tag from its original bytes: only the values of
+/// edited attributes are replaced (keeping their quotes), attributes set to an
+/// empty value are removed, and new ones are appended after the last attribute.
+/// Everything else, spacing and quote style included, is copied as is.
+fn rebuild_tags(original: &[u8], edits: &[(&'static str, String)]) -> Result<Vec<u8>, String> {
+    let (name_end, spans) = attribute_spans(original)?;
+    let mut out = Vec::with_capacity(original.len() + 64);
+    let mut written: HashSet<&str> = HashSet::new();
+    let mut copied = 0;
+
+    for span in &spans {
+        let key = &original[span.name.clone()];
+        let Some((name, value)) = edits.iter().find(|(name, _)| name.as_bytes() == key) else {
+            continue;
+        };
+        written.insert(name);
+        if value.is_empty() {
+            out.extend_from_slice(&original[copied..span.start]);
+            copied = span.end;
+        } else {
+            out.extend_from_slice(&original[copied..span.value.start]);
+            out.extend_from_slice(escape(value).as_bytes());
+            copied = span.value.end;
+        }
+    }
+
+    let insert_at = spans.last().map_or(name_end, |span| span.end);
+    out.extend_from_slice(&original[copied..insert_at]);
+    for (name, value) in edits {
+        if !written.contains(name) && !value.is_empty() {
+            out.extend_from_slice(format!(" {}=\"{}\"", name, escape(value)).as_bytes());
+        }
+    }
+    out.extend_from_slice(&original[insert_at..]);
+    Ok(out)
+}
+
+fn attribute_value(element: &BytesStart, name: &[u8]) -> Result<Option<String>, String> {
+    for attr in element.attributes().with_checks(false) {
+        let attr = attr.map_err(xml_err)?;
+        if attr.key.as_ref() == name {
+            return attr.unescape_value().map(|v| Some(v.into_owned())).map_err(xml_err);
+        }
+    }
+    Ok(None)
+}
+
+/// State while walking a `<Song>` that has edits
+struct OpenSong<'a> {
+    key: String,
+    edit: &'a SongEdit,
+    saw_tags: bool,
+    saw_comment: bool,
+    comment_content_start: Option<usize>,
+    /// Whitespace used to indent the song's children
+    child_indent: Option<Vec<u8>>,
+    /// Start of a whitespace-only text node that may turn out to precede `</Song>`
+    trailing_ws_start: Option<usize>,
+}
+
+/// Apply edits to raw database.xml bytes. Returns the new bytes and the keys of matched songs.
+fn apply_edits(xml: &[u8], edits: &HashMap<String, SongEdit>) -> Result<(Vec<u8>, HashSet<String>), PikaError> {
+    // (start, end, replacement) - non-overlapping, produced in document order
+    let mut splices: Vec<(usize, usize, Vec<u8>)> = Vec::new();
+    let mut matched = HashSet::new();
+    let mut song: Option<OpenSong> = None;
+
+    let mut reader = Reader::from_reader(xml);
+    let mut buf = Vec::new();
+    loop {
+        let start = reader.buffer_position() as usize;
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| PikaError::xml_at(reader.error_position(), format!("XML parsing error: {}", e)))?;
+        let end = reader.buffer_position() as usize;
+        // Attribute errors are reported at the element they belong to
+        let attr_err = |message: String| PikaError::xml_at(start as u64, message);
+
+        match event {
+            Event::Eof => break,
+            Event::Start(ref e) if e.name().as_ref() == b"Song" => {
+                if let Some(path) = attribute_value(e, b"FilePath").map_err(attr_err)? {
+                    let key = path_key(&path);
+                    if let Some(edit) = edits.get(&key) {
+                        song = Some(OpenSong {
+                            key,
+                            edit,
+                            saw_tags: false,
+                            saw_comment: false,
+                            comment_content_start: None,
+                            child_indent: None,
+                            trailing_ws_start: None,
+                        });
+                    }
+                }
+            }
+            Event::Empty(ref e) if e.name().as_ref() == b"Song" => {
+                // Childless song: expand it so the edits have somewhere to go
+                if let Some(path) = attribute_value(e, b"FilePath").map_err(attr_err)? {
+                    let key = path_key(&path);
+                    if let Some(edit) = edits.get(&key) {
+                        let original = &xml[start..end];
+                        let open_tag = original
+                            .strip_suffix(b"/>")
+                            .map(|s| s.strip_suffix(b" ").unwrap_or(s))
+                            .unwrap_or(original);
+                        let mut replacement = open_tag.to_vec();
+                        replacement.push(b'>');
+                        replacement.extend(missing_children(edit, true, true, b"").map_err(attr_err)?);
+                        replacement.extend_from_slice(b"</Song>");
+                        splices.push((start, end, replacement));
+                        matched.insert(key);
+                    }
+                }
+            }
+            Event::Empty(ref e) | Event::Start(ref e) if song.is_some() => {
+                let open = song.as_mut().expect("checked above");
+                open.trailing_ws_start = None;
+                match e.name().as_ref() {
+                    b"Tags" => {
+                        open.saw_tags = true;
+                        if !open.edit.tag_attrs.is_empty() {
+                            splices.push((start, end, rebuild_tags(&xml[start..end], &open.edit.tag_attrs).map_err(attr_err)?));
+                        }
+                    }
+                    b"Comment" => {
+                        open.saw_comment = true;
+                        if let Some(ref comment) = open.edit.comment {
+                            if matches!(event, Event::Empty(_)) {
+                                splices.push((start, end, format!("<Comment>{}</Comment>", escape(comment)).into_bytes()));
+                            } else {
+                                open.comment_content_start = Some(end);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::End(ref e) if song.is_some() => {
+                let name = e.name();
+                if name.as_ref() == b"Comment" {
+                    let open = song.as_mut().expect("checked above");
+                    if let (Some(content_start), Some(comment)) = (open.comment_content_start.take(), &open.edit.comment) {
+                        splices.push((content_start, start, escape(comment).into_bytes()));
+                    }
+                    open.trailing_ws_start = None;
+                } else if name.as_ref() == b"Song" {
+                    let open = song.take().expect("checked above");
+                    let indent = open.child_indent.clone().unwrap_or_default();
+                    let insert_at = open.trailing_ws_start.unwrap_or(start);
+                    let children = missing_children(open.edit, !open.saw_tags, !open.saw_comment, &indent).map_err(attr_err)?;
+                    if !children.is_empty() {
+                        splices.push((insert_at, insert_at, children));
+                    }
+                    matched.insert(open.key);
+                }
+            }
+            Event::Text(ref t) if song.is_some() => {
+                let open = song.as_mut().expect("checked above");
+                if open.comment_content_start.is_none() && t.iter().all(u8::is_ascii_whitespace) {
+                    if open.child_indent.is_none() {
+                        open.child_indent = Some(t.to_vec());
+                    }
+                    open.trailing_ws_start = Some(start);
+                } else {
+                    open.trailing_ws_start = None;
+                }
+            }
+            _ => {
+                if let Some(open) = song.as_mut() {
+                    open.trailing_ws_start = None;
+                }
+            }
+        }
+        buf.clear();
+    }
+
+    let mut out = Vec::with_capacity(xml.len() + splices.len() * 64);
+    let mut copied = 0;
+    for (start, end, replacement) in splices {
+        out.extend_from_slice(&xml[copied..start]);
+        out.extend_from_slice(&replacement);
+        copied = end;
+    }
+    out.extend_from_slice(&xml[copied..]);
+
+    Ok((out, matched))
+}
+
+/// `<Tags>` / `<Comment>` elements for a song that doesn't have them yet
+fn missing_children(edit: &SongEdit, need_tags: bool, need_comment: bool, indent: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    if need_tags && edit.tag_attrs.iter().any(|(_, v)| !v.is_empty()) {
+        out.extend_from_slice(indent);
+        out.extend(rebuild_tags(b"<Tags />", &edit.tag_attrs)?);
+    }
+    if need_comment {
+        if let Some(ref comment) = edit.comment {
+            out.extend_from_slice(indent);
+            out.extend_from_slice(format!("<Comment>{}</Comment>", escape(comment)).as_bytes());
+        }
+    }
+    Ok(out)
+}
+
+/// A backup file name not used by an earlier write (two can land in the same millisecond)
+fn new_backup_path(db_path: &Path, file_name: &str) -> PathBuf {
+    let stem = format!("{}.pika-backup-{}", file_name, chrono::Local::now().format("%Y%m%d-%H%M%S%.3f"));
+    let mut path = db_path.with_file_name(&stem);
+    let mut n = 1;
+    while path.exists() {
+        path = db_path.with_file_name(format!("{}-{}", stem, n));
+        n += 1;
+    }
+    path
+}
+
+fn modified_and_len(path: &Path) -> Result<(SystemTime, u64), PikaError> {
+    let metadata = std::fs::metadata(path).map_err(|e| PikaError::io("Failed to get database metadata", path, e))?;
+    let modified = metadata.modified().map_err(|e| PikaError::io("Failed to get modification time", path, e))?;
+    Ok((modified, metadata.len()))
+}
+
+/// Write Pika data into database.xml.
+/// Takes a timestamped backup first and writes atomically via a temp file + rename.
+pub fn write_back(db_path: &Path, updates: &[VdjTagUpdate], options: &VdjWriteBackOptions) -> Result<VdjWriteBackReport, PikaError> {
+    write_back_with(db_path, updates, options, || {})
+}
+
+/// `write_back`, calling `before_commit` once the new contents are ready
+/// (tests use it to play VDJ saving mid-update)
+fn write_back_with(
+    db_path: &Path,
+    updates: &[VdjTagUpdate],
+    options: &VdjWriteBackOptions,
+    before_commit: impl FnOnce(),
+) -> Result<VdjWriteBackReport, PikaError> {
+    if options.tags_field == options.notes_field {
+        return Err(PikaError::invalid_input("Tags and notes cannot be written to the same VirtualDJ field"));
+    }
+
+    let before = modified_and_len(db_path)?;
+    let age = SystemTime::now().duration_since(before.0).unwrap_or_default();
+    if age < MIN_QUIET_PERIOD {
+        return Err(PikaError::busy("database.xml is being modified (is VirtualDJ saving?). Try again in a moment", db_path));
+    }
+
+    let edits: HashMap<String, SongEdit> = updates
+        .iter()
+        .map(|u| (path_key(&u.file_path), SongEdit::from_update(u, options)))
+        .collect();
+
+    let original = std::fs::read(db_path).map_err(|e| PikaError::io("Failed to read database.xml", db_path, e))?;
+    let (output, matched) = apply_edits(&original, &edits).map_err(|e| e.in_file(db_path))?;
+    before_commit();
+
+    // Someone wrote to the file while we were working - don't clobber their changes
+    if modified_and_len(db_path)? != before {
+        return Err(PikaError::busy("database.xml changed while preparing the update. Nothing was written", db_path));
+    }
+
+    let file_name = db_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "database.xml".to_string());
+    let backup_path = new_backup_path(db_path, &file_name);
+    std::fs::copy(db_path, &backup_path).map_err(|e| PikaError::io("Failed to back up database.xml", &backup_path, e))?;
+
+    let tmp_path: PathBuf = db_path.with_file_name(format!("{}.pika-tmp", file_name));
+    std::fs::write(&tmp_path, &output).map_err(|e| PikaError::io("Failed to write database.xml", &tmp_path, e))?;
+    std::fs::rename(&tmp_path, db_path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp_path);
+        PikaError::io("Failed to replace database.xml", db_path, e)
+    })?;
+
+    let not_found = updates
+        .iter()
+        .filter(|u| !matched.contains(&path_key(&u.file_path)))
+        .map(|u| u.file_path.clone())
+        .collect();
+
+    Ok(VdjWriteBackReport {
+        updated: matched.len(),
+        not_found,
+        backup_path: backup_path.to_string_lossy().into_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATABASE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<VirtualDJ_Database Version="8.5">
+ <Song FilePath="C:\Music\a &amp; b.mp3" FileSize="100">
+  <Tags Author="A" Title="B" User1="old" Unknown='x"y' />
+  <Infos SongLength="200.0" Custom="keep" />
+  <Comment>old comment</Comment>
+  <Poi Pos="1.0" Type="cue" Num="1" />
+ </Song>
+ <Song FilePath="C:\Music\c.mp3" FileSize="200">
+  <Infos SongLength="100.0" />
+ </Song>
+ <Song FilePath="C:\Music\untouched.mp3"><Weird>data</Weird></Song>
+</VirtualDJ_Database>
+"#;
+
+    fn edits_for(updates: &[VdjTagUpdate]) -> HashMap<String, SongEdit> {
+        let options = VdjWriteBackOptions::default();
+        updates
+            .iter()
+            .map(|u| (path_key(&u.file_path), SongEdit::from_update(u, &options)))
+            .collect()
+    }
+
+    #[test]
+    fn test_no_edits_is_byte_identical() {
+        let (output, matched) = apply_edits(DATABASE.as_bytes(), &HashMap::new()).unwrap();
+        assert_eq!(output, DATABASE.as_bytes());
+        assert!(matched.is_empty());
+    }
+
+    #[test]
+    fn test_edits_only_touch_target_fields() {
+        let edits = edits_for(&[
+            VdjTagUpdate {
+                file_path: "C:\\Music\\a & b.mp3".to_string(),
+                tags: Some(vec!["blues".to_string(), "opener".to_string()]),
+                notes: Some("Crowd <3".to_string()),
+                rating: Some(4),
+            },
+            VdjTagUpdate {
+                file_path: "C:\\Music\\c.mp3".to_string(),
+                tags: Some(vec!["peak".to_string()]),
+                notes: Some("new".to_string()),
+                rating: None,
+            },
+        ]);
+        let (output, matched) = apply_edits(DATABASE.as_bytes(), &edits).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(matched.len(), 2);
+        assert!(output.contains(r#"<Tags Author="A" Title="B" User1="blues, opener" Unknown='x"y' Stars="4" />"#));
+        assert!(output.contains("<Comment>Crowd &lt;3</Comment>"));
+        assert!(output.contains(
+            "<Infos SongLength=\"100.0\" />\n  <Tags User1=\"peak\" />\n  <Comment>new</Comment>\n </Song>"
+        ));
+        // Everything else is unchanged
+        assert!(output.contains(r#"<Infos SongLength="200.0" Custom="keep" />"#));
+        assert!(output.contains(r#"<Song FilePath="C:\Music\untouched.mp3"><Weird>data</Weird></Song>"#));
+        assert!(output.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+    }
+
+    #[test]
+    fn test_tags_rewrite_keeps_raw_bytes() {
+        let original = b"<Tags  Author='Mr \"X\" &amp; co'   Title=\"T\"\tStars='3'  User1 = 'old' />";
+        let edits = [("User1", "new & 'improved'".to_string()), ("Stars", String::new()), ("User2", "added".to_string())];
+        let rebuilt = rebuild_tags(original, &edits).unwrap();
+        assert_eq!(
+            String::from_utf8(rebuilt).unwrap(),
+            "<Tags  Author='Mr \"X\" &amp; co'   Title=\"T\"  User1 = 'new &amp; &apos;improved&apos;' User2=\"added\" />"
+        );
+
+        // Nothing to edit: identical bytes, whatever the formatting
+        let odd = b"<Tags\n   Author='A'\n   Title=\"B\">";
+        assert_eq!(rebuild_tags(odd, &[("User2", String::new())]).unwrap(), odd.to_vec());
+    }
+
+    #[test]
+    fn test_zero_rating_removes_stars() {
+        let xml = "<VirtualDJ_Database>\n <Song FilePath=\"/a.mp3\">\n  <Tags Author='A' Stars='4' Title=\"B\"/>\n </Song>\n</VirtualDJ_Database>\n";
+        let edits = edits_for(&[VdjTagUpdate { file_path: "/a.mp3".to_string(), tags: None, notes: None, rating: Some(0) }]);
+        let (output, _) = apply_edits(xml.as_bytes(), &edits).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), xml.replace(" Stars='4'", ""));
+    }
+
+    /// database.xml in a temp dir, last written a minute ago (outside `MIN_QUIET_PERIOD`)
+    fn quiet_database(dir: &Path) -> PathBuf {
+        let db_path = dir.join("database.xml");
+        std::fs::write(&db_path, DATABASE).unwrap();
+        set_quiet(&db_path);
+        db_path
+    }
+
+    fn set_quiet(path: &Path) {
+        let file = std::fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(60)).unwrap();
+    }
+
+    fn rating_update() -> VdjTagUpdate {
+        VdjTagUpdate { file_path: "C:\\Music\\c.mp3".to_string(), tags: None, notes: None, rating: Some(5) }
+    }
+
+    #[test]
+    fn test_write_back_backs_up_and_replaces() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = quiet_database(tmp.path());
+        let missing = VdjTagUpdate { file_path: "C:\\Music\\gone.mp3".to_string(), ..rating_update() };
+
+        let report = write_back(&db_path, &[rating_update(), missing], &VdjWriteBackOptions::default()).unwrap();
+        assert_eq!(report.updated, 1);
+        assert_eq!(report.not_found, ["C:\\Music\\gone.mp3"]);
+        assert_eq!(std::fs::read_to_string(&report.backup_path).unwrap(), DATABASE);
+        assert!(std::fs::read_to_string(&db_path).unwrap().contains("<Tags Stars=\"5\" />"));
+        assert!(!tmp.path().join("database.xml.pika-tmp").exists());
+
+        // A second write straight after keeps the first backup
+        set_quiet(&db_path);
+        let again = write_back(&db_path, &[rating_update()], &VdjWriteBackOptions::default()).unwrap();
+        assert_ne!(again.backup_path, report.backup_path);
+        assert!(Path::new(&report.backup_path).exists());
+    }
+
+    #[test]
+    fn test_write_back_refuses_while_vdj_is_saving() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("database.xml");
+        std::fs::write(&db_path, DATABASE).unwrap();
+
+        let error = write_back(&db_path, &[rating_update()], &VdjWriteBackOptions::default()).unwrap_err();
+        assert_eq!(error.code(), "busy");
+        assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_back_aborts_when_database_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = quiet_database(tmp.path());
+        let saved_by_vdj = format!("{}<!-- saved -->\n", DATABASE);
+
+        let error = write_back_with(&db_path, &[rating_update()], &VdjWriteBackOptions::default(), || {
+            std::fs::write(&db_path, &saved_by_vdj).unwrap();
+        })
+        .unwrap_err();
+        assert_eq!(error.code(), "busy");
+        assert_eq!(std::fs::read_to_string(&db_path).unwrap(), saved_by_vdj);
+        assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 1);
+    }
+}
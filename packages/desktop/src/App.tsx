@@ -19,7 +19,10 @@ import { useLiveSession } from "./hooks/useLiveSession";
 import { useSidecar } from "./hooks/useSidecar";
 import { useSettings } from "./hooks/useSettings";
 import { useLayoutResizer } from "./hooks/useLayoutResizer";
-import { setSidecarUrl } from "./services/progressiveAnalysisService";
+import {
+  setAnalysisCpuPriority,
+  startAnalysisListener,
+} from "./services/progressiveAnalysisService";
 import { getLocalIp } from "./config";
 import "./App.css";
 
@@ -103,9 +106,21 @@ function App() {
     refreshTracks();
   }, [refreshTracks]);
 
+  // Store results from the Rust analysis queue as jobs complete
+  useEffect(() => {
+    if (!inTauri) return;
+    const unlisten = startAnalysisListener();
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [inTauri]);
+
   useEffect(() => {
-    setSidecarUrl(baseUrl);
-  }, [baseUrl]);
+    if (!inTauri) return;
+    setAnalysisCpuPriority(settings["analysis.cpuPriority"]).catch((e) =>
+      console.error("[ProgressiveAnalysis] Failed to set CPU priority:", e),
+    );
+  }, [inTauri, settings["analysis.cpuPriority"]]);
 
   // Sync theme to document element for global CSS variables
   useEffect(() => {
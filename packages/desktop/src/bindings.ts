@@ -0,0 +1,839 @@
+// @ts-nocheck
+// This file was generated by [tauri-specta](https://github.com/oscartbeaumont/tauri-specta). Do not edit this file manually.
+
+/** user-defined commands **/
+
+
+export const commands = {
+/**
+ * Import a VDJ library. Emits `vdj://import-progress` while parsing;
+ * `cancel_virtualdj_import` aborts it.
+ */
+async importVirtualdjLibrary(xmlPath: string) : Promise<VirtualDJTrack[]> {
+    return await TAURI_INVOKE("import_virtualdj_library", { xmlPath });
+},
+/**
+ * Abort an in-flight `import_virtualdj_library` parse
+ */
+async cancelVirtualdjImport() : Promise<void> {
+    await TAURI_INVOKE("cancel_virtualdj_import");
+},
+/**
+ * Import a Rekordbox library from its XML export (File > Export Collection in xml format)
+ */
+async importRekordboxLibrary(xmlPath: string) : Promise<ImportedLibrary> {
+    return await TAURI_INVOKE("import_rekordbox_library", { xmlPath });
+},
+/**
+ * Import a Traktor library from its collection.nml
+ */
+async importTraktorLibrary(nmlPath: string) : Promise<ImportedLibrary> {
+    return await TAURI_INVOKE("import_traktor_library", { nmlPath });
+},
+/**
+ * Import a Serato library. `serato_path` is the `_Serato_` folder (or its database V2 file);
+ * crates come back as playlists, with sub-crates nested under their parent.
+ */
+async importSeratoLibrary(seratoPath: string) : Promise<ImportedLibrary> {
+    return await TAURI_INVOKE("import_serato_library", { seratoPath });
+},
+/**
+ * Import an Engine DJ library. `db_path` may be m.db, its Database2 folder or the Engine Library folder.
+ * Unsupported schema versions come back as `invalid_format`.
+ */
+async importEngineDjLibrary(dbPath: string) : Promise<ImportedLibrary> {
+    return await TAURI_INVOKE("import_engine_dj_library", { dbPath });
+},
+/**
+ * Import a Mixxx library. `db_path` may be mixxxdb.sqlite or the Mixxx settings folder.
+ * Unsupported schema versions come back as `invalid_format`.
+ */
+async importMixxxLibrary(dbPath: string) : Promise<ImportedLibrary> {
+    return await TAURI_INVOKE("import_mixxx_library", { dbPath });
+},
+async readVirtualdjHistory(customPath: string | null) : Promise<HistoryTrack | null> {
+    return await TAURI_INVOKE("read_virtualdj_history", { customPath });
+},
+/**
+ * Read ALL entries from the VirtualDJ history file (not just the last one)
+ */
+async readVirtualdjHistoryFull(customPath: string | null, maxEntries: number | null) : Promise<HistoryTrack[]> {
+    return await TAURI_INVOKE("read_virtualdj_history_full", { customPath, maxEntries });
+},
+/**
+ * Read only the history entries appended since `cursor`.
+ * Without a cursor the whole current file is returned. When VDJ has rolled over to a
+ * new day's file, the rest of the old file is drained before moving to the new one.
+ */
+async readVirtualdjHistorySince(customPath: string | null, cursor: HistoryCursor | null) : Promise<HistoryDelta> {
+    return await TAURI_INVOKE("read_virtualdj_history_since", { customPath, cursor });
+},
+/**
+ * Rebuild past DJ sessions from every VDJ history file (including year/month
+ * subfolders): entries are merged across midnight and split wherever nothing was
+ * played for longer than the idle gap. Newest first. `history_dir` overrides auto-detection.
+ */
+async findHistorySessions(historyDir: string | null, options: SessionOptions | null) : Promise<SessionCandidate[]> {
+    return await TAURI_INVOKE("find_history_sessions", { historyDir, options });
+},
+/**
+ * Page through history across every VDJ history file: entries played between
+ * `from` and `to`, optionally filtered by artist/title, with counts over all matches.
+ * Only files whose play-time range overlaps the query are read.
+ */
+async queryVirtualdjHistory(historyDir: string | null, query: HistoryQuery) : Promise<HistoryPage> {
+    return await TAURI_INVOKE("query_virtualdj_history", { historyDir, query });
+},
+/**
+ * Start (or restart) the Rust-side history follower for the configured
+ * now-playing source. New entries are pushed as `vdj://track-changed` events
+ * whichever app is playing; the current last entry is returned so the caller
+ * can seed its state without waiting. `custom_path` pins the VDJ history file.
+ */
+async startHistoryWatcher(customPath: string | null) : Promise<HistoryTrack | null> {
+    return await TAURI_INVOKE("start_history_watcher", { customPath });
+},
+/**
+ * Stop the history follower. Safe to call when it is not running.
+ */
+async stopHistoryWatcher() : Promise<null> {
+    return await TAURI_INVOKE("stop_history_watcher");
+},
+/**
+ * Choose which app the history watcher follows. Takes effect on the next
+ * `start_history_watcher`.
+ */
+async setNowPlayingSource(config: NowPlayingConfig) : Promise<null> {
+    return await TAURI_INVOKE("set_now_playing_source", { config });
+},
+/**
+ * Lookup track metadata from VDJ database.xml by file path
+ * Used to get BPM/key for tracks not imported into Pika! library.
+ * Falls back to artist + title when the path is unknown (e.g. the file was moved).
+ */
+async lookupVdjTrackMetadata(filePath: string, artist: string | null, title: string | null) : Promise<VdjTrackMetadata | null> {
+    return await TAURI_INVOKE("lookup_vdj_track_metadata", { filePath, artist, title });
+},
+/**
+ * Read BPM, key and descriptive tags embedded in the audio file itself.
+ * Fallback for tracks no DJ database knows about; needs no sidecar.
+ */
+async readAudioTags(filePath: string) : Promise<AudioTagMetadata> {
+    return await TAURI_INVOKE("read_audio_tags", { filePath });
+},
+/**
+ * Analyze BPM, key, energy and the fingerprint metrics natively, without the sidecar.
+ * Returns the same fields as the sidecar's `/analyze`; failures are reported in `error`.
+ */
+async analyzeTrack(filePath: string) : Promise<AnalysisResult> {
+    return await TAURI_INVOKE("analyze_track", { filePath });
+},
+/**
+ * Write Pika tags, notes and ratings into VirtualDJ's database.xml.
+ * A timestamped backup is taken first; unknown elements and attributes are preserved.
+ */
+async writePikaTagsToVirtualdj(updates: VdjTagUpdate[], options: VdjWriteBackOptions | null, xmlPath: string | null) : Promise<VdjWriteBackReport> {
+    return await TAURI_INVOKE("write_pika_tags_to_virtualdj", { updates, options, xmlPath });
+},
+/**
+ * Write BPM, key, energy and Pika tags into the audio files' own tags
+ * (ID3v2, FLAC/Ogg Vorbis comments, MP4 atoms). With `dry_run` only the diff is
+ * returned; otherwise the previous values are journaled for `undo_audio_tag_write`.
+ */
+async writeAudioTags(updates: AudioTagUpdate[], options: AudioTagWriteOptions | null) : Promise<AudioTagWriteReport> {
+    return await TAURI_INVOKE("write_audio_tags", { updates, options });
+},
+/**
+ * Revert an audio tag write using the journal path from its report
+ */
+async undoAudioTagWrite(journalPath: string) : Promise<AudioTagWriteReport> {
+    return await TAURI_INVOKE("undo_audio_tag_write", { journalPath });
+},
+/**
+ * Current state of the analysis sidecar (updates arrive as `sidecar://status` events)
+ */
+async getSidecarStatus() : Promise<SidecarStatus> {
+    return await TAURI_INVOKE("get_sidecar_status");
+},
+/**
+ * Kill and respawn the analysis sidecar, resetting the restart backoff
+ */
+async restartSidecar() : Promise<SidecarStatus> {
+    return await TAURI_INVOKE("restart_sidecar");
+},
+/**
+ * Queue a track for analysis by the sidecar. A track that is already queued
+ * keeps its job, moved up if the new priority is higher.
+ */
+async enqueueAnalysisJob(trackId: number, filePath: string, priority: JobPriority | null) : Promise<AnalysisJob> {
+    return await TAURI_INVOKE("enqueue_analysis_job", { trackId, filePath, priority });
+},
+/**
+ * Cancel a pending or running analysis job; false if it already finished
+ */
+async cancelAnalysisJob(jobId: number) : Promise<boolean> {
+    return await TAURI_INVOKE("cancel_analysis_job", { jobId });
+},
+/**
+ * Cancel every queued and running analysis job
+ */
+async clearAnalysisQueue() : Promise<QueueSnapshot> {
+    return await TAURI_INVOKE("clear_analysis_queue");
+},
+/**
+ * Stop starting new analysis jobs (running ones finish) or resume
+ */
+async setAnalysisPaused(paused: boolean) : Promise<QueueSnapshot> {
+    return await TAURI_INVOKE("set_analysis_paused", { paused });
+},
+/**
+ * Apply the `analysis.cpuPriority` setting to the queue's concurrency
+ */
+async setAnalysisCpuPriority(priority: CpuPriority) : Promise<QueueSnapshot> {
+    return await TAURI_INVOKE("set_analysis_cpu_priority", { priority });
+},
+/**
+ * Pending and running analysis jobs
+ */
+async getAnalysisQueue() : Promise<QueueSnapshot> {
+    return await TAURI_INVOKE("get_analysis_queue");
+},
+/**
+ * Finished analysis jobs whose results haven't been acknowledged yet (e.g.
+ * ones that completed while the window was closed), oldest first
+ */
+async getAnalysisResults() : Promise<JobCompletion[]> {
+    return await TAURI_INVOKE("get_analysis_results");
+},
+/**
+ * Mark finished jobs' results as stored so they aren't delivered again.
+ * Returns how many were removed.
+ */
+async ackAnalysisResults(jobIds: number[]) : Promise<number> {
+    return await TAURI_INVOKE("ack_analysis_results", { jobIds });
+},
+/**
+ * Replace the user-configured extra database.xml paths (files or folders)
+ */
+async setVdjExtraDatabases(paths: string[]) : Promise<null> {
+    return await TAURI_INVOKE("set_vdj_extra_databases", { paths });
+},
+/**
+ * Rescan for VDJ databases (home, external drives, extras) and list each with its track count.
+ * Later lookups use the result of this scan.
+ */
+async listVdjDatabases() : Promise<VdjDatabaseInfo[]> {
+    return await TAURI_INVOKE("list_vdj_databases");
+},
+/**
+ * Set (or clear, with None) the VDJ home folder configured in Settings
+ */
+async setVdjHome(path: string | null) : Promise<null> {
+    return await TAURI_INVOKE("set_vdj_home", { path });
+},
+/**
+ * Every folder checked for VDJ's home, database and history, with why each was rejected.
+ * Shown in Settings when VirtualDJ can't be found.
+ */
+async getVdjLocations() : Promise<VdjLocations> {
+    return await TAURI_INVOKE("get_vdj_locations");
+},
+/**
+ * Get the local network IP address for LAN sharing
+ * Returns the first non-loopback IPv4 address found
+ */
+async getLocalIp() : Promise<string | null> {
+    return await TAURI_INVOKE("get_local_ip");
+}
+}
+
+/** user-defined events **/
+
+
+export const events = __makeEvents__<{
+analysisComplete: JobCompletion,
+analysisProgress: JobProgress,
+sidecarStatus: SidecarStatus,
+vdjImportProgress: ImportProgress,
+vdjTrackChanged: HistoryTrack
+}>({
+analysisComplete: "analysis://complete",
+analysisProgress: "analysis://progress",
+sidecarStatus: "sidecar://status",
+vdjImportProgress: "vdj://import-progress",
+vdjTrackChanged: "vdj://track-changed"
+})
+
+/** user-defined constants **/
+
+
+
+/** user-defined types **/
+
+export type AnalysisJob = { id: number; track_id: number; file_path: string; priority: JobPriority; 
+/**
+ * Failed connection attempts so far
+ */
+attempts?: number }
+/**
+ * Result of audio analysis - same fields as the sidecar's `AnalysisResult`
+ * (and `AnalysisResultSchema` in @pika/shared)
+ */
+export type AnalysisResult = { bpm: number | null; energy: number | null; key: string | null; danceability: number | null; brightness: number | null; acousticness: number | null; groove: number | null; error: string | null }
+/**
+ * Tags read from an audio file. Same shape as `VdjTrackMetadata` (bpm, key,
+ * volume) plus the descriptive fields a DJ database would otherwise provide.
+ */
+export type AudioTagMetadata = { bpm: number | null; 
+/**
+ * Initial key exactly as tagged (e.g. "Am", "8A", "1m")
+ */
+key: string | null; 
+/**
+ * Linear gain from the ReplayGain track gain, comparable to VDJ's volume
+ */
+volume: number | null; artist: string | null; title: string | null; album: string | null; genre: string | null; year: number | null; 
+/**
+ * Duration in seconds
+ */
+duration: number | null; comment: string | null; 
+/**
+ * Container the tags were read from (mp3, flac, m4a, ogg, wav, aiff)
+ */
+format: string }
+/**
+ * Pika data for a single file. `None` leaves the field untouched.
+ */
+export type AudioTagUpdate = { file_path: string; bpm: number | null; 
+/**
+ * Any notation `keys::parse` understands; written in `key_notation`
+ */
+key: string | null; energy: number | null; 
+/**
+ * An empty list removes the field
+ */
+tags: string[] | null }
+export type AudioTagWriteOptions = { key_notation: KeyNotation; 
+/**
+ * Report what would change without touching any file
+ */
+dry_run: boolean }
+export type AudioTagWriteReport = { dry_run: boolean; 
+/**
+ * Files with at least one changed field (for an undo: the fields restored)
+ */
+files: FileTagChanges[]; 
+/**
+ * Files (or, for an undo, fields) that were left alone, and why
+ */
+failed: TagWriteFailure[]; 
+/**
+ * Pass to `undo_audio_tag_write` to revert this write
+ */
+journal_path: string | null }
+/**
+ * Why a folder was considered
+ */
+export type CandidateOrigin = 
+/**
+ * Configured in Pika's Settings
+ */
+"setting" | 
+/**
+ * `PIKA_VDJ_HOME`
+ */
+"environment" | 
+/**
+ * Home-folder override found in a VDJ settings.xml
+ */
+"settings_xml" | 
+/**
+ * Default install location for this OS
+ */
+"standard" | 
+/**
+ * Inside a Wine prefix (Linux)
+ */
+"wine" | 
+/**
+ * `VirtualDJ` folder at the root of a drive
+ */
+"portable"
+/**
+ * Mirrors the `analysis.cpuPriority` setting
+ */
+export type CpuPriority = "low" | "normal" | "high"
+/**
+ * Where a database.xml was found
+ */
+export type DatabaseSource = 
+/**
+ * The VDJ home folder
+ */
+"home" | 
+/**
+ * Root of a mounted drive / volume
+ */
+"volume" | 
+/**
+ * Configured by the user
+ */
+"custom"
+export type FileTagChanges = { file_path: string; changes: TagChange[] }
+/**
+ * Body of the sidecar's /health endpoint
+ */
+export type HealthData = { status: string; version: string }
+/**
+ * Position in a VDJ history file, so reads only return entries appended since last time.
+ * Serializable so the frontend can persist it and resume after an app restart.
+ */
+export type HistoryCursor = { path: string; file_id: number | null; size: number; byte_offset: number }
+/**
+ * New history entries plus the cursor to pass on the next call
+ */
+export type HistoryDelta = { entries: HistoryTrack[]; cursor: HistoryCursor }
+/**
+ * One page of matching history entries plus counts over every match
+ */
+export type HistoryPage = { entries: HistoryTrack[]; 
+/**
+ * Matching entries across all pages
+ */
+total: number; offset: number; 
+/**
+ * Distinct artist + title pairs among the matches
+ */
+unique_tracks: number; unique_artists: number; 
+/**
+ * Play time of the first and last match
+ */
+first_played: number | null; last_played: number | null; 
+/**
+ * History files that overlapped the range and were read
+ */
+files_read: number }
+/**
+ * Entries without a play time only match when neither `from` nor `to` is set
+ */
+export type HistoryQuery = { 
+/**
+ * Unix timestamp, inclusive
+ */
+from: number | null; 
+/**
+ * Unix timestamp, inclusive
+ */
+to: number | null; 
+/**
+ * Case-insensitive substring of the artist
+ */
+artist: string | null; 
+/**
+ * Case-insensitive substring of the title
+ */
+title: string | null; 
+/**
+ * Matching entries to skip (entries are in play order)
+ */
+offset: number; 
+/**
+ * Page size
+ */
+limit: number }
+/**
+ * Read the VirtualDJ history file for the current day
+ */
+export type HistoryTrack = { artist: string; title: string; file_path: string; timestamp: number; remix: string | null; 
+/**
+ * Track length in seconds
+ */
+song_length: number | null; file_size: number | null; 
+/**
+ * Wall-clock play time as written by VDJ
+ */
+time: string | null; 
+/**
+ * #EXTVDJ tags without a dedicated field
+ */
+extras: Partial<{ [key in string]: string }> }
+/**
+ * Progress of a database parse, reported to the UI during import
+ */
+export type ImportProgress = { bytes_read: number; total_bytes: number; songs: number }
+/**
+ * A Rekordbox / Traktor / Serato import: tracks in the same shape as a VDJ import, plus the playlist tree
+ */
+export type ImportedLibrary = { tracks: VirtualDJTrack[]; 
+/**
+ * Top-level folders and playlists, to become Pika saved sets
+ */
+playlists: LibraryPlaylist[] }
+export type JobCompletion = { job: AnalysisJob; state: JobState; 
+/**
+ * The sidecar's JSON response, passed through as-is
+ */
+result: AnalysisResult | null; error: string | null }
+/**
+ * Declaration order is dispatch order
+ */
+export type JobPriority = "now_playing" | "next_up" | "background"
+export type JobProgress = { job: AnalysisJob; state: JobState; pending: number; running: number }
+export type JobState = "queued" | "running" | 
+/**
+ * Sidecar unreachable; back in the queue
+ */
+"retrying" | "cancelled" | 
+/**
+ * The sidecar answered; the result may still carry an analysis error
+ */
+"completed" | 
+/**
+ * Gave up after MAX_ATTEMPTS or the sidecar answered with an HTTP error
+ */
+"failed"
+/**
+ * How a key is written back into files
+ */
+export type KeyNotation = 
+/**
+ * "Am", "F#"
+ */
+"standard" | 
+/**
+ * "8A", "2B"
+ */
+"camelot" | 
+/**
+ * "1m", "7d"
+ */
+"open_key"
+/**
+ * A folder or playlist from another DJ app's library
+ */
+export type LibraryPlaylist = { name: string; is_folder: boolean; 
+/**
+ * Sub-folders and playlists
+ */
+children: LibraryPlaylist[]; 
+/**
+ * File paths of the playlist's tracks, in order.
+ * Usually empty for folders, but a Serato parent crate has tracks of its own.
+ */
+track_paths: string[] }
+/**
+ * Which source to follow, as chosen in Settings
+ */
+export type NowPlayingConfig = 
+/**
+ * VDJ's History folder (or the history file passed to `start_history_watcher`)
+ */
+{ kind: "virtual_dj" } | 
+/**
+ * The set log in Mixxx's database (default location when `db_path` is None)
+ */
+{ kind: "mixxx"; db_path: string | null } | 
+/**
+ * history_*.nml archives in Traktor's History folder
+ */
+{ kind: "traktor"; history_dir: string | null } | 
+/**
+ * Any M3U or "Artist - Title" text file, appended to or rewritten in place
+ */
+{ kind: "text_file"; path: string }
+export type PikaError = 
+/**
+ * A file or folder we need does not exist (or was never configured)
+ */
+{ code: "not_found"; message: string; path: string | null } | 
+/**
+ * The file exists but the OS refused access
+ */
+{ code: "permission_denied"; message: string; path: string } | 
+/**
+ * Any other I/O failure
+ */
+{ code: "io"; message: string; path: string } | 
+/**
+ * Malformed XML. `line`/`column` are 1-based and filled in when the file can be re-read.
+ */
+{ code: "xml_parse"; message: string; path: string | null; byte_offset: number | null; line: number | null; column: number | null } | 
+/**
+ * Well-formed, but not the kind of file we expected
+ */
+{ code: "invalid_format"; message: string; path: string | null } | 
+/**
+ * The request itself can't be carried out (e.g. conflicting options)
+ */
+{ code: "invalid_input"; message: string } | 
+/**
+ * Another app is writing the file right now; retrying later may work
+ */
+{ code: "busy"; message: string; path: string } | 
+/**
+ * The user cancelled the operation
+ */
+{ code: "cancelled"; message: string } | 
+/**
+ * A background task or lock failed; not the user's fault
+ */
+{ code: "internal"; message: string }
+export type QueueSnapshot = { paused: boolean; cpu_priority: CpuPriority; concurrency: number; running: AnalysisJob[]; pending: AnalysisJob[] }
+/**
+ * A stretch of continuous playing, ready to be imported into the Logbook
+ */
+export type SessionCandidate = { 
+/**
+ * Unix timestamp of the first track
+ */
+started_at: number; 
+/**
+ * Unix timestamp the last track ended (or started, if its length is unknown)
+ */
+ended_at: number; 
+/**
+ * History files the tracks came from, in order
+ */
+source_files: string[]; 
+/**
+ * Tracks in play order
+ */
+tracks: HistoryTrack[] }
+export type SessionOptions = { 
+/**
+ * Start a new session after this long without a new track
+ */
+idle_gap_minutes: number; 
+/**
+ * Drop sessions with fewer tracks (pre-listening, quick tests)
+ */
+min_tracks: number; 
+/**
+ * Only sessions ending at or after this Unix timestamp
+ */
+since: number | null; 
+/**
+ * Only sessions starting at or before this Unix timestamp
+ */
+until: number | null }
+export type SidecarState = 
+/**
+ * Not started, or stopped on exit
+ */
+"idle" | 
+/**
+ * Spawned, waiting for SIDECAR_READY
+ */
+"starting" | 
+/**
+ * Listening and answering /health
+ */
+"ready" | 
+/**
+ * Exited or unhealthy; a restart is scheduled in `retry_in_ms`
+ */
+"error"
+export type SidecarStatus = { state: SidecarState; 
+/**
+ * http://127.0.0.1:<port> once ready
+ */
+base_url: string | null; pid: number | null; health: HealthData | null; 
+/**
+ * Restarts since the app started (or since the last manual restart)
+ */
+restarts: number; last_error: string | null; retry_in_ms: number | null }
+/**
+ * One field of one file, before and after
+ */
+export type TagChange = { field: TagField; 
+/**
+ * Frame, comment or atom name in this file's format (TBPM, INITIALKEY, tmpo, ...)
+ */
+native: string; before: string | null; after: string | null }
+/**
+ * Pika data that can be written into a file
+ */
+export type TagField = "bpm" | "key" | "energy" | "tags"
+export type TagWriteFailure = { file_path: string; error: string }
+/**
+ * A database.xml found on this machine, for the Settings screen
+ */
+export type VdjDatabaseInfo = { path: string; source: DatabaseSource; track_count: number | null; 
+/**
+ * Why the database could not be read, if it couldn't
+ */
+error: string | null }
+/**
+ * VDJ fields Pika data can be written into
+ */
+export type VdjField = 
+/**
+ * The `<Comment>` element of a song
+ */
+"comment" | 
+/**
+ * `Tags@User1`
+ */
+"user1" | 
+/**
+ * `Tags@User2`
+ */
+"user2"
+/**
+ * One folder that was checked, and what was found there
+ */
+export type VdjHomeCandidate = { path: string; origin: CandidateOrigin; has_database: boolean; has_history: boolean; 
+/**
+ * Why the folder was not used (None = usable)
+ */
+rejected: string | null }
+/**
+ * Result of resolving VDJ's locations, with a full diagnostic trail
+ */
+export type VdjLocations = { 
+/**
+ * First usable home folder
+ */
+home: string | null; 
+/**
+ * First database.xml found, in candidate order
+ */
+database: string | null; 
+/**
+ * First History folder found, in candidate order
+ */
+history_dir: string | null; candidates: VdjHomeCandidate[] }
+/**
+ * Pika data for a single track. `None` leaves the VDJ field untouched.
+ */
+export type VdjTagUpdate = { file_path: string; tags: string[] | null; notes: string | null; 
+/**
+ * 1-5 stars, written to `Tags@Stars`; 0 removes the rating
+ */
+rating: number | null }
+/**
+ * Metadata returned from VDJ database lookup (for ghost tracks)
+ */
+export type VdjTrackMetadata = { bpm: number | null; key: string | null; volume: number | null; 
+/**
+ * database.xml the track was found in
+ */
+database: string }
+/**
+ * Which VDJ field receives which piece of Pika data
+ */
+export type VdjWriteBackOptions = { tags_field: VdjField; notes_field: VdjField }
+export type VdjWriteBackReport = { 
+/**
+ * Songs that were modified
+ */
+updated: number; 
+/**
+ * Requested file paths that are not in database.xml
+ */
+not_found: string[]; backup_path: string }
+/**
+ * Structured form of a VDJ `<Poi>` element
+ */
+export type VirtualDJPoint = { 
+/**
+ * "cue", "loop", "beatgrid", "automix", "remix", ...
+ */
+kind: string; name: string | null; 
+/**
+ * Position in seconds
+ */
+position: number | null; 
+/**
+ * Hotcue / slot number
+ */
+number: number | null; 
+/**
+ * Loop length (loops only)
+ */
+size: number | null; 
+/**
+ * Automix point name, e.g. "realStart" / "realEnd"
+ */
+point: string | null; color: string | null; 
+/**
+ * Actual BPM at a beatgrid anchor (converted from VDJ's beat period)
+ */
+bpm: number | null }
+export type VirtualDJTrack = { file_path: string; artist: string | null; title: string | null; bpm: string | null; key: string | null; duration: number | null; album: string | null; genre: string | null; year: number | null; track_number: string | null; 
+/**
+ * VDJ tag flag bitmask
+ */
+flag: number | null; play_count: number | null; 
+/**
+ * Unix timestamp of when VDJ first saw the file
+ */
+first_seen: number | null; 
+/**
+ * Star rating, 1-5. Empty for VirtualDJ and Serato imports
+ */
+rating: number | null; 
+/**
+ * Cue points, loops, beatgrid anchors and automix markers
+ */
+pois: VirtualDJPoint[] }
+
+/** tauri-specta globals **/
+
+import {
+	invoke as TAURI_INVOKE,
+	Channel as TAURI_CHANNEL,
+} from "@tauri-apps/api/core";
+import * as TAURI_API_EVENT from "@tauri-apps/api/event";
+import { type WebviewWindow as __WebviewWindow__ } from "@tauri-apps/api/webviewWindow";
+
+type __EventObj__<T> = {
+	listen: (
+		cb: TAURI_API_EVENT.EventCallback<T>,
+	) => ReturnType<typeof TAURI_API_EVENT.listen<T>>;
+	once: (
+		cb: TAURI_API_EVENT.EventCallback<T>,
+	) => ReturnType<typeof TAURI_API_EVENT.once<T>>;
+	emit: null extends T
+		? (payload?: T) => ReturnType<typeof TAURI_API_EVENT.emit>
+		: (payload: T) => ReturnType<typeof TAURI_API_EVENT.emit>;
+};
+
+export type Result<T, E> =
+	| { status: "ok"; data: T }
+	| { status: "error"; error: E };
+
+function __makeEvents__<T extends Record<string, any>>(
+	mappings: Record<keyof T, string>,
+) {
+	return new Proxy(
+		{} as unknown as {
+			[K in keyof T]: __EventObj__<T[K]> & {
+				(handle: __WebviewWindow__): __EventObj__<T[K]>;
+			};
+		},
+		{
+			get: (_, event) => {
+				const name = mappings[event as keyof T];
+
+				return new Proxy((() => {}) as any, {
+					apply: (_, __, [window]: [__WebviewWindow__]) => ({
+						listen: (arg: any) => window.listen(name, arg),
+						once: (arg: any) => window.once(name, arg),
+						emit: (arg: any) => window.emit(name, arg),
+					}),
+					get: (_, command: keyof __EventObj__<any>) => {
+						switch (command) {
+							case "listen":
+								return (arg: any) => TAURI_API_EVENT.listen(name, arg);
+							case "once":
+								return (arg: any) => TAURI_API_EVENT.once(name, arg);
+							case "emit":
+								return (arg: any) => TAURI_API_EVENT.emit(name, arg);
+						}
+					},
+				});
+			},
+		},
+	);
+}
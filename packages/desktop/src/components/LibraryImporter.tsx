@@ -1,7 +1,8 @@
-import { invoke } from "@tauri-apps/api/core";
 import { open } from "@tauri-apps/plugin-dialog";
 import { useState } from "react";
+import { commands } from "../bindings";
 import { trackRepository, type VirtualDJTrack } from "../db/repositories/trackRepository";
+import { formatPikaError } from "../utils/pikaError";
 
 interface Props {
   onImportComplete?: () => void;
@@ -34,14 +35,12 @@ export function LibraryImporter({ onImportComplete }: Props) {
         return;
       }
 
-      const result = await invoke<VirtualDJTrack[]>("import_virtualdj_library", {
-        xmlPath: selected,
-      });
+      const result = await commands.importVirtualdjLibrary(selected);
 
       setParsedTracks(result);
     } catch (err: any) {
       console.error("Import error:", err);
-      setError(String(err));
+      setError(formatPikaError(err));
     } finally {
       setLoading(false);
     }
@@ -110,6 +110,13 @@ export const sessionRepository = {
     await db.update(sessions).set({ endedAt: now() }).where(eq(sessions.id, sessionId));
   },
 
+  /**
+   * Backdate a session to when it was actually played (sessions rebuilt from VDJ history)
+   */
+  async setSessionTimes(sessionId: number, startedAt: number, endedAt: number): Promise<void> {
+    await db.update(sessions).set({ startedAt, endedAt }).where(eq(sessions.id, sessionId));
+  },
+
   /**
    * Set the cloud session ID for a session (for recap link)
    */
@@ -225,6 +225,7 @@ describe("trackRepository", () => {
         bpm: "120",
         key: "Am",
         duration: 180,
+        pois: [],
       }));
 
       await trackRepository.addTracks(tracks);
@@ -247,6 +248,7 @@ describe("trackRepository", () => {
           artist: "Artist",
           title: "Song",
           bpm: "128.5",
+          pois: [],
         },
       ];
 
@@ -263,6 +265,7 @@ describe("trackRepository", () => {
           artist: "Artist",
           title: "Song",
           bpm: "invalid",
+          pois: [],
         },
       ];
 
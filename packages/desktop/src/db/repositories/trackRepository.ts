@@ -1,5 +1,6 @@
 import { type AnalysisResult, logger } from "@pika/shared";
 import { eq, type InferInsertModel, sql } from "drizzle-orm";
+import type { VirtualDJTrack } from "../../bindings";
 import { db, getSqlite } from "../index";
 import { tracks } from "../schema";
 
@@ -11,15 +12,8 @@ export const CURRENT_ANALYSIS_VERSION = 1;
 // Type-safe insert model from schema (exclude ID for auto-increment)
 type NewTrack = Omit<InferInsertModel<typeof tracks>, "id">;
 
-// Helper type matching the Rust output
-export interface VirtualDJTrack {
-  file_path: string;
-  artist?: string;
-  title?: string;
-  bpm?: string;
-  key?: string;
-  duration?: number;
-}
+// Library import payloads, generated from the Rust structs
+export type { VirtualDJPoint, VirtualDJTrack } from "../../bindings";
 
 // Re-export AnalysisResult for backwards compatibility
 export type { AnalysisResult } from "@pika/shared";
@@ -1,5 +1,4 @@
-import { getTrackKey, MESSAGE_TYPES, parseWebSocketMessage } from "@pika/shared";
-import { invoke } from "@tauri-apps/api/core";
+import { MESSAGE_TYPES, parseWebSocketMessage } from "@pika/shared";
 import { useCallback, useEffect, useRef } from "react";
 import ReconnectingWebSocket from "reconnecting-websocket";
 import { toast } from "sonner";
@@ -7,6 +6,7 @@ import { sessionRepository } from "../db/repositories/sessionRepository";
 import { settingsRepository } from "../db/repositories/settingsRepository";
 import { trackRepository } from "../db/repositories/trackRepository";
 import { enqueueForAnalysis } from "../services/progressiveAnalysisService";
+import { type DbTrackInfo, findOrCreateTrack } from "../services/trackService";
 import { type NowPlayingTrack, toTrackInfo, virtualDjWatcher } from "../services/virtualDjWatcher";
 import { logger } from "../utils/logger";
 import { getAuthToken, getConfiguredUrls, getDjName } from "./useDjSettings";
@@ -36,7 +36,6 @@ import {
 // Import constants
 import {
   CONNECTION_TIMEOUT_MS,
-  GHOST_FILE_PREFIX,
   LIKE_STORAGE_DEBOUNCE_MS,
   MAX_ANNOUNCEMENT_DURATION_SECONDS,
   MAX_ANNOUNCEMENT_LENGTH,
@@ -276,140 +275,6 @@ function createRouterContext(sessionId: string, endSet: () => Promise<void>): Me
   };
 }
 
-/**
- * Track info from database with fingerprint data
- */
-interface DbTrackInfo {
-  id: number;
-  bpm: number | null;
-  key: string | null;
-  energy: number | null;
-  danceability: number | null;
-  brightness: number | null;
-  acousticness: number | null;
-  groove: number | null;
-}
-
-/**
- * VDJ track metadata from Rust lookup
- */
-interface VdjTrackMetadata {
-  bpm: number | null;
-  key: string | null;
-  volume: number | null;
-}
-
-/**
- * Find or create a track in the database by artist/title
- * Returns the track with fingerprint data for broadcasting
- *
- * Uses O(log n) indexed lookup via track_key
- */
-async function findOrCreateTrack(
-  artist: string,
-  title: string,
-  filePath?: string,
-): Promise<DbTrackInfo> {
-  const trackKey = getTrackKey(artist, title);
-
-  // O(log n) indexed lookup - no table scan!
-  const existing = await trackRepository.findByTrackKey(trackKey);
-
-  if (existing) {
-    // Self-healing: If BPM is missing, try to fetch it from VDJ
-    if (!existing.bpm && filePath && !filePath.startsWith(GHOST_FILE_PREFIX)) {
-      try {
-        const vdjMeta = await invoke<VdjTrackMetadata | null>("lookup_vdj_track_metadata", {
-          filePath,
-        });
-        if (vdjMeta?.bpm) {
-          logger.debug("Live", "Healing track metadata", {
-            id: existing.id,
-            bpm: vdjMeta.bpm,
-            key: vdjMeta.key,
-          });
-
-          // Update DB (fire and forget await, but use the value for return)
-          const bpmToSave =
-            typeof vdjMeta.bpm === "string"
-              ? Number.parseFloat(vdjMeta.bpm)
-              : (vdjMeta.bpm as number | null);
-
-          await trackRepository.insertTrack({
-            filePath,
-            artist,
-            title,
-            bpm: bpmToSave,
-            key: vdjMeta.key || existing.key,
-          });
-
-          return {
-            ...existing,
-            bpm: bpmToSave,
-            key: vdjMeta.key || existing.key,
-          };
-        }
-      } catch (error) {
-        logger.warn("Live", "Failed to heal track metadata", error);
-      }
-    }
-
-    return {
-      id: existing.id,
-      bpm: existing.bpm,
-      key: existing.key,
-      energy: existing.energy,
-      danceability: existing.danceability,
-      brightness: existing.brightness,
-      acousticness: existing.acousticness,
-      groove: existing.groove,
-    };
-  }
-
-  // New track - try VDJ lookup for BPM/key (lazy extraction)
-  let vdjBpm: number | null = null;
-  let vdjKey: string | null = null;
-
-  if (filePath && !filePath.startsWith(GHOST_FILE_PREFIX)) {
-    try {
-      const vdjMeta = await invoke<VdjTrackMetadata | null>("lookup_vdj_track_metadata", {
-        filePath,
-      });
-      if (vdjMeta) {
-        vdjBpm = vdjMeta.bpm;
-        vdjKey = vdjMeta.key;
-        logger.debug("Live", "Got VDJ metadata", { bpm: vdjBpm, key: vdjKey });
-      }
-    } catch (error) {
-      logger.warn("Live", "VDJ lookup failed", error);
-    }
-  }
-
-  // Insert new track
-  const bpmToSave =
-    typeof vdjBpm === "string" ? Number.parseFloat(vdjBpm) : (vdjBpm as number | null);
-
-  logger.debug("Live", "Creating track", { artist, title, bpm: bpmToSave });
-  const newId = await trackRepository.insertTrack({
-    filePath: filePath || `${GHOST_FILE_PREFIX}${artist}/${title}`,
-    artist,
-    title,
-    bpm: bpmToSave,
-    key: vdjKey,
-  });
-
-  return {
-    id: newId,
-    bpm: bpmToSave,
-    key: vdjKey,
-    energy: null,
-    danceability: null,
-    brightness: null,
-    acousticness: null,
-    groove: null,
-  };
-}
-
 /**
  * Record a track play to the database
  * Returns the DbTrackInfo with fingerprint data, or null if deduped/failed
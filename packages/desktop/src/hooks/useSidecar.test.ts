@@ -7,7 +7,7 @@
  *
  * PURPOSE:
  * Tests the Python sidecar process lifecycle management.
- * Uses mocks since we can't actually spawn processes in tests.
+ * Spawning and supervision live in src-tauri/src/sidecar.rs; these document the protocol.
  *
  * SAFETY CONSTRAINTS:
  * - Every test documents production behavior
@@ -15,7 +15,7 @@
  * - Error handling verified
  */
 
-import { describe, it, expect, beforeEach, vi } from "vitest";
+import { describe, it, expect } from "vitest";
 
 // ============================================================================
 // MOCKS
@@ -43,7 +43,7 @@ describe("Sidecar Helper Functions", () => {
      * The sidecar should only spawn in Tauri, not in browser preview mode.
      * This prevents errors when developing in the browser.
      *
-     * PRODUCTION LOCATION: useSidecar.ts line 28-30
+     * PRODUCTION LOCATION: useSidecar.ts isTauri
      */
     it("returns true when __TAURI_INTERNALS__ exists", () => {
       mockTauriWindow();
@@ -57,40 +57,6 @@ describe("Sidecar Helper Functions", () => {
       expect(isTauri).toBe(false);
     });
   });
-
-  describe("getRandomPort", () => {
-    /**
-     * TEST: Port generation is in ephemeral range
-     *
-     * RATIONALE:
-     * Using ephemeral ports (49152-65535) reduces collision risk with
-     * system services. Critical for multi-instance scenarios.
-     *
-     * PRODUCTION LOCATION: useSidecar.ts line 35-37
-     */
-    it("generates port in ephemeral range (49152-65535)", () => {
-      const getRandomPort = () => Math.floor(Math.random() * (65535 - 49152 + 1)) + 49152;
-
-      // Test 100 ports to verify range
-      for (let i = 0; i < 100; i++) {
-        const port = getRandomPort();
-        expect(port).toBeGreaterThanOrEqual(49152);
-        expect(port).toBeLessThanOrEqual(65535);
-      }
-    });
-
-    it("generates different ports on each call", () => {
-      const getRandomPort = () => Math.floor(Math.random() * (65535 - 49152 + 1)) + 49152;
-
-      const ports = new Set<number>();
-      for (let i = 0; i < 50; i++) {
-        ports.add(getRandomPort());
-      }
-
-      // Should have multiple unique ports (statistically near impossible to get <10)
-      expect(ports.size).toBeGreaterThan(10);
-    });
-  });
 });
 
 describe("Sidecar Status State Machine", () => {
@@ -135,42 +101,6 @@ describe("Sidecar Status State Machine", () => {
   });
 });
 
-describe("Sidecar Process Key", () => {
-  /**
-   * TEST: Global process key prevents duplicate sidecars
-   *
-   * RATIONALE:
-   * Without global tracking, HMR could spawn multiple sidecars.
-   * The global key ensures zero-tolerance: only one sidecar at a time.
-   *
-   * PRODUCTION LOCATION: useSidecar.ts line 40
-   */
-  const SIDE_PROCESS_KEY = "__PIKA_SIDECAR_CHILD__";
-
-  beforeEach(() => {
-    delete (globalThis as any)[SIDE_PROCESS_KEY];
-  });
-
-  it("uses unique global key for process tracking", () => {
-    expect(SIDE_PROCESS_KEY).toBe("__PIKA_SIDECAR_CHILD__");
-  });
-
-  it("can store and retrieve process reference", () => {
-    const mockChild = { pid: 12345, kill: vi.fn() };
-    (globalThis as any)[SIDE_PROCESS_KEY] = mockChild;
-
-    expect((globalThis as any)[SIDE_PROCESS_KEY]).toBe(mockChild);
-  });
-
-  it("can clear process reference", () => {
-    const mockChild = { pid: 12345, kill: vi.fn() };
-    (globalThis as any)[SIDE_PROCESS_KEY] = mockChild;
-    (globalThis as any)[SIDE_PROCESS_KEY] = undefined;
-
-    expect((globalThis as any)[SIDE_PROCESS_KEY]).toBeUndefined();
-  });
-});
-
 describe("Sidecar Ready Detection", () => {
   /**
    * TEST: SIDECAR_READY message parsing
@@ -179,7 +109,7 @@ describe("Sidecar Ready Detection", () => {
    * The Python sidecar outputs "SIDECAR_READY port=XXXXX" when ready.
    * Parsing this correctly is critical for establishing the API URL.
    *
-   * PRODUCTION LOCATION: useSidecar.ts lines 99-109
+   * PRODUCTION LOCATION: src-tauri/src/sidecar.rs parse_ready
    */
   const parseReadyMessage = (line: string): string | null => {
     if (line.includes("SIDECAR_READY")) {
@@ -221,10 +151,10 @@ describe("Sidecar Error Detection", () => {
    * TEST: Address collision detection
    *
    * RATIONALE:
-   * When a port is already in use, the sidecar logs "address already in use".
-   * We detect this to trigger a retry with a new random port.
+   * When a port is already in use, the sidecar logs "address already in use"
+   * and exits; the Rust supervisor restarts it on a newly checked free port.
    *
-   * PRODUCTION LOCATION: useSidecar.ts lines 115-119
+   * PRODUCTION LOCATION: src-tauri/src/sidecar.rs run_once
    */
   const detectAddressInUse = (line: string): boolean => {
     return line.includes("address already in use");
@@ -254,7 +184,7 @@ describe("Health Check API", () => {
    * The health check verifies sidecar is responsive and returns version.
    * Used to populate healthData in the hook state.
    *
-   * PRODUCTION LOCATION: useSidecar.ts lines 50-60
+   * PRODUCTION LOCATION: src-tauri/src/sidecar.rs check_health
    */
   interface HealthData {
     status: string;
@@ -1,11 +1,13 @@
 /**
  * useSidecar Hook
- * Manages the Python sidecar process lifecycle.
- * Spawns the sidecar, monitors its status, and provides the base URL for API calls.
+ * Follows the Python sidecar, which is owned by the Rust side: it picks a free
+ * port, spawns the process, health-checks it and restarts it with backoff.
+ * This hook only mirrors the supervisor's status and provides the base URL for API calls.
  */
 
-import { type Child, Command } from "@tauri-apps/plugin-shell";
-import { useCallback, useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { useCallback, useEffect, useState } from "react";
 
 export type SidecarStatus = "idle" | "starting" | "ready" | "error" | "browser";
 
@@ -22,6 +24,19 @@ export interface UseSidecarResult {
   restart: () => Promise<void>;
 }
 
+/** Payload of `get_sidecar_status` and `sidecar://status` events */
+interface SupervisorStatus {
+  state: "idle" | "starting" | "ready" | "error";
+  base_url: string | null;
+  pid: number | null;
+  health: HealthData | null;
+  restarts: number;
+  last_error: string | null;
+  retry_in_ms: number | null;
+}
+
+const SIDECAR_STATUS_EVENT = "sidecar://status";
+
 /**
  * Check if we're running inside Tauri
  */
@@ -29,133 +44,50 @@ function isTauri(): boolean {
   return typeof window !== "undefined" && "__TAURI_INTERNALS__" in window;
 }
 
-/**
- * Generate a random port in the ephemeral range (49152-65535)
- */
-function getRandomPort(): number {
-  return Math.floor(Math.random() * (65535 - 49152 + 1)) + 49152;
-}
-
-// Global key for sidecar child
-const SIDE_PROCESS_KEY = "__PIKA_SIDECAR_CHILD__";
-
 export function useSidecar(): UseSidecarResult {
   const [status, setStatus] = useState<SidecarStatus>("idle");
   const [baseUrl, setBaseUrl] = useState<string | null>(null);
   const [healthData, setHealthData] = useState<HealthData | null>(null);
   const [error, setError] = useState<string | null>(null);
 
-  const isSpawningRef = useRef(false);
+  const apply = useCallback((supervisor: SupervisorStatus) => {
+    setStatus(supervisor.state);
+    setBaseUrl(supervisor.state === "ready" ? supervisor.base_url : null);
+    setHealthData(supervisor.health);
+    setError(supervisor.last_error);
+  }, []);
 
-  const fetchHealth = useCallback(async (url: string) => {
+  const restart = useCallback(async () => {
+    if (!isTauri()) return;
     try {
-      const response = await fetch(`${url}/health`);
-      if (response.ok) {
-        const data = (await response.json()) as HealthData;
-        setHealthData(data);
-      }
+      apply(await invoke<SupervisorStatus>("restart_sidecar"));
     } catch (err) {
-      console.error("Failed to fetch health:", err);
-    }
-  }, []);
-
-  // Robust cleanup function
-  const killSidecar = useCallback(async () => {
-    const child = (globalThis as Record<string, unknown>)[SIDE_PROCESS_KEY] as Child | undefined;
-    if (child) {
-      try {
-        await child.kill();
-        console.log("[Sidecar] Process killed successfully");
-      } catch (e) {
-        console.warn("[Sidecar] Kill failed (process may already be gone):", e);
-      } finally {
-        (globalThis as Record<string, unknown>)[SIDE_PROCESS_KEY] = undefined;
-      }
+      setError(err instanceof Error ? err.message : String(err));
+      setStatus("error");
     }
-  }, []);
+  }, [apply]);
 
-  const spawnSidecar = useCallback(async () => {
+  useEffect(() => {
     if (!isTauri()) {
       setStatus("browser");
       return;
     }
 
-    if (isSpawningRef.current) return;
-    isSpawningRef.current = true;
+    let disposed = false;
+    const unlisten = listen<SupervisorStatus>(SIDECAR_STATUS_EVENT, (event) => {
+      if (!disposed) apply(event.payload);
+    });
+    invoke<SupervisorStatus>("get_sidecar_status")
+      .then((supervisor) => {
+        if (!disposed) apply(supervisor);
+      })
+      .catch((err) => console.error("[Sidecar] Failed to get status:", err));
 
-    // Zero-tolerance cleanup: always kill previous global child if exists
-    await killSidecar();
-
-    setStatus("starting");
-    setBaseUrl(null);
-    setHealthData(null);
-    setError(null);
-
-    const port = getRandomPort();
-
-    try {
-      const command = Command.sidecar("binaries/api", ["--port", port.toString()]);
-
-      command.stdout.on("data", (line: string) => {
-        if (line.includes("SIDECAR_READY")) {
-          const match = line.match(/port=(\d+)/);
-          if (match) {
-            const detectedPort = match[1];
-            const url = `http://127.0.0.1:${detectedPort}`;
-            setBaseUrl(url);
-            setStatus("ready");
-            fetchHealth(url);
-          }
-        }
-        console.log("[Sidecar stdout]:", line);
-      });
-
-      command.stderr.on("data", (line: string) => {
-        console.log("[Sidecar stderr]:", line);
-        if (line.includes("address already in use")) {
-          setStatus("error");
-          setError("Port collision. Retrying...");
-          isSpawningRef.current = false;
-        }
-      });
-
-      command.on("close", (data: any) => {
-        console.log("[Sidecar closed]:", data);
-        setStatus((prev) => (prev !== "error" ? "idle" : prev));
-        (globalThis as Record<string, unknown>)[SIDE_PROCESS_KEY] = undefined;
-        isSpawningRef.current = false;
-      });
-
-      command.on("error", (err) => {
-        console.error("[Sidecar error]:", err);
-        setError(String(err));
-        setStatus("error");
-        isSpawningRef.current = false;
-      });
-
-      const child = await command.spawn();
-      (globalThis as Record<string, unknown>)[SIDE_PROCESS_KEY] = child;
-    } catch (err) {
-      console.error("Failed to spawn sidecar:", err);
-      setError(err instanceof Error ? err.message : String(err));
-      setStatus("error");
-      isSpawningRef.current = false;
-    }
-  }, [fetchHealth, killSidecar]);
-
-  const restart = useCallback(async () => {
-    isSpawningRef.current = false;
-    await spawnSidecar();
-  }, [spawnSidecar]);
-
-  useEffect(() => {
-    spawnSidecar();
     return () => {
-      // In development, we don't kill on unmount to keep HMR fast,
-      // but spawnSidecar() ensures only one process exists.
-      // For production, you might want to call killSidecar() here.
+      disposed = true;
+      unlisten.then((fn) => fn());
     };
-  }, [spawnSidecar]);
+  }, [apply]);
 
   return {
     status,
@@ -1,15 +1,10 @@
-import { invoke } from "@tauri-apps/api/core";
 import { useCallback } from "react";
+import { commands, type HistoryTrack, type SessionCandidate, type SessionOptions } from "../bindings";
 import { sessionRepository } from "../db/repositories/sessionRepository";
 import { findOrCreateTrack } from "../services/trackService";
 import { logger } from "../utils/logger";
 
-export interface VdjHistoryTrack {
-  artist: string;
-  title: string;
-  file_path: string;
-  timestamp: number; // Unix timestamp in seconds
-}
+export type VdjHistoryTrack = HistoryTrack;
 
 export interface DetectedSession {
   tracks: VdjHistoryTrack[];
@@ -26,9 +21,7 @@ export function useVdjHistory() {
   const detectSession = useCallback(async (): Promise<DetectedSession | null> => {
     try {
       // Read up to 200 tracks (should cover any realistic session)
-      const allTracks = await invoke<VdjHistoryTrack[]>("read_virtualdj_history_full", {
-        maxEntries: 200,
-      });
+      const allTracks = await commands.readVirtualdjHistoryFull(null, 200);
 
       if (allTracks.length === 0) {
         logger.info("VDJ History", "No tracks found in history");
@@ -141,5 +134,51 @@ export function useVdjHistory() {
     [],
   );
 
-  return { detectSession, importTracks };
+  /**
+   * Past sessions rebuilt from the whole VDJ History folder, newest first.
+   * Sessions that already have plays in the Logbook are left out.
+   */
+  const findPastSessions = useCallback(
+    async (options?: SessionOptions): Promise<SessionCandidate[]> => {
+      const candidates = await commands.findHistorySessions(null, options);
+      const fresh: SessionCandidate[] = [];
+      for (const candidate of candidates) {
+        const existing = await sessionRepository.getSessionsInTimeRange(
+          candidate.started_at,
+          candidate.ended_at,
+        );
+        if (existing.length === 0) fresh.push(candidate);
+      }
+      logger.info(
+        "VDJ History",
+        `Found ${candidates.length} past sessions (${fresh.length} not in Logbook)`,
+      );
+      return fresh;
+    },
+    [],
+  );
+
+  /**
+   * Insert a rebuilt session into the Logbook with its original start/end times
+   */
+  const importPastSession = useCallback(
+    async (candidate: SessionCandidate, name?: string): Promise<number> => {
+      const startedAt = new Date(candidate.started_at * 1000);
+      const session = await sessionRepository.createSession(
+        name ?? `Session ${startedAt.toLocaleDateString()}`,
+      );
+      // Backdate before importing so the session is never left looking live
+      await sessionRepository.setSessionTimes(session.id, candidate.started_at, candidate.ended_at);
+      try {
+        await importTracks(candidate.tracks, session.id);
+      } catch (error) {
+        await sessionRepository.deleteSession(session.id);
+        throw error;
+      }
+      return session.id;
+    },
+    [importTracks],
+  );
+
+  return { detectSession, importTracks, findPastSessions, importPastSession };
 }
@@ -0,0 +1,76 @@
+import { describe, it, expect, vi, beforeEach } from "vitest";
+import { commands } from "../../bindings";
+import { trackRepository } from "../../db/repositories/trackRepository";
+import { logger } from "../../utils/logger";
+import { findOrCreateTrack } from "../trackService";
+
+vi.mock("../../bindings", () => ({
+  commands: {
+    lookupVdjTrackMetadata: vi.fn(),
+    readAudioTags: vi.fn(),
+  },
+}));
+
+vi.mock("../../db/repositories/trackRepository", () => ({
+  trackRepository: {
+    findByTrackKey: vi.fn(),
+    insertTrack: vi.fn(),
+  },
+}));
+
+vi.mock("../../utils/logger", () => ({
+  logger: { debug: vi.fn(), warn: vi.fn() },
+}));
+
+describe("findOrCreateTrack metadata lookup", () => {
+  beforeEach(() => {
+    vi.clearAllMocks();
+    (trackRepository.findByTrackKey as any).mockResolvedValue(null);
+    (trackRepository.insertTrack as any).mockResolvedValue(1);
+    (commands.readAudioTags as any).mockResolvedValue({ bpm: 124, key: "8A", volume: null });
+  });
+
+  it("falls back to the file's tags when the VDJ database can't be read", async () => {
+    (commands.lookupVdjTrackMetadata as any).mockRejectedValue({
+      code: "xml_parse",
+      message: "Malformed database.xml",
+      path: "/vdj/database.xml",
+      line: 3,
+      column: 7,
+    });
+
+    const track = await findOrCreateTrack("Artist", "Title", "/music/a.mp3");
+
+    expect(track.bpm).toBe(124);
+    expect(track.key).toBe("8A");
+    expect(logger.warn).toHaveBeenCalledWith(
+      "Live",
+      "VDJ metadata lookup failed",
+      expect.objectContaining({ code: "xml_parse", path: "/vdj/database.xml" }),
+    );
+  });
+
+  it("falls back quietly when there is no VDJ database", async () => {
+    (commands.lookupVdjTrackMetadata as any).mockRejectedValue({
+      code: "not_found",
+      message: "No database",
+    });
+
+    const track = await findOrCreateTrack("Artist", "Title", "/music/a.mp3");
+
+    expect(track.bpm).toBe(124);
+    expect(logger.warn).not.toHaveBeenCalled();
+  });
+
+  it("doesn't read the file after a cancelled lookup", async () => {
+    (commands.lookupVdjTrackMetadata as any).mockRejectedValue({
+      code: "cancelled",
+      message: "Cancelled",
+    });
+
+    const track = await findOrCreateTrack("Artist", "Title", "/music/a.mp3");
+
+    expect(commands.readAudioTags).not.toHaveBeenCalled();
+    expect(track.bpm).toBeNull();
+  });
+});
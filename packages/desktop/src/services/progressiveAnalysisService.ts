@@ -2,38 +2,61 @@
  * Progressive Analysis Service
  * Analyzes tracks in the background as they're played.
  *
+ * The queue itself lives in Rust (analysis_queue.rs): it orders jobs by priority,
+ * dispatches them to the sidecar, persists pending jobs across restarts and
+ * emits progress/completion events. This service enqueues tracks and stores
+ * results as they complete. Rust keeps each result until it is acknowledged
+ * here, so results that finished while the window was closed are replayed on
+ * startup.
+ *
  * This is a singleton service (not a hook) that can be called from
  * useLiveSession without React context requirements.
  */
 
-import { fetch } from "@tauri-apps/plugin-http";
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import { type AnalysisResult, trackRepository } from "../db/repositories/trackRepository";
 import { settingsRepository } from "../db/repositories/settingsRepository";
 import { sendMessage } from "../hooks/live";
 import { getSessionId as getStoreSessionId } from "../hooks/live/stateHelpers";
 import { MESSAGE_TYPES } from "@pika/shared";
 
-interface AnalysisJob {
-  trackId: number;
-  filePath: string;
+export type AnalysisPriority = "now_playing" | "next_up" | "background";
+export type CpuPriority = "low" | "normal" | "high";
+
+export interface AnalysisJob {
+  id: number;
+  track_id: number;
+  file_path: string;
+  priority: AnalysisPriority;
+  attempts: number;
 }
 
-// Singleton state
-let queue: AnalysisJob[] = [];
-let isProcessing = false;
-let sidecarBaseUrl: string | null = null;
-let processingTimeout: ReturnType<typeof setTimeout> | null = null;
+export interface AnalysisJobProgress {
+  job: AnalysisJob;
+  state: "queued" | "running" | "retrying" | "cancelled";
+  pending: number;
+  running: number;
+}
 
-// Delay is now dynamic based on CPU priority setting (see scheduleNextProcess)
+export interface AnalysisJobCompletion {
+  job: AnalysisJob;
+  state: "completed" | "failed";
+  result: AnalysisResult | null;
+  error: string | null;
+}
 
-/**
- * Set the sidecar base URL (called from App when sidecar is ready)
- */
-export function setSidecarUrl(url: string | null) {
-  sidecarBaseUrl = url;
-  console.log("[ProgressiveAnalysis] Sidecar URL set:", url);
+export interface AnalysisQueueSnapshot {
+  paused: boolean;
+  cpu_priority: CpuPriority;
+  concurrency: number;
+  running: AnalysisJob[];
+  pending: AnalysisJob[];
 }
 
+export const ANALYSIS_PROGRESS_EVENT = "analysis://progress";
+export const ANALYSIS_COMPLETE_EVENT = "analysis://complete";
+
 /**
  * Enqueue a track for progressive analysis
  * Respects the analysis.onTheFly setting
@@ -41,184 +64,152 @@ export function setSidecarUrl(url: string | null) {
 export async function enqueueForAnalysis(
   trackId: number,
   filePath: string,
-  waitForCompletion = false,
-) {
+  priority: AnalysisPriority = "now_playing",
+): Promise<AnalysisJob | null> {
   // Check if progressive analysis is enabled
   const enabled = await settingsRepository.get("analysis.onTheFly");
   if (!enabled) {
     console.log("[ProgressiveAnalysis] On-the-fly analysis disabled");
-    return;
-  }
-
-  if (!sidecarBaseUrl) {
-    console.log("[ProgressiveAnalysis] Sidecar not ready");
-    return;
-  }
-
-  // Check if already in queue
-  if (queue.some((job) => job.trackId === trackId)) {
-    console.log("[ProgressiveAnalysis] Track already in queue:", trackId);
-    return;
+    return null;
   }
 
   // Check if already analyzed
   const track = await trackRepository.getTrackById(trackId);
   if (track?.analyzed) {
     console.log("[ProgressiveAnalysis] Track already analyzed:", trackId);
-    return;
+    return null;
   }
 
-  console.log("[ProgressiveAnalysis] Enqueueing track:", trackId);
-  queue.push({ trackId, filePath });
+  console.log("[ProgressiveAnalysis] Enqueueing track:", trackId, priority);
+  return invoke<AnalysisJob>("enqueue_analysis_job", { trackId, filePath, priority });
+}
 
-  // Start processing if not already running
-  if (!isProcessing && !processingTimeout) {
-    const promise = processQueue();
-    if (waitForCompletion) return promise;
-  }
+/**
+ * Store a finished job's result, then acknowledge it so Rust stops keeping it.
+ * A result that fails to store stays unacknowledged and is retried on the next start.
+ */
+export async function handleAnalysisComplete(completion: AnalysisJobCompletion) {
+  await storeAnalysisResult(completion);
+  await invoke<number>("ack_analysis_results", { jobIds: [completion.job.id] });
 }
 
 /**
- * Process the analysis queue
+ * Store a finished job's result and broadcast the improved metadata
  */
-async function processQueue() {
-  if (isProcessing || queue.length === 0 || !sidecarBaseUrl) {
+async function storeAnalysisResult(completion: AnalysisJobCompletion) {
+  const { job, result } = completion;
+
+  if (completion.state === "failed" && !result) {
+    // Sidecar unreachable or HTTP error - leave unanalyzed so it can be retried later
+    console.error("[ProgressiveAnalysis] Job failed:", job.file_path, completion.error);
     return;
   }
 
-  isProcessing = true;
-
-  const job = queue.shift();
-  if (!job) {
-    isProcessing = false;
+  // Double-check track isn't analyzed (could have changed while queued)
+  const current = await trackRepository.getTrackById(job.track_id);
+  if (current?.analyzed) {
+    console.log("[ProgressiveAnalysis] Track already analyzed, skipping");
     return;
   }
-  console.log("[ProgressiveAnalysis] Processing:", job.filePath);
-
-  try {
-    // Double-check track isn't analyzed (could have changed while queued)
-    const track = await trackRepository.getTrackById(job.trackId);
-    if (track?.analyzed) {
-      console.log("[ProgressiveAnalysis] Track already analyzed, skipping");
-      isProcessing = false;
-      scheduleNextProcess();
-      return;
-    }
-
-    // Call sidecar for analysis
-    const url = `${sidecarBaseUrl}/analyze?path=${encodeURIComponent(job.filePath)}`;
-    const response = await fetch(url, { method: "GET" });
-
-    if (response.ok) {
-      const result: AnalysisResult = await response.json();
-      if (result.error) {
-        console.error("[ProgressiveAnalysis] Analysis error:", result.error);
-        await trackRepository.markTrackAnalyzed(job.trackId, null);
-      } else {
-        console.log("[ProgressiveAnalysis] Complete:", result.bpm, result.key);
-        await trackRepository.markTrackAnalyzed(job.trackId, result);
-
-        // U4 Fix: Immediately broadcast improved data to listeners
-        // This solves the issue where Web App is stuck with "null" BPM until next track
-        const sessionId = getStoreSessionId();
-        if (sessionId) {
-          const track = await trackRepository.getTrackById(job.trackId);
-          if (track) {
-            console.log("[ProgressiveAnalysis] Broadcasting updated metadata");
-            // 🛡️ Issue 49 Fix: Use METADATA_UPDATED to bypass rate limits on server
-            // and avoid resetting like counters.
-            console.log("[TestDebug] About to sendMessage METADATA_UPDATED");
-            try {
-              sendMessage({
-                type: MESSAGE_TYPES.METADATA_UPDATED,
-                sessionId,
-                track: {
-                  artist: track.artist ?? "",
-                  title: track.title ?? "",
-                  bpm: track.bpm ?? undefined,
-                  key: track.key ?? undefined,
-                  energy: track.energy ?? undefined,
-                  danceability: track.danceability ?? undefined,
-                  brightness: track.brightness ?? undefined,
-                  acousticness: track.acousticness ?? undefined,
-                  groove: track.groove ?? undefined,
-                },
-              });
-              console.log("[TestDebug] sendMessage called");
-            } catch (err) {
-              console.error("[TestDebug] sendMessage failed", err);
-            }
-          } else {
-            console.log("[TestDebug] Track not found for broadcast");
-          }
-        } else {
-          console.log("[TestDebug] No sessionId");
-        }
-      }
-    } else {
-      console.log("[ProgressiveAnalysis] HTTP error:", response.status);
-      await trackRepository.markTrackAnalyzed(job.trackId, null);
-    }
-  } catch (e) {
-    console.error("[ProgressiveAnalysis] Error:", e);
-    // Don't mark as analyzed on network error - can retry later
+
+  if (!result || result.error) {
+    console.error("[ProgressiveAnalysis] Analysis error:", completion.error);
+    await trackRepository.markTrackAnalyzed(job.track_id, null);
+    return;
   }
 
-  isProcessing = false;
-  scheduleNextProcess();
+  console.log("[ProgressiveAnalysis] Complete:", result.bpm, result.key);
+  await trackRepository.markTrackAnalyzed(job.track_id, result);
+
+  // U4 Fix: Immediately broadcast improved data to listeners
+  // This solves the issue where Web App is stuck with "null" BPM until next track
+  const sessionId = getStoreSessionId();
+  if (!sessionId) return;
+
+  const track = await trackRepository.getTrackById(job.track_id);
+  if (!track) return;
+
+  console.log("[ProgressiveAnalysis] Broadcasting updated metadata");
+  // 🛡️ Issue 49 Fix: Use METADATA_UPDATED to bypass rate limits on server
+  // and avoid resetting like counters.
+  sendMessage({
+    type: MESSAGE_TYPES.METADATA_UPDATED,
+    sessionId,
+    track: {
+      artist: track.artist ?? "",
+      title: track.title ?? "",
+      bpm: track.bpm ?? undefined,
+      key: track.key ?? undefined,
+      energy: track.energy ?? undefined,
+      danceability: track.danceability ?? undefined,
+      brightness: track.brightness ?? undefined,
+      acousticness: track.acousticness ?? undefined,
+      groove: track.groove ?? undefined,
+    },
+  });
 }
 
 /**
- * Schedule the next queue processing with delay based on CPU priority
+ * Listen for completed jobs, then pick up results that finished while no
+ * listener was running (previous run, window reload). Returns the unlisten function.
  */
-async function scheduleNextProcess() {
-  if (queue.length === 0) {
-    return;
-  }
+export async function startAnalysisListener(): Promise<UnlistenFn> {
+  const handle = (completion: AnalysisJobCompletion) =>
+    handleAnalysisComplete(completion).catch((e) =>
+      console.error("[ProgressiveAnalysis] Error:", e),
+    );
+
+  const unlisten = await listen<AnalysisJobCompletion>(ANALYSIS_COMPLETE_EVENT, (event) => {
+    handle(event.payload);
+  });
+
+  replayUnacknowledgedResults(handle).catch((e) =>
+    console.error("[ProgressiveAnalysis] Failed to load unacknowledged results:", e),
+  );
+  return unlisten;
+}
 
-  // Clear any existing timeout
-  if (processingTimeout) {
-    clearTimeout(processingTimeout);
+async function replayUnacknowledgedResults(handle: (completion: AnalysisJobCompletion) => Promise<void>) {
+  const pending = await invoke<AnalysisJobCompletion[]>("get_analysis_results");
+  if (pending.length > 0) {
+    console.log("[ProgressiveAnalysis] Storing", pending.length, "unacknowledged results");
+  }
+  for (const completion of pending) {
+    await handle(completion);
   }
+}
 
-  // Get delay based on CPU priority setting
-  const priority = await settingsRepository.get("analysis.cpuPriority");
-  const delay = priority === "high" ? 0 : priority === "low" ? 3000 : 2000;
+/**
+ * Apply the analysis.cpuPriority setting to the queue's concurrency
+ */
+export function setAnalysisCpuPriority(priority: CpuPriority) {
+  return invoke<AnalysisQueueSnapshot>("set_analysis_cpu_priority", { priority });
+}
 
-  // Schedule next processing with delay
-  processingTimeout = setTimeout(() => {
-    processingTimeout = null;
-    processQueue();
-  }, delay);
+/**
+ * Pause or resume the queue (running jobs finish)
+ */
+export function setAnalysisPaused(paused: boolean) {
+  return invoke<AnalysisQueueSnapshot>("set_analysis_paused", { paused });
 }
 
 /**
- * Get current queue length (for debugging)
+ * Cancel a pending or running job
  */
-export function getQueueLength(): number {
-  return queue.length;
+export function cancelAnalysisJob(jobId: number) {
+  return invoke<boolean>("cancel_analysis_job", { jobId });
 }
 
 /**
- * Clear the queue (for cleanup)
+ * Get pending and running jobs
  */
-export function clearQueue() {
-  queue = [];
-  if (processingTimeout) {
-    clearTimeout(processingTimeout);
-    processingTimeout = null;
-  }
+export function getAnalysisQueue() {
+  return invoke<AnalysisQueueSnapshot>("get_analysis_queue");
 }
 
 /**
- * Reset internal state (For testing only)
+ * Clear the queue (for cleanup)
  */
-export function resetServiceState() {
-  queue = [];
-  isProcessing = false;
-  sidecarBaseUrl = null;
-  if (processingTimeout) {
-    clearTimeout(processingTimeout);
-    processingTimeout = null;
-  }
+export function clearQueue() {
+  return invoke<AnalysisQueueSnapshot>("clear_analysis_queue");
 }
@@ -1,7 +1,8 @@
 import { getTrackKey } from "@pika/shared";
-import { invoke } from "@tauri-apps/api/core";
+import { commands, type VdjTrackMetadata } from "../bindings";
 import { trackRepository } from "../db/repositories/trackRepository";
 import { logger } from "../utils/logger";
+import { formatPikaError, isPikaError } from "../utils/pikaError";
 
 const GHOST_FILE_PREFIX = "ghost://";
 
@@ -16,10 +17,45 @@ export interface DbTrackInfo {
   groove: number | null;
 }
 
-interface VdjTrackMetadata {
-  bpm: number | null;
-  key: string | null;
-  volume: number | null;
+type TrackMetadata = Pick<VdjTrackMetadata, "bpm" | "key" | "volume">;
+
+/**
+ * BPM/key for a file: VDJ's database first, then the file's own tags
+ * (ID3 TBPM/TKEY, Vorbis comments, MP4 atoms) for tracks VDJ doesn't know.
+ */
+async function lookupTrackMetadata(filePath: string): Promise<TrackMetadata | null> {
+  let vdjMeta: TrackMetadata | null = null;
+  try {
+    vdjMeta = await commands.lookupVdjTrackMetadata(filePath, null, null);
+  } catch (error) {
+    // A cancelled lookup means the caller gave up; don't go on to read the file
+    if (isPikaError(error) && error.code === "cancelled") throw error;
+    // No VDJ database is expected for non-VDJ users; a broken one shouldn't
+    // stop the file's own tags from being used
+    if (!isPikaError(error) || error.code !== "not_found") {
+      logger.warn("Live", "VDJ metadata lookup failed", {
+        filePath,
+        error: formatPikaError(error),
+        code: isPikaError(error) ? error.code : undefined,
+        path: isPikaError(error) && "path" in error ? error.path : undefined,
+      });
+    }
+  }
+  if (vdjMeta?.bpm) return vdjMeta;
+
+  try {
+    const tags = await commands.readAudioTags(filePath);
+    if (tags.bpm || tags.key) {
+      return {
+        bpm: vdjMeta?.bpm ?? tags.bpm,
+        key: vdjMeta?.key ?? tags.key,
+        volume: vdjMeta?.volume ?? tags.volume,
+      };
+    }
+  } catch (error) {
+    logger.debug("Live", "No readable tags", { filePath, error });
+  }
+  return vdjMeta;
 }
 
 /**
@@ -42,9 +78,7 @@ export async function findOrCreateTrack(
     // Self-healing: If BPM is missing, try to fetch it from VDJ
     if (!existing.bpm && filePath && !filePath.startsWith(GHOST_FILE_PREFIX)) {
       try {
-        const vdjMeta = await invoke<VdjTrackMetadata | null>("lookup_vdj_track_metadata", {
-          filePath,
-        });
+        const vdjMeta = await lookupTrackMetadata(filePath);
         if (vdjMeta?.bpm) {
           logger.debug("Live", "Healing track metadata", {
             id: existing.id,
@@ -95,12 +129,10 @@ export async function findOrCreateTrack(
 
   if (filePath && !filePath.startsWith(GHOST_FILE_PREFIX)) {
     try {
-      const vdjMeta = await invoke<VdjTrackMetadata | null>("lookup_vdj_track_metadata", {
-        filePath,
-      });
+      const vdjMeta = await lookupTrackMetadata(filePath);
       if (vdjMeta) {
-        vdjBpm = vdjMeta.bpm;
-        vdjKey = vdjMeta.key;
+        vdjBpm = vdjMeta.bpm ?? null;
+        vdjKey = vdjMeta.key ?? null;
         logger.debug("Live", "Got VDJ metadata", { bpm: vdjBpm, key: vdjKey });
       }
     } catch (error) {
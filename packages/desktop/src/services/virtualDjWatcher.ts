@@ -1,15 +1,16 @@
 import type { TrackInfo } from "@pika/shared";
-import { invoke } from "@tauri-apps/api/core";
+import { commands } from "../bindings";
 import { settingsRepository } from "../db/repositories/settingsRepository";
+import { isPikaError } from "../utils/pikaError";
 
 const IPC_TIMEOUT_MS = 5000;
 
 // 🛡️ Issue 27 Fix: Timeout wrapper for IPC calls
-async function invokeWithTimeout<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
+async function withTimeout<T>(cmd: string, call: Promise<T>): Promise<T> {
   const timeout = new Promise<never>((_, reject) =>
     setTimeout(() => reject(new Error(`IPC Timeout: ${cmd}`)), IPC_TIMEOUT_MS),
   );
-  return Promise.race([invoke<T>(cmd, args), timeout]);
+  return Promise.race([call, timeout]);
 }
 
 /**
@@ -45,21 +46,6 @@ export function toTrackInfo(track: NowPlayingTrack): TrackInfo {
   };
 }
 
-interface HistoryTrack {
-  artist: string;
-  title: string;
-  file_path: string;
-  timestamp: number;
-  // Optional fields that might come from improved Rust backend
-  bpm?: number;
-  key?: string;
-  energy?: number;
-  danceability?: number;
-  brightness?: number;
-  acousticness?: number;
-  groove?: number;
-}
-
 type TrackChangeCallback = (track: NowPlayingTrack) => void;
 
 /**
@@ -81,57 +67,50 @@ class VirtualDJWatcher {
       // Get custom VDJ path from settings (may be "auto" or a file path)
       const customPath = await settingsRepository.get("library.vdjPath");
 
-      const result = await invokeWithTimeout<HistoryTrack | null>("read_virtualdj_history", {
-        customPath: customPath,
-      });
+      const result = await withTimeout(
+        "read_virtualdj_history",
+        commands.readVirtualdjHistory(customPath),
+      );
 
       if (!result) {
         return null;
       }
 
-      // If BPM is missing (common with history logs), fetch from VDJ database/sidecar
-      let bpm = result.bpm;
-      let key = result.key;
+      // History logs carry no BPM/key; fetch them from the VDJ database
+      let bpm: number | undefined;
+      let key: string | undefined;
 
-      if ((!bpm || !key) && result.file_path && !result.file_path.startsWith("unknown")) {
+      if (result.file_path && !result.file_path.startsWith("unknown")) {
         try {
-          const metadata = await invokeWithTimeout<{
-            bpm: number | null;
-            key: string | null;
-            energy: number | null;
-          } | null>("lookup_vdj_track_metadata", {
-            filePath: result.file_path,
-          });
+          const metadata = await withTimeout(
+            "lookup_vdj_track_metadata",
+            commands.lookupVdjTrackMetadata(result.file_path, null, null),
+          );
 
           if (metadata) {
-            bpm = metadata.bpm ?? bpm;
-            key = metadata.key ?? key;
-            // Only update energy if provided
-            // energy = metadata.energy ?? energy;
+            bpm = metadata.bpm ?? undefined;
+            key = metadata.key ?? undefined;
           }
         } catch (e) {
           console.warn("[VDJ Watcher] Metadata lookup failed:", e);
         }
       }
 
+      // Fingerprint fields are filled in later by analysis
       const track: NowPlayingTrack = {
         artist: result.artist,
         title: result.title,
-        bpm: bpm ? Number.parseFloat(String(bpm)) : undefined,
-        key: key,
+        bpm,
+        key,
         filePath: result.file_path,
         timestamp: new Date(result.timestamp * 1000),
         rawTimestamp: result.timestamp,
-        // Fingerprint fields
-        energy: result.energy,
-        danceability: result.danceability,
-        brightness: result.brightness,
-        acousticness: result.acousticness,
-        groove: result.groove,
       };
 
       return track;
     } catch (e) {
+      // No history yet (VDJ not installed or nothing played today) is not an error
+      if (isPikaError(e) && e.code === "not_found") return null;
       console.error("[VDJ Watcher] Failed to read history:", e);
       return null;
     }
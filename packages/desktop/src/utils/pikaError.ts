@@ -0,0 +1,32 @@
+/**
+ * Structured errors rejected by Tauri commands (Rust `PikaError`, error.rs).
+ * Every command rejects with this shape; the type comes from the generated bindings.
+ */
+import type { PikaError } from "../bindings";
+
+export type { PikaError };
+export type PikaErrorCode = PikaError["code"];
+
+export function isPikaError(err: unknown): err is PikaError {
+  return (
+    typeof err === "object" &&
+    err !== null &&
+    typeof (err as PikaError).code === "string" &&
+    typeof (err as PikaError).message === "string"
+  );
+}
+
+/**
+ * Human-readable message for a rejected invoke (or any other thrown value)
+ */
+export function formatPikaError(err: unknown): string {
+  if (!isPikaError(err)) {
+    return err instanceof Error ? err.message : String(err);
+  }
+  if (err.code === "xml_parse" && err.path) {
+    return err.line
+      ? `${err.message} (${err.path}, line ${err.line}, column ${err.column})`
+      : `${err.message} (${err.path})`;
+  }
+  return err.message;
+}